@@ -1,26 +1,47 @@
-use super::{defines, utils, Config};
+use super::{defines, layers, utils, Config};
 use crate::out;
 
-/// Loads the config file.
+/// Loads the config file, and applies the repository, environment and command line overrides.
 /// If the config file doesn't exist or is invalid, it will create a new one.
 ///
 /// ### Returns
 /// A Config struct.
 pub fn load_config() -> Config {
-    if utils::config_exists() {
-        if utils::validate_config_file() {
-            return utils::read_config();
+    if !utils::config_exists() {
+        out::print_error("Config file not found. Creating one...\n");
+        create_config();
+    } else {
+        utils::upgrade_config_file();
+
+        if !utils::validate_config_file() {
+            out::print_error("Config file is invalid. Creating a new one...\n");
+            backup_invalid_config();
+            create_config();
         }
-        out::print_error("Config file is invalid. Creating a new one...\n");
-        return create_config();
     }
 
-    out::print_error("Config file not found. Creating one...\n");
-    create_config()
+    match layers::resolve() {
+        Ok(layered) => layered.config,
+        Err(err) => {
+            out::print_error(&format!("Invalid setting: {}\n", err));
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Keeps a copy of an invalid config file before it's replaced, so the settings can be recovered.
+pub fn backup_invalid_config() {
+    match utils::backup_config_file("invalid") {
+        Ok(path) => out::print_error(&format!("The old config file was saved to {}\n", path)),
+        Err(err) => out::print_error(&format!("Failed to back up the config file: {}\n", err)),
+    }
 }
 
 pub fn create_config() -> Config {
-    utils::handle_config_folder();
+    if let Err(err) = utils::handle_config_folder() {
+        out::print_error(&format!("{}\n", err));
+        std::process::exit(1);
+    }
 
     let username = ask_username();
     let sort = ask_sort();
@@ -35,6 +56,7 @@ pub fn create_config() -> Config {
         protocol,
         color,
         fancy,
+        ..Default::default()
     };
 
     utils::save_config_file(config.clone());
@@ -6,12 +6,20 @@ pub enum SORTING {
     Alphabetical,
 }
 
-#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum PROTOCOL {
     SSH,
     HTTPS,
 }
 
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CHANNEL {
+    STABLE,
+    /// The stable releases and the pre-releases (ex. `0.2.0-beta.1`)
+    BETA,
+}
+
 #[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum COLOR {
     NORMAL,
@@ -59,3 +67,129 @@ impl std::fmt::Display for COLOR {
         write!(f, "{}{}\x1B[m", color, self.to_string())
     }
 }
+
+impl SORTING {
+    pub const VALUES: [&'static str; 2] = ["last-updated", "alphabetical"];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SORTING::LastUpdated => "last-updated",
+            SORTING::Alphabetical => "alphabetical",
+        }
+    }
+}
+
+impl std::str::FromStr for SORTING {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match normalize(value).as_str() {
+            "lastupdated" => Ok(SORTING::LastUpdated),
+            "alphabetical" => Ok(SORTING::Alphabetical),
+            _ => Err(invalid_value(value, &SORTING::VALUES)),
+        }
+    }
+}
+
+impl PROTOCOL {
+    pub const VALUES: [&'static str; 2] = ["ssh", "https"];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PROTOCOL::SSH => "ssh",
+            PROTOCOL::HTTPS => "https",
+        }
+    }
+}
+
+impl std::str::FromStr for PROTOCOL {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match normalize(value).as_str() {
+            "ssh" => Ok(PROTOCOL::SSH),
+            "https" => Ok(PROTOCOL::HTTPS),
+            _ => Err(invalid_value(value, &PROTOCOL::VALUES)),
+        }
+    }
+}
+
+impl COLOR {
+    pub const VALUES: [&'static str; 9] = [
+        "normal", "red", "green", "yellow", "blue", "magenta", "cyan", "white", "gray",
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            COLOR::NORMAL => "normal",
+            COLOR::RED => "red",
+            COLOR::GREEN => "green",
+            COLOR::YELLOW => "yellow",
+            COLOR::BLUE => "blue",
+            COLOR::MAGENTA => "magenta",
+            COLOR::CYAN => "cyan",
+            COLOR::WHITE => "white",
+            COLOR::GRAY => "gray",
+        }
+    }
+}
+
+impl std::str::FromStr for COLOR {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match normalize(value).as_str() {
+            "normal" => Ok(COLOR::NORMAL),
+            "red" => Ok(COLOR::RED),
+            "green" => Ok(COLOR::GREEN),
+            "yellow" => Ok(COLOR::YELLOW),
+            "blue" => Ok(COLOR::BLUE),
+            "magenta" => Ok(COLOR::MAGENTA),
+            "cyan" => Ok(COLOR::CYAN),
+            "white" => Ok(COLOR::WHITE),
+            "gray" | "grey" => Ok(COLOR::GRAY),
+            _ => Err(invalid_value(value, &COLOR::VALUES)),
+        }
+    }
+}
+
+impl CHANNEL {
+    pub const VALUES: [&'static str; 2] = ["stable", "beta"];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CHANNEL::STABLE => "stable",
+            CHANNEL::BETA => "beta",
+        }
+    }
+}
+
+impl std::str::FromStr for CHANNEL {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match normalize(value).as_str() {
+            "stable" => Ok(CHANNEL::STABLE),
+            "beta" => Ok(CHANNEL::BETA),
+            _ => Err(invalid_value(value, &CHANNEL::VALUES)),
+        }
+    }
+}
+
+/// Lowercases the value and removes separators, so `last-updated`, `last_updated` and `LastUpdated` all match.
+fn normalize(value: &str) -> String {
+    value
+        .trim()
+        .chars()
+        .filter(|c| *c != '-' && *c != '_')
+        .collect::<String>()
+        .to_lowercase()
+}
+
+fn invalid_value(value: &str, allowed: &[&str]) -> String {
+    format!(
+        "Invalid value `{}`, expected one of: {}",
+        value,
+        allowed.join(", ")
+    )
+}
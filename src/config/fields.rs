@@ -0,0 +1,318 @@
+use super::profiles::{Profile, PROFILE_KEYS};
+use super::Config;
+use crate::view::theme::Role;
+
+/// The keys of the settings which can be read and changed with `tgh config`.
+pub const KEYS: [&str; 11] = [
+    "username",
+    "token",
+    "sort",
+    "protocol",
+    "color",
+    "fancy",
+    "host",
+    "ca_bundle",
+    "client_id",
+    "update_check",
+    "update_channel",
+];
+
+/// The settings without a usable default, they can only be changed.
+const REQUIRED_KEYS: [&str; 1] = ["username"];
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            version: super::migrations::CURRENT_VERSION,
+            username: "".into(),
+            token: "".into(),
+            sort: super::defines::SORTING::LastUpdated,
+            protocol: super::defines::PROTOCOL::HTTPS,
+            color: super::defines::COLOR::NORMAL,
+            fancy: true,
+            host: super::github::DEFAULT_HOST.into(),
+            ca_bundle: "".into(),
+            client_id: "".into(),
+            profiles: Default::default(),
+            theme: Default::default(),
+            update_check: true,
+            update_channel: super::defines::CHANNEL::STABLE,
+        }
+    }
+}
+
+impl Config {
+    pub fn get(&self, key: &str) -> Result<String, String> {
+        if let Some((name, field)) = split_profile_key(key)? {
+            let profile = self
+                .profiles
+                .get(name)
+                .ok_or(format!("Unknown profile `{}`", name))?;
+
+            return Ok(profile.get(field));
+        }
+
+        if let Some(role) = split_theme_key(key)? {
+            return Ok(self.theme.get(role).cloned().unwrap_or_default());
+        }
+
+        let value = match key {
+            "username" => self.username.clone(),
+            "token" => self.token.clone(),
+            "sort" => self.sort.as_str().into(),
+            "protocol" => self.protocol.as_str().into(),
+            "color" => self.color.as_str().into(),
+            "fancy" => self.fancy.to_string(),
+            "host" => self.host.clone(),
+            "ca_bundle" => self.ca_bundle.clone(),
+            "client_id" => self.client_id.clone(),
+            "update_check" => self.update_check.to_string(),
+            "update_channel" => self.update_channel.as_str().into(),
+            _ => return Err(unknown_key(key)),
+        };
+
+        Ok(value)
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        if let Some((name, field)) = split_profile_key(key)? {
+            let mut profile = self.profiles.get(name).cloned().unwrap_or_default();
+            profile.set(field, value)?;
+            self.profiles.insert(name.to_string(), profile);
+
+            return Ok(());
+        }
+
+        if let Some(role) = split_theme_key(key)? {
+            crate::view::theme::parse_style(value)?;
+            self.theme
+                .insert(role.to_string(), value.trim().to_string());
+
+            return Ok(());
+        }
+
+        match key {
+            "username" => {
+                if value.trim().is_empty() {
+                    return Err("Username can't be empty".into());
+                }
+                self.username = value.trim().into();
+            }
+            "token" => self.token = value.trim().into(),
+            "sort" => self.sort = value.parse()?,
+            "protocol" => self.protocol = value.parse()?,
+            "color" => self.color = value.parse()?,
+            "fancy" => self.fancy = parse_bool(value)?,
+            "host" => self.host = parse_host(value)?,
+            "ca_bundle" => self.ca_bundle = value.trim().into(),
+            "client_id" => self.client_id = value.trim().into(),
+            "update_check" => self.update_check = parse_bool(value)?,
+            "update_channel" => self.update_channel = value.parse()?,
+            _ => return Err(unknown_key(key)),
+        }
+
+        Ok(())
+    }
+
+    /// Resets the setting to its default value.
+    /// `profile.<name>` removes the whole profile, `profile.<name>.<field>` clears the field.
+    pub fn reset(&mut self, key: &str) -> Result<(), String> {
+        if let Some(name) = key
+            .strip_prefix("profile.")
+            .filter(|name| !name.contains('.'))
+        {
+            return match self.profiles.remove(name) {
+                Some(_) => Ok(()),
+                None => Err(format!("Unknown profile `{}`", name)),
+            };
+        }
+
+        if let Some((name, field)) = split_profile_key(key)? {
+            let profile = self
+                .profiles
+                .get_mut(name)
+                .ok_or(format!("Unknown profile `{}`", name))?;
+
+            return profile.reset(field);
+        }
+
+        if let Some(role) = split_theme_key(key)? {
+            self.theme.remove(role);
+
+            return Ok(());
+        }
+
+        if REQUIRED_KEYS.contains(&key) {
+            return Err(format!("`{}` is required and can't be reset", key));
+        }
+
+        let value = Config::default().get(key)?;
+
+        self.set(key, &value)
+    }
+}
+
+impl Profile {
+    fn get(&self, field: &str) -> String {
+        match field {
+            "username" => self.username.clone().unwrap_or_default(),
+            "token" => self.token.clone().unwrap_or_default(),
+            "protocol" => self
+                .protocol
+                .map(|protocol| protocol.as_str().to_string())
+                .unwrap_or_default(),
+            "fancy" => self
+                .fancy
+                .map(|fancy| fancy.to_string())
+                .unwrap_or_default(),
+            "host" => self.host.clone().unwrap_or_default(),
+            "ca_bundle" => self.ca_bundle.clone().unwrap_or_default(),
+            "client_id" => self.client_id.clone().unwrap_or_default(),
+            "remotes" => self.remotes.join(","),
+            "directories" => self.directories.join(","),
+            _ => unreachable!(),
+        }
+    }
+
+    fn set(&mut self, field: &str, value: &str) -> Result<(), String> {
+        match field {
+            "username" => {
+                if value.trim().is_empty() {
+                    return Err("Username can't be empty".into());
+                }
+                self.username = Some(value.trim().into());
+            }
+            "token" => self.token = Some(value.trim().into()),
+            "protocol" => self.protocol = Some(value.parse()?),
+            "fancy" => self.fancy = Some(parse_bool(value)?),
+            "host" => self.host = Some(parse_host(value)?),
+            "ca_bundle" => self.ca_bundle = Some(value.trim().into()),
+            "client_id" => self.client_id = Some(value.trim().into()),
+            "remotes" => self.remotes = parse_list(value),
+            "directories" => self.directories = parse_list(value),
+            _ => unreachable!(),
+        }
+
+        Ok(())
+    }
+
+    fn reset(&mut self, field: &str) -> Result<(), String> {
+        match field {
+            "username" => self.username = None,
+            "token" => self.token = None,
+            "protocol" => self.protocol = None,
+            "fancy" => self.fancy = None,
+            "host" => self.host = None,
+            "ca_bundle" => self.ca_bundle = None,
+            "client_id" => self.client_id = None,
+            "remotes" => self.remotes.clear(),
+            "directories" => self.directories.clear(),
+            _ => unreachable!(),
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits a profile key (ex. `profile.work.token`) into the profile name and the field.
+/// Returns `None` for top-level keys.
+fn split_profile_key(key: &str) -> Result<Option<(&str, &str)>, String> {
+    let rest = match key.strip_prefix("profile.") {
+        Some(rest) => rest,
+        None => return Ok(None),
+    };
+
+    match rest.split_once('.') {
+        Some((name, field)) if !name.is_empty() && PROFILE_KEYS.contains(&field) => {
+            Ok(Some((name, field)))
+        }
+        _ => Err(format!(
+            "Invalid profile key `{}`, expected profile.<name>.<{}>",
+            key,
+            PROFILE_KEYS.join("|")
+        )),
+    }
+}
+
+/// Returns the role of a theme key (ex. `theme.hash`), `None` for other keys.
+fn split_theme_key(key: &str) -> Result<Option<&str>, String> {
+    let role = match key.strip_prefix("theme.") {
+        Some(role) => role,
+        None => return Ok(None),
+    };
+
+    match Role::from_name(role) {
+        Some(_) => Ok(Some(role)),
+        None => {
+            let roles: Vec<&str> = Role::ALL.iter().map(Role::name).collect();
+
+            Err(format!(
+                "Invalid theme key `{}`, expected theme.<{}>",
+                key,
+                roles.join("|")
+            ))
+        }
+    }
+}
+
+/// Parses a GitHub host, with an optional scheme (ex. `github.acme.com` or `http://localhost:8080`).
+fn parse_host(value: &str) -> Result<String, String> {
+    let host = value.trim().trim_end_matches('/');
+    let name = host.split_once("://").map(|(_, name)| name).unwrap_or(host);
+
+    if name.is_empty() || name.contains('/') || name.contains(char::is_whitespace) {
+        return Err(format!(
+            "Invalid host `{}`, expected a host name (ex. github.acme.com)",
+            value
+        ));
+    }
+
+    Ok(host.to_string())
+}
+
+/// Parses a comma separated list (ex. `acme/*, ~/work`).
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        _ => Err(format!(
+            "Invalid value `{}`, expected one of: true, false",
+            value
+        )),
+    }
+}
+
+fn unknown_key(key: &str) -> String {
+    format!(
+        "Unknown key `{}`, expected one of: {}",
+        key,
+        KEYS.join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reset() {
+        let mut config = Config::default();
+        config.set("username", "octocat").unwrap();
+        config.set("sort", "alphabetical").unwrap();
+
+        config.reset("sort").unwrap();
+        assert_eq!(config.get("sort"), Config::default().get("sort"));
+
+        // An empty username would make the config invalid
+        assert!(config.reset("username").is_err());
+        assert_eq!(config.username, "octocat");
+    }
+}
@@ -35,7 +35,7 @@ $b ` `$u `https://git-scm.com/`
 "#;
 
 const GIT_NAME_NOT_FOUND: &str = r#"
-    $b$cr `error`: Git user.name not found.
+    $@error `error`: Git user.name not found.
 
     You can set it using the following command:
     $i ` git config user.name "Your Name"`
@@ -46,7 +46,7 @@ const GIT_NAME_NOT_FOUND: &str = r#"
 "#;
 
 const GIT_EMAIL_NOT_FOUND: &str = r#"
-    $b$cr `error`: Git user.email not found.
+    $@error `error`: Git user.email not found.
 
     You can set it using the following command:
     $i ` git config user.email "`
@@ -73,7 +73,7 @@ impl GitError {
 
                 return format!(
                     r#"
-                    $b$cr `error`: $b `git` is not installed.
+                    $@error `error`: $b `git` is not installed.
                     
                     {}"#,
                     message,
@@ -89,7 +89,7 @@ impl GitError {
 
                 let msg = format!(
                     r#"
-                    $b$cr `error`: $b `git` version not supported.
+                    $@error `error`: $b `git` version not supported.
                     You need at least version {}, you are currently using version {}
 
                     {}
@@ -217,7 +217,7 @@ fn get_git_installation_instructions() -> String {
         Ok(binding) => binding,
         Err(_) => {
             return r#"
-            $b$cr `error`: $b `git` is not installed.
+            $@error `error`: $b `git` is not installed.
             
             You can download it from the official website:
             $b ` `$u `https://git-scm.com/download/linux`
@@ -248,7 +248,7 @@ fn get_git_installation_instructions() -> String {
         }
         _ => {
             return r#"
-            $b$cr `error`: $b `git` is not installed.
+            $@error `error`: $b `git` is not installed.
             
             You can download it from the official website:
             $b ` `$u `https://git-scm.com/download/linux`
@@ -259,7 +259,7 @@ fn get_git_installation_instructions() -> String {
 
     let instructions = format!(
         r#"
-        $b$cr `error`: $b `git` is not installed.
+        $@error `error`: $b `git` is not installed.
 
         You can install it using your package manager:
         $i ` {}`
@@ -1,5 +1,4 @@
 use super::utils;
-use super::Config;
 use crate::out;
 use crate::view;
 
@@ -8,7 +7,8 @@ pub fn check_token() -> bool {
         return false;
     }
 
-    let config = utils::read_config();
+    // The token may come from `TGH_TOKEN`
+    let config = super::load_config();
 
     if config.token.len() == 0 {
         return false;
@@ -17,109 +17,246 @@ pub fn check_token() -> bool {
     true
 }
 
-/// Authenticate user with GitHub.
-/// @TODO: Split this function into smaller functions.
-pub async fn authenticate() -> Result<String, reqwest::Error> {
-    use arboard::Clipboard;
+/// The GitHub host used when none is configured.
+pub const DEFAULT_HOST: &str = "github.com";
 
-    let client_id = "Iv1.d8c9cc38202b9305";
-    let client = reqwest::Client::new();
+/// The client id of the tgh OAuth app on github.com.
+const GITHUB_CLIENT_ID: &str = "Iv1.d8c9cc38202b9305";
 
-    let mut text = client
-        .post(format!(
-            "https://github.com/login/device/code?client_id={}",
-            client_id
-        ))
-        .send()
-        .await?
-        .text()
-        .await?;
-
-    text = text.replace('"', "").to_string();
-    let text_split: Vec<String> = text
-        .split("&")
-        .map(|s| s.split("=").map(|s| s.to_string()).collect::<Vec<_>>()[1].to_string())
-        .collect();
-
-    let device_code = text_split[0].to_string();
-    let expires_in = text_split[1].parse::<u64>().unwrap();
-    let interval = text_split[2].parse::<u64>().unwrap();
-    let user_code = text_split[3].to_string();
-    let login_url = text_split[4].replace("%3A", ":").replace("%2F", "/");
-    let grant_type = "urn:ietf:params:oauth:grant-type:device_code";
+/// The URLs of a GitHub (or GitHub Enterprise Server) instance.
+#[derive(Debug, PartialEq)]
+pub struct Endpoints {
+    pub web_url: String,
+    pub api_url: String,
+}
 
-    view::printer(&format!(
-        "\nPlease visit this URL to authenticate: $u `{}`\n",
-        login_url
-    ));
+impl Endpoints {
+    /// Derives the URLs from the host, ex. `github.acme.com` -> `https://github.acme.com/api/v3`.
+    /// The host may include a scheme and a port (ex. `http://localhost:8080`).
+    pub fn new(host: &str) -> Self {
+        let host = host.trim().trim_end_matches('/');
+        let web_url = match host.contains("://") {
+            true => host.to_string(),
+            false => format!("https://{}", host),
+        };
 
-    let clipboard = Clipboard::new();
-    match clipboard {
-        Ok(mut clipboard) => {
-            clipboard.set_text(user_code.clone()).unwrap();
-            println!(
-                "Your user code has been copied to your clipboard. ({})",
-                user_code
-            )
+        let api_url = match host == DEFAULT_HOST || host == "https://github.com" {
+            true => "https://api.github.com".to_string(),
+            false => format!("{}/api/v3", web_url),
+        };
+
+        Endpoints { web_url, api_url }
+    }
+
+    pub fn device_code_url(&self) -> String {
+        format!("{}/login/device/code", self.web_url)
+    }
+
+    pub fn access_token_url(&self) -> String {
+        format!("{}/login/oauth/access_token", self.web_url)
+    }
+}
+
+/// Returns the URL of the REST API for the configured host (ex. `https://api.github.com`).
+pub fn api_url() -> String {
+    api_url_for(&super::load_config())
+}
+
+/// Returns the URL of the REST API for the host of the config.
+pub fn api_url_for(config: &super::Config) -> String {
+    Endpoints::new(&config.host).api_url
+}
+
+/// Creates a client builder which trusts the configured CA bundle.
+fn client_builder(ca_bundle: &str) -> Result<reqwest::ClientBuilder, String> {
+    let mut builder = reqwest::Client::builder().user_agent("tgh");
+
+    if !ca_bundle.is_empty() {
+        let pem = std::fs::read(ca_bundle).map_err(|err| format!("{}: {}", ca_bundle, err))?;
+        let certificates = reqwest::Certificate::from_pem_bundle(&pem)
+            .map_err(|err| format!("{}: {}", ca_bundle, err))?;
+
+        if certificates.is_empty() {
+            return Err(format!("{}: No certificates found", ca_bundle));
         }
-        Err(_) => {
-            println!(
-                "Could not copy the code to the clipboard, copy the code manually: {}",
-                user_code
-            );
+
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
         }
     }
 
+    Ok(builder)
+}
+
+fn build_client(config: &super::Config, headers: reqwest::header::HeaderMap) -> reqwest::Client {
+    let builder = match client_builder(&config.ca_bundle) {
+        Ok(builder) => builder,
+        Err(err) => {
+            out::print_error(&format!("Failed to load the CA bundle: {}\n", err));
+            std::process::exit(1);
+        }
+    };
+
+    builder.default_headers(headers).build().unwrap()
+}
+
+/// Creates a client for the GitHub REST API, authenticated with the user's token.
+pub fn api_client() -> reqwest::Client {
+    api_client_for(&super::load_config())
+}
+
+/// Creates a client for the REST API of the config's host, authenticated with its token.
+pub fn api_client_for(config: &super::Config) -> reqwest::Client {
+    let mut headers = reqwest::header::HeaderMap::new();
+
+    headers.append(
+        "Authorization",
+        format!("Bearer {}", config.token).parse().unwrap(),
+    );
+    headers.append("Accept", "application/vnd.github+json".parse().unwrap());
+    headers.append("X-GitHub-Api-Version", "2022-11-28".parse().unwrap());
+
+    build_client(config, headers)
+}
+
+/// Creates a client for downloads outside of the API, without the user's token.
+pub fn download_client() -> reqwest::Client {
+    build_client(&super::load_config(), reqwest::header::HeaderMap::new())
+}
+
+#[derive(serde::Deserialize)]
+struct DeviceCode {
+    device_code: String,
+    user_code: String,
+    verification_uri: String,
+    expires_in: u64,
+    interval: u64,
+}
+
+#[derive(serde::Deserialize)]
+struct AccessToken {
+    access_token: Option<String>,
+    /// Ex. `authorization_pending` or `slow_down` while the user hasn't entered the code
+    error: Option<String>,
+}
+
+async fn request_device_code(
+    client: &reqwest::Client,
+    endpoints: &Endpoints,
+    client_id: &str,
+) -> Result<DeviceCode, reqwest::Error> {
+    client
+        .post(endpoints.device_code_url())
+        .header(reqwest::header::ACCEPT, "application/json")
+        .form(&[("client_id", client_id)])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+}
+
+async fn request_access_token(
+    client: &reqwest::Client,
+    endpoints: &Endpoints,
+    client_id: &str,
+    device_code: &str,
+) -> Result<AccessToken, reqwest::Error> {
     let params = [
         ("client_id", client_id),
-        ("device_code", &device_code),
-        ("grant_type", grant_type),
+        ("device_code", device_code),
+        ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
     ];
 
+    client
+        .post(endpoints.access_token_url())
+        .header(reqwest::header::ACCEPT, "application/json")
+        .form(&params)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+}
+
+/// Authenticate user with GitHub, using the device flow.
+pub async fn authenticate() -> Result<String, String> {
+    authenticate_with(&super::load_config(), true).await
+}
+
+/// Copies the text to the clipboard, returns `false` if there's no clipboard or it can't be written.
+fn copy_to_clipboard(text: &str) -> bool {
+    match arboard::Clipboard::new() {
+        Ok(mut clipboard) => clipboard.set_text(text.to_string()).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Authenticates with the host of the config.
+/// With `copy_code`, the user code is also copied to the clipboard (the tests leave it alone).
+async fn authenticate_with(config: &super::Config, copy_code: bool) -> Result<String, String> {
+    let endpoints = Endpoints::new(&config.host);
+
+    // The tgh OAuth app only exists on github.com, GHES needs its own app
+    let client_id = match (config.client_id.as_str(), endpoints.api_url.as_str()) {
+        ("", "https://api.github.com") => GITHUB_CLIENT_ID.to_string(),
+        ("", _) => {
+            return Err(format!(
+                "Set `client_id` to the client id of an OAuth app on {} (tgh config set client_id <id>)",
+                config.host
+            ))
+        }
+        (client_id, _) => client_id.to_string(),
+    };
+
+    let client = build_client(config, reqwest::header::HeaderMap::new());
+    let device = request_device_code(&client, &endpoints, &client_id)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    view::printer(&format!(
+        "\nPlease visit this URL to authenticate: $u `{}`\n",
+        device.verification_uri
+    ));
+
+    match copy_code && copy_to_clipboard(&device.user_code) {
+        true => println!(
+            "Your user code has been copied to your clipboard. ({})",
+            device.user_code
+        ),
+        false => println!(
+            "Could not copy the code to the clipboard, copy the code manually: {}",
+            device.user_code
+        ),
+    }
+
     let start_time = std::time::Instant::now();
-    let token;
+    let mut interval = device.interval;
 
     loop {
-        let res = client
-            .post("https://github.com/login/oauth/access_token")
-            .header(reqwest::header::ACCEPT, "application/json")
-            .form(&params)
-            .send()
-            .await?;
-
-        let mut res = res.text().await?;
+        // Wait for the interval
+        tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
 
-        if res.contains("access_token") {
-            res = res
-                .replace("{", "")
-                .replace("}", "")
-                .replace('"', "")
-                .replace("/", "");
-            let res_split: Vec<String> = res
-                .split(",")
-                .map(|s| s.split(":").map(|s| s.to_string()).collect::<Vec<_>>()[1].to_string())
-                .collect();
+        let response = request_access_token(&client, &endpoints, &client_id, &device.device_code)
+            .await
+            .map_err(|err| err.to_string())?;
 
-            token = res_split[0].to_string();
+        if let Some(token) = response.access_token {
+            return Ok(token);
+        }
 
-            break;
+        match response.error.as_deref() {
+            Some("authorization_pending") | None => {}
+            Some("slow_down") => interval += 5,
+            Some(error) => return Err(error.to_string()),
         }
 
         // Check if the authentication timed out
-        if std::time::Instant::now()
-            .duration_since(start_time)
-            .as_secs()
-            > (expires_in)
-        {
+        if start_time.elapsed().as_secs() > device.expires_in {
             println!("Authentication timed out.");
             std::process::exit(1);
         }
-
-        // Wait for the interval
-        std::thread::sleep(std::time::Duration::from_secs(interval));
     }
-
-    return Ok(token);
 }
 
 pub async fn login() {
@@ -132,24 +269,239 @@ pub async fn login() {
             out::print_success("Successfully authenticated.\n");
         }
         Err(err) => {
-            println!("{:?}", err);
-            out::print_error("Error: Failed to authenticate.\n");
+            out::print_error(&format!("Error: Failed to authenticate: {}\n", err));
             std::process::exit(1);
         }
     }
 }
 
+/// Saves the token to the active profile, or to the top-level settings without one.
 fn update_token(token: String) {
-    let config = utils::read_config();
-
-    let new_config = Config {
-        username: config.username,
-        token,
-        sort: config.sort,
-        protocol: config.protocol,
-        color: config.color,
-        fancy: config.fancy,
-    };
+    let mut config = utils::read_config();
+
+    match super::layers::active_profile(&config) {
+        Ok(Some(name)) => {
+            if let Some(profile) = config.profiles.get_mut(&name) {
+                profile.token = Some(token);
+            }
+        }
+        _ => config.token = token,
+    }
+
+    utils::save_config_file(config);
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    /// Starts a server emulating the GitHub Enterprise Server paths.
+    /// Returns its host (ex. `http://127.0.0.1:1234`) and the requested paths,
+    /// with the token of the authenticated requests (ex. `/api/v3/user/repos gho_123`).
+    pub(crate) fn mock_server() -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let host = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = requests.clone();
+        let server_host = host.clone();
+
+        std::thread::spawn(move || {
+            let mut polls = 0;
+
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+
+                // Only the token is read from the headers, the bodies aren't needed
+                let mut token = None;
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    if let Some(value) = line.to_lowercase().strip_prefix("authorization: bearer ")
+                    {
+                        token = Some(value.trim().to_string());
+                    }
+                    line.clear();
+                }
+
+                let path = request_line
+                    .split(' ')
+                    .nth(1)
+                    .unwrap_or_default()
+                    .to_string();
+                log.lock().unwrap().push(match token {
+                    Some(token) => format!("{} {}", path, token),
+                    None => path.clone(),
+                });
+
+                let (status, body) = match path.as_str() {
+                    "/login/device/code" => (
+                        "200 OK",
+                        r#"{"device_code":"dc","user_code":"ABCD-1234","verification_uri":"https://ghe/login/device","expires_in":900,"interval":0}"#.to_string(),
+                    ),
+                    "/login/oauth/access_token" => {
+                        polls += 1;
+                        match polls {
+                            1 => ("200 OK", r#"{"error":"authorization_pending"}"#.to_string()),
+                            _ => ("200 OK", r#"{"access_token":"ghu_enterprise","token_type":"bearer"}"#.to_string()),
+                        }
+                    }
+                    "/api/v3/user/repos" => (
+                        "200 OK",
+                        format!(
+                            r#"[{{"name":"tgh","full_name":"acme/tgh","ssh_url":"git@ghe:acme/tgh.git","clone_url":"{}/acme/tgh.git"}}]"#,
+                            server_host
+                        ),
+                    ),
+                    _ => ("404 Not Found", r#"{"message":"Not Found"}"#.to_string()),
+                };
 
-    utils::save_config_file(new_config);
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+
+        (host, requests)
+    }
+
+    #[test]
+    fn test_endpoints() {
+        let endpoints = Endpoints::new("github.com");
+        assert_eq!(endpoints.api_url, "https://api.github.com");
+        assert_eq!(
+            endpoints.device_code_url(),
+            "https://github.com/login/device/code"
+        );
+
+        let endpoints = Endpoints::new("github.acme.com/");
+        assert_eq!(endpoints.web_url, "https://github.acme.com");
+        assert_eq!(endpoints.api_url, "https://github.acme.com/api/v3");
+        assert_eq!(
+            endpoints.access_token_url(),
+            "https://github.acme.com/login/oauth/access_token"
+        );
+
+        let endpoints = Endpoints::new("http://localhost:8080");
+        assert_eq!(endpoints.api_url, "http://localhost:8080/api/v3");
+    }
+
+    #[tokio::test]
+    async fn test_enterprise_device_flow() {
+        let (host, requests) = mock_server();
+        let endpoints = Endpoints::new(&host);
+        let client = client_builder("").unwrap().build().unwrap();
+
+        let device = request_device_code(&client, &endpoints, "Iv1.enterprise")
+            .await
+            .unwrap();
+        assert_eq!(device.user_code, "ABCD-1234");
+
+        let pending =
+            request_access_token(&client, &endpoints, "Iv1.enterprise", &device.device_code)
+                .await
+                .unwrap();
+        assert_eq!(pending.access_token, None);
+        assert_eq!(pending.error.as_deref(), Some("authorization_pending"));
+
+        let granted =
+            request_access_token(&client, &endpoints, "Iv1.enterprise", &device.device_code)
+                .await
+                .unwrap();
+        assert_eq!(granted.access_token.as_deref(), Some("ghu_enterprise"));
+
+        let response = client
+            .get(format!("{}/user/repos", endpoints.api_url))
+            .send()
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+
+        assert_eq!(
+            *requests.lock().unwrap(),
+            vec![
+                "/login/device/code",
+                "/login/oauth/access_token",
+                "/login/oauth/access_token",
+                "/api/v3/user/repos"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_enterprise_authenticate() {
+        let (host, requests) = mock_server();
+        let config = super::super::Config {
+            host: host.clone(),
+            client_id: "Iv1.enterprise".into(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            authenticate_with(&config, false).await.unwrap(),
+            "ghu_enterprise"
+        );
+        assert_eq!(
+            *requests.lock().unwrap(),
+            vec![
+                "/login/device/code",
+                "/login/oauth/access_token",
+                "/login/oauth/access_token"
+            ]
+        );
+
+        // The tgh OAuth app only exists on github.com
+        let config = super::super::Config {
+            host,
+            ..Default::default()
+        };
+        assert!(authenticate_with(&config, false)
+            .await
+            .unwrap_err()
+            .contains("client_id"));
+    }
+
+    #[tokio::test]
+    async fn test_enterprise_api_client() {
+        let (host, requests) = mock_server();
+        let config = super::super::Config {
+            host: host.clone(),
+            token: "gho_123".into(),
+            ..Default::default()
+        };
+
+        assert_eq!(api_url_for(&config), format!("{}/api/v3", host));
+
+        let response = api_client_for(&config)
+            .get(format!("{}/user/repos", api_url_for(&config)))
+            .send()
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+
+        assert_eq!(
+            *requests.lock().unwrap(),
+            vec!["/api/v3/user/repos gho_123"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_ca_bundle() {
+        let path = std::env::temp_dir().join(format!("tgh-ca-{}.pem", std::process::id()));
+        std::fs::write(&path, "not a certificate").unwrap();
+
+        let result = client_builder(&path.display().to_string());
+        let _ = std::fs::remove_file(&path);
+
+        assert!(result.is_err());
+        assert!(client_builder("/nonexistent/ca.pem").is_err());
+    }
 }
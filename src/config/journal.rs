@@ -0,0 +1,334 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::utils;
+use crate::functions::git;
+
+/// How many operations are kept in the journal.
+const MAX_OPERATIONS: usize = 50;
+
+/// A single operation performed by tgh, with enough information to reverse it.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Operation {
+    /// The tgh command that performed the operation (ex. `commit`, `amend`)
+    pub command: String,
+    /// The top level directory of the repository
+    pub repository: String,
+    /// The branch HEAD pointed to (ex. `refs/heads/main`), empty when detached
+    pub branch: String,
+    /// The commit HEAD pointed to before the operation, `None` if there were no commits
+    pub head_before: Option<String>,
+    pub head_after: String,
+    /// The tree of the index before the operation (from `git write-tree`)
+    pub index_before: Option<String>,
+    pub pushed: Option<Push>,
+    pub timestamp: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Push {
+    pub remote: String,
+    /// The ref on the remote (ex. `refs/heads/main`)
+    pub remote_ref: String,
+    /// The commit the remote ref pointed to after the push
+    pub commit: String,
+}
+
+/// The state of the repository before an operation.
+pub struct Snapshot {
+    head: Option<String>,
+    index: Option<String>,
+}
+
+pub fn snapshot() -> Snapshot {
+    snapshot_in(".")
+}
+
+fn snapshot_in(dir: &str) -> Snapshot {
+    Snapshot {
+        head: git_in(dir, &["rev-parse", "--verify", "--quiet", "HEAD"]).ok(),
+        index: git_in(dir, &["write-tree"]).ok(),
+    }
+}
+
+/// Runs git in the directory, the public functions use the current one.
+fn git_in(dir: &str, args: &[&str]) -> Result<String, String> {
+    let mut all = vec!["-C", dir];
+    all.extend(args);
+
+    git(&all)
+}
+
+fn journal_path() -> Option<PathBuf> {
+    match utils::get_journal_path() {
+        Ok(path) => Some(PathBuf::from(path)),
+        Err(err) => {
+            crate::out::print_error(&format!("Failed to read the operation journal: {}", err));
+            None
+        }
+    }
+}
+
+fn read_journal(path: &Path) -> Vec<Operation> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_default(),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn save_journal(path: &Path, operations: &[Operation]) {
+    let contents = serde_json::to_string_pretty(operations).unwrap();
+    let saved = match path.parent() {
+        Some(parent) => std::fs::create_dir_all(parent),
+        None => Ok(()),
+    }
+    .and_then(|_| std::fs::write(path, contents));
+
+    if let Err(err) = saved {
+        crate::out::print_error(&format!("Failed to save the operation journal: {}", err));
+    }
+}
+
+fn current_repository(dir: &str) -> Option<String> {
+    git_in(dir, &["rev-parse", "--show-toplevel"]).ok()
+}
+
+/// Records an operation that moved HEAD from the state in `before` to the current HEAD.
+pub fn record(command: &str, before: Snapshot, pushed: bool) {
+    if let Some(path) = journal_path() {
+        record_in(&path, ".", command, before, pushed);
+    }
+}
+
+fn record_in(journal: &Path, dir: &str, command: &str, before: Snapshot, pushed: bool) {
+    let repository = match current_repository(dir) {
+        Some(repository) => repository,
+        None => return,
+    };
+
+    let head_after = match git_in(dir, &["rev-parse", "HEAD"]) {
+        Ok(head) => head,
+        Err(_) => return,
+    };
+
+    let branch = git_in(dir, &["symbolic-ref", "--quiet", "HEAD"]).unwrap_or_default();
+
+    let pushed = match pushed && !branch.is_empty() {
+        true => get_push_target(dir, &branch).map(|(remote, remote_ref)| Push {
+            remote,
+            remote_ref,
+            commit: head_after.clone(),
+        }),
+        false => None,
+    };
+
+    let operation = Operation {
+        command: command.to_string(),
+        repository,
+        branch,
+        head_before: before.head,
+        head_after,
+        index_before: before.index,
+        pushed,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    };
+
+    let mut operations = read_journal(journal);
+    operations.push(operation);
+
+    if operations.len() > MAX_OPERATIONS {
+        operations.drain(..operations.len() - MAX_OPERATIONS);
+    }
+
+    save_journal(journal, &operations);
+}
+
+/// Returns the remote name and the remote ref the branch pushes to.
+fn get_push_target(dir: &str, branch: &str) -> Option<(String, String)> {
+    let output = git_in(
+        dir,
+        &[
+            "for-each-ref",
+            "--format=%(push:remotename)%00%(push:remoteref)%00%(upstream:remoteref)",
+            branch,
+        ],
+    )
+    .ok()?;
+
+    let mut parts = output.split('\0');
+    let remote = parts.next().filter(|remote| !remote.is_empty())?;
+
+    // `push:remoteref` is only set with an explicit push refspec, so fall back to the upstream,
+    // and then to the branch itself (`push.default = current`)
+    let remote_ref = parts
+        .find(|remote_ref| !remote_ref.is_empty())
+        .unwrap_or(branch);
+
+    Some((remote.to_string(), remote_ref.to_string()))
+}
+
+/// Returns the last operation recorded in the current repository.
+pub fn last_operation() -> Option<Operation> {
+    last_operation_in(&journal_path()?, ".")
+}
+
+fn last_operation_in(journal: &Path, dir: &str) -> Option<Operation> {
+    let repository = current_repository(dir)?;
+
+    read_journal(journal)
+        .into_iter()
+        .rev()
+        .find(|operation| operation.repository == repository)
+}
+
+/// Removes the last operation recorded in the current repository.
+pub fn remove_last_operation() {
+    let (journal, repository) = match (journal_path(), current_repository(".")) {
+        (Some(journal), Some(repository)) => (journal, repository),
+        _ => return,
+    };
+
+    let mut operations = read_journal(&journal);
+
+    if let Some(index) = operations
+        .iter()
+        .rposition(|operation| operation.repository == repository)
+    {
+        operations.remove(index);
+        save_journal(&journal, &operations);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a repository with a commit on `main`, in a temporary directory.
+    fn repository() -> tempfile::TempDir {
+        let repo = tempfile::tempdir().unwrap();
+        let dir = repo.path().to_str().unwrap();
+
+        git_in(dir, &["init", "-q", "-b", "main"]).unwrap();
+        commit(dir, "First");
+
+        repo
+    }
+
+    fn commit(dir: &str, message: &str) {
+        git_in(
+            dir,
+            &[
+                "-c",
+                "user.name=Ada",
+                "-c",
+                "user.email=ada@example.com",
+                "commit",
+                "-q",
+                "--allow-empty",
+                "-m",
+                message,
+            ],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_empty_journal() {
+        let state = tempfile::tempdir().unwrap();
+        let journal = state.path().join("state/journal.json");
+        let repo = repository();
+
+        assert!(last_operation_in(&journal, repo.path().to_str().unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_last_operation() {
+        let state = tempfile::tempdir().unwrap();
+        let journal = state.path().join("state/journal.json");
+        let (repo, other) = (repository(), repository());
+        let (dir, other_dir) = (
+            repo.path().to_str().unwrap(),
+            other.path().to_str().unwrap(),
+        );
+
+        let before = snapshot_in(dir);
+        commit(dir, "Second");
+        record_in(&journal, dir, "commit", before, false);
+
+        let before = snapshot_in(dir);
+        commit(dir, "Third");
+        record_in(&journal, dir, "amend", before, false);
+
+        // A later operation in another repository
+        let before = snapshot_in(other_dir);
+        commit(other_dir, "Second");
+        record_in(&journal, other_dir, "commit", before, false);
+
+        assert_eq!(read_journal(&journal).len(), 3);
+
+        let last = last_operation_in(&journal, dir).unwrap();
+        assert_eq!(last.command, "amend");
+        assert_eq!(last.branch, "refs/heads/main");
+        assert_eq!(
+            last.head_after,
+            git_in(dir, &["rev-parse", "HEAD"]).unwrap()
+        );
+        assert_eq!(
+            last.head_before.as_deref(),
+            git_in(dir, &["rev-parse", "HEAD~1"]).ok().as_deref()
+        );
+        assert!(last.pushed.is_none());
+    }
+
+    #[test]
+    fn test_corrupted_journal() {
+        let state = tempfile::tempdir().unwrap();
+        let journal = state.path().join("journal.json");
+        let repo = repository();
+        let dir = repo.path().to_str().unwrap();
+
+        std::fs::write(&journal, "[{\"command\": \"commit\",").unwrap();
+        assert!(last_operation_in(&journal, dir).is_none());
+
+        // The journal starts over
+        let before = snapshot_in(dir);
+        commit(dir, "Second");
+        record_in(&journal, dir, "commit", before, false);
+
+        assert_eq!(read_journal(&journal).len(), 1);
+        assert_eq!(last_operation_in(&journal, dir).unwrap().command, "commit");
+    }
+
+    #[test]
+    fn test_get_push_target() {
+        let repo = repository();
+        let dir = repo.path().to_str().unwrap();
+
+        // No remote to push to
+        assert_eq!(get_push_target(dir, "refs/heads/main"), None);
+
+        git_in(
+            dir,
+            &[
+                "remote",
+                "add",
+                "origin",
+                "https://example.com/acme/tgh.git",
+            ],
+        )
+        .unwrap();
+        git_in(dir, &["config", "branch.main.remote", "origin"]).unwrap();
+        assert_eq!(
+            get_push_target(dir, "refs/heads/main"),
+            Some(("origin".to_string(), "refs/heads/main".to_string()))
+        );
+
+        // The upstream is used when it has another name
+        git_in(dir, &["config", "branch.main.merge", "refs/heads/trunk"]).unwrap();
+        assert_eq!(
+            get_push_target(dir, "refs/heads/main"),
+            Some(("origin".to_string(), "refs/heads/trunk".to_string()))
+        );
+    }
+}
@@ -0,0 +1,440 @@
+use std::sync::OnceLock;
+
+use super::{migrations, profiles, utils, Config, KEYS};
+use crate::functions::git;
+
+/// The name of the per-repository config file, read from the root of the repository.
+pub const REPOSITORY_FILE: &str = ".tgh.toml";
+
+/// Settings which can't be changed by a repository file, since anyone can commit one
+/// (a host or CA bundle from a repository could send the token to someone else,
+/// and the update channel decides which releases get installed).
+const GLOBAL_ONLY: [&str; 6] = [
+    "username",
+    "token",
+    "host",
+    "ca_bundle",
+    "client_id",
+    "update_channel",
+];
+
+/// The `-c key=value` overrides passed on the command line.
+static OVERRIDES: OnceLock<Vec<(String, String)>> = OnceLock::new();
+
+/// The profile passed with `--profile`.
+static PROFILE: OnceLock<String> = OnceLock::new();
+
+/// The layer of the repository's `.tgh.toml` and its remotes, they're only read once per run
+/// (unlike the global config, which can be changed by the command).
+static REPOSITORY_LAYER: OnceLock<Result<Option<Layer>, String>> = OnceLock::new();
+static REMOTE_URLS: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Where the value of a setting came from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Origin {
+    Default,
+    Global(String),
+    Profile(String),
+    Repository(String),
+    Environment(String),
+    CommandLine,
+}
+
+impl std::fmt::Display for Origin {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Origin::Default => write!(f, "default"),
+            Origin::Global(path) => write!(f, "global: {}", path),
+            Origin::Profile(name) => write!(f, "profile: {}", name),
+            Origin::Repository(path) => write!(f, "repository: {}", path),
+            Origin::Environment(variable) => write!(f, "env: {}", variable),
+            Origin::CommandLine => write!(f, "command line"),
+        }
+    }
+}
+
+/// A set of values from a single source.
+#[derive(Clone)]
+pub struct Layer {
+    pub origin: Origin,
+    pub values: Vec<(String, String)>,
+}
+
+/// The resolved config, with the origin of every setting.
+pub struct Layered {
+    pub config: Config,
+    origins: Vec<(&'static str, Origin)>,
+}
+
+impl Layered {
+    pub fn origin(&self, key: &str) -> Origin {
+        self.origins
+            .iter()
+            .find(|(name, _)| *name == key)
+            .map(|(_, origin)| origin.clone())
+            .unwrap_or(Origin::Default)
+    }
+}
+
+/// Parses and stores the `-c key=value` overrides, so they apply to every config lookup.
+pub fn set_overrides(values: &[String]) -> Result<(), String> {
+    let overrides = values
+        .iter()
+        .map(|value| parse_override(value))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let _ = OVERRIDES.set(overrides);
+
+    Ok(())
+}
+
+/// Selects a profile for this run, instead of matching it by the remote or directory.
+pub fn set_profile(name: Option<String>) {
+    if let Some(name) = name.or_else(|| std::env::var("TGH_PROFILE").ok()) {
+        let _ = PROFILE.set(name);
+    }
+}
+
+fn parse_override(value: &str) -> Result<(String, String), String> {
+    let (key, value) = value
+        .split_once('=')
+        .ok_or(format!("Invalid override `{}`, expected KEY=VALUE", value))?;
+    let key = key.trim();
+
+    if !KEYS.contains(&key) {
+        return Err(format!(
+            "Unknown key `{}`, expected one of: {}",
+            key,
+            KEYS.join(", ")
+        ));
+    }
+
+    Ok((key.to_string(), value.to_string()))
+}
+
+/// The settings of a repository config file.
+#[derive(Debug, PartialEq)]
+pub struct RepositoryFile {
+    pub values: Vec<(String, String)>,
+    /// The keys this version doesn't know (ex. from a newer tgh), they're skipped
+    pub unknown: Vec<String>,
+}
+
+/// Parses a repository config file (ex. `sort = "alphabetical"` or `fancy = false`).
+pub fn parse_repository_file(contents: &str) -> Result<RepositoryFile, String> {
+    let table: toml::Table = toml::from_str(contents).map_err(|err| err.message().to_string())?;
+    let mut file = RepositoryFile {
+        values: Vec::new(),
+        unknown: Vec::new(),
+    };
+
+    for (key, value) in table {
+        if GLOBAL_ONLY.contains(&key.as_str()) {
+            return Err(format!("`{}` can only be set in the global config", key));
+        }
+
+        if !KEYS.contains(&key.as_str()) {
+            file.unknown.push(key);
+            continue;
+        }
+
+        let value = match value {
+            toml::Value::String(value) => value,
+            toml::Value::Boolean(value) => value.to_string(),
+            toml::Value::Integer(value) => value.to_string(),
+            _ => return Err(format!("`{}` must be a string or a boolean", key)),
+        };
+
+        file.values.push((key, value));
+    }
+
+    Ok(file)
+}
+
+/// Applies the layers in order, later layers override earlier ones.
+pub fn merge(layers: Vec<Layer>) -> Result<Layered, String> {
+    let mut layered = Layered {
+        config: Config::default(),
+        origins: KEYS.iter().map(|key| (*key, Origin::Default)).collect(),
+    };
+
+    for layer in layers {
+        for (key, value) in layer.values {
+            layered
+                .config
+                .set(&key, &value)
+                .map_err(|err| format!("{} (from {})", err, layer.origin))?;
+
+            if let Some((_, origin)) = layered.origins.iter_mut().find(|(name, _)| *name == key) {
+                *origin = layer.origin.clone();
+            }
+        }
+    }
+
+    Ok(layered)
+}
+
+/// Reads the global config file, `None` if it doesn't exist yet.
+fn read_global() -> Result<Option<Config>, String> {
+    if !utils::config_exists() {
+        return Ok(None);
+    }
+
+    let path = utils::get_config_path().map_err(|err| err.to_string())?;
+    let contents = utils::read_file_content(path.clone()).map_err(|err| err.to_string())?;
+    let (config, _) =
+        migrations::deserialize(&contents).map_err(|err| format!("{}: {}", path, err))?;
+
+    Ok(Some(config))
+}
+
+fn global_layer(config: &Config) -> Layer {
+    let values = KEYS
+        .iter()
+        .map(|key| (key.to_string(), config.get(key).unwrap()))
+        // An empty username isn't valid, and would fail the layer
+        .filter(|(key, value)| key != "username" || !value.is_empty())
+        .collect();
+
+    Layer {
+        origin: Origin::Global(utils::get_config_path().unwrap_or_default()),
+        values,
+    }
+}
+
+fn get_remote_urls() -> &'static [String] {
+    REMOTE_URLS.get_or_init(|| {
+        let output = git(&["config", "--get-regexp", r"^remote\..*\.url$"]).unwrap_or_default();
+
+        output
+            .lines()
+            .filter_map(|line| line.split_once(' ').map(|(_, url)| url.to_string()))
+            .collect()
+    })
+}
+
+/// Returns the name of the profile for the current repository or directory.
+pub fn active_profile(config: &Config) -> Result<Option<String>, String> {
+    let directory = std::env::current_dir().unwrap_or_default();
+
+    profiles::select_profile(
+        &config.profiles,
+        PROFILE.get().map(String::as_str),
+        get_remote_urls(),
+        &directory,
+        &home::home_dir(),
+    )
+}
+
+fn profile_layer(config: &Config) -> Result<Option<Layer>, String> {
+    let name = match active_profile(config)? {
+        Some(name) => name,
+        None => return Ok(None),
+    };
+
+    Ok(Some(Layer {
+        values: config.profiles[&name].values(),
+        origin: Origin::Profile(name),
+    }))
+}
+
+fn repository_layer() -> Result<Option<Layer>, String> {
+    REPOSITORY_LAYER.get_or_init(read_repository_layer).clone()
+}
+
+fn read_repository_layer() -> Result<Option<Layer>, String> {
+    let root = match git(&["rev-parse", "--show-toplevel"]) {
+        Ok(root) => root,
+        Err(_) => return Ok(None),
+    };
+
+    let path = std::path::Path::new(&root).join(REPOSITORY_FILE);
+
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let path = path.display().to_string();
+    let contents = utils::read_file_content(path.clone()).map_err(|err| err.to_string())?;
+    let file = parse_repository_file(&contents).map_err(|err| format!("{}: {}", path, err))?;
+
+    // Printed to stderr, the theme may not be loaded yet
+    for key in &file.unknown {
+        eprintln!("warning: {}: unknown key `{}`, skipping it", path, key);
+    }
+
+    Ok(Some(Layer {
+        origin: Origin::Repository(path),
+        values: file.values,
+    }))
+}
+
+fn environment_layers() -> Vec<Layer> {
+    KEYS.iter()
+        .filter_map(|key| {
+            let variable = format!("TGH_{}", key.to_uppercase());
+            let value = std::env::var(&variable).ok()?;
+
+            Some(Layer {
+                origin: Origin::Environment(variable),
+                values: vec![(key.to_string(), value)],
+            })
+        })
+        .collect()
+}
+
+/// Resolves the config from the built-in defaults, the global config file, the active profile,
+/// the repository's `.tgh.toml`, the `TGH_*` environment variables and the `-c` overrides (in that order).
+pub fn resolve() -> Result<Layered, String> {
+    let mut layers = Vec::new();
+    let global = read_global()?;
+
+    if let Some(config) = &global {
+        layers.push(global_layer(config));
+        layers.extend(profile_layer(config)?);
+    } else if let Some(name) = PROFILE.get() {
+        return Err(format!("Unknown profile `{}`", name));
+    }
+
+    layers.extend(repository_layer()?);
+    layers.extend(environment_layers());
+
+    if let Some(overrides) = OVERRIDES.get() {
+        layers.push(Layer {
+            origin: Origin::CommandLine,
+            values: overrides.clone(),
+        });
+    }
+
+    let mut layered = merge(layers)?;
+
+    // The profiles and the theme aren't layered, they're only read from the global config
+    if let Some(config) = global {
+        layered.config.profiles = config.profiles;
+        layered.config.theme = config.theme;
+    }
+
+    Ok(layered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::defines::{PROTOCOL, SORTING};
+
+    fn layer(origin: Origin, values: &[(&str, &str)]) -> Layer {
+        Layer {
+            origin,
+            values: values
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_merge_order() {
+        let layered = merge(vec![
+            layer(
+                Origin::Global("config.json".into()),
+                &[
+                    ("username", "dkomeza"),
+                    ("sort", "alphabetical"),
+                    ("protocol", "https"),
+                    ("token", "gho_personal"),
+                ],
+            ),
+            layer(
+                Origin::Profile("work".into()),
+                &[("token", "gho_work"), ("protocol", "https")],
+            ),
+            layer(
+                Origin::Repository(".tgh.toml".into()),
+                &[("protocol", "ssh"), ("fancy", "false")],
+            ),
+            layer(
+                Origin::Environment("TGH_FANCY".into()),
+                &[("fancy", "true")],
+            ),
+            layer(Origin::CommandLine, &[("sort", "last-updated")]),
+        ])
+        .unwrap();
+
+        assert_eq!(layered.config.username, "dkomeza");
+        assert_eq!(layered.config.token, "gho_work");
+        assert!(layered.config.sort == SORTING::LastUpdated);
+        assert!(layered.config.protocol == PROTOCOL::SSH);
+        assert!(layered.config.fancy);
+
+        assert_eq!(
+            layered.origin("username"),
+            Origin::Global("config.json".into())
+        );
+        assert_eq!(layered.origin("token"), Origin::Profile("work".into()));
+        assert_eq!(layered.origin("sort"), Origin::CommandLine);
+        assert_eq!(
+            layered.origin("protocol"),
+            Origin::Repository(".tgh.toml".into())
+        );
+        assert_eq!(
+            layered.origin("fancy"),
+            Origin::Environment("TGH_FANCY".into())
+        );
+        assert_eq!(layered.origin("color"), Origin::Default);
+    }
+
+    #[test]
+    fn test_merge_invalid_value() {
+        let layers = vec![layer(
+            Origin::Environment("TGH_SORT".into()),
+            &[("sort", "random")],
+        )];
+        let err = merge(layers).err().unwrap();
+
+        assert!(err.ends_with("(from env: TGH_SORT)"));
+    }
+
+    #[test]
+    fn test_parse_repository_file() {
+        let file = parse_repository_file("protocol = \"ssh\"\nfancy = false\n").unwrap();
+
+        assert_eq!(
+            file.values,
+            vec![
+                ("fancy".to_string(), "false".to_string()),
+                ("protocol".to_string(), "ssh".to_string())
+            ]
+        );
+        assert!(file.unknown.is_empty());
+
+        assert!(parse_repository_file("token = \"gho_123\"").is_err());
+        assert!(parse_repository_file("sort = [1, 2]").is_err());
+        assert!(parse_repository_file("sort = ").is_err());
+    }
+
+    #[test]
+    fn test_parse_repository_file_with_unknown_keys() {
+        // ex. a file written for a newer tgh
+        let file = parse_repository_file("sort = \"alphabetical\"\nlayout = [\"tree\"]\n").unwrap();
+
+        assert_eq!(
+            file.values,
+            vec![("sort".to_string(), "alphabetical".to_string())]
+        );
+        assert_eq!(file.unknown, vec!["layout".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_override() {
+        assert_eq!(
+            parse_override("sort=alphabetical"),
+            Ok(("sort".to_string(), "alphabetical".to_string()))
+        );
+        assert_eq!(
+            parse_override("color=cyan=1"),
+            Ok(("color".to_string(), "cyan=1".to_string()))
+        );
+        assert!(parse_override("sort").is_err());
+        assert!(parse_override("editor=vim").is_err());
+    }
+}
@@ -0,0 +1,326 @@
+use serde_json::{Map, Value};
+
+use super::Config;
+
+/// The version of the config file written by this build of tgh.
+pub const CURRENT_VERSION: u64 = 5;
+
+type Migration = fn(&mut Map<String, Value>);
+
+/// `MIGRATIONS[n]` upgrades a config file from version `n` to version `n + 1`.
+const MIGRATIONS: [Migration; CURRENT_VERSION as usize] = [
+    migrate_v0_to_v1,
+    migrate_v1_to_v2,
+    migrate_v2_to_v3,
+    migrate_v3_to_v4,
+    migrate_v4_to_v5,
+];
+
+/// Returns the version of the config file, files written before versioning are version 0.
+pub fn get_version(value: &Value) -> u64 {
+    value.get("version").and_then(Value::as_u64).unwrap_or(0)
+}
+
+/// Upgrades the raw config to the current version.
+///
+/// ### Returns
+/// `true` if any migration was applied.
+pub fn migrate(value: &mut Value) -> Result<bool, String> {
+    let version = get_version(value);
+    let object = value
+        .as_object_mut()
+        .ok_or("The config file must contain a JSON object")?;
+
+    // Configs written by a newer tgh are loaded as they are (unknown fields are ignored)
+    if version >= CURRENT_VERSION {
+        return Ok(false);
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(object);
+    }
+
+    object.insert("version".into(), Value::from(CURRENT_VERSION));
+
+    Ok(true)
+}
+
+/// Parses the contents of a config file, upgrading it from older versions if needed.
+/// Missing fields are filled with their default values.
+pub fn deserialize(contents: &str) -> Result<(Config, bool), String> {
+    let mut value: Value = serde_json::from_str(contents).map_err(|err| err.to_string())?;
+    let migrated = migrate(&mut value)?;
+    let config = serde_json::from_value(value).map_err(|err| err.to_string())?;
+
+    Ok((config, migrated))
+}
+
+/// Version 0 had no `version` field, and releases before the `color` and `fancy` settings
+/// wrote files without them. Fill in any missing setting, so the file is complete again.
+/// The defaults are the ones of version 1, the later settings are added by their own migrations.
+fn migrate_v0_to_v1(config: &mut Map<String, Value>) {
+    let defaults = serde_json::json!({
+        "username": "",
+        "token": "",
+        "sort": "LastUpdated",
+        "protocol": "HTTPS",
+        "color": "NORMAL",
+        "fancy": true,
+    });
+
+    for (key, value) in defaults.as_object().unwrap() {
+        if !config.contains_key(key) || config[key].is_null() {
+            config.insert(key.clone(), value.clone());
+        }
+    }
+}
+
+/// Version 2 added the account profiles.
+fn migrate_v1_to_v2(config: &mut Map<String, Value>) {
+    config
+        .entry("profiles")
+        .or_insert_with(|| Value::Object(Map::new()));
+}
+
+/// Version 3 added the GitHub host, so the existing configs point to github.com.
+fn migrate_v2_to_v3(config: &mut Map<String, Value>) {
+    config
+        .entry("host")
+        .or_insert_with(|| Value::from(super::github::DEFAULT_HOST));
+    config.entry("ca_bundle").or_insert_with(|| Value::from(""));
+    config.entry("client_id").or_insert_with(|| Value::from(""));
+}
+
+/// Version 4 added the output theme, which starts empty (the default styles and `color` apply).
+fn migrate_v3_to_v4(config: &mut Map<String, Value>) {
+    config
+        .entry("theme")
+        .or_insert_with(|| Value::Object(Map::new()));
+}
+
+/// Version 5 added the update settings, the existing configs keep checking the stable releases.
+fn migrate_v4_to_v5(config: &mut Map<String, Value>) {
+    config
+        .entry("update_check")
+        .or_insert_with(|| Value::from(true));
+    config
+        .entry("update_channel")
+        .or_insert_with(|| Value::from("STABLE"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::defines::{CHANNEL, COLOR, PROTOCOL, SORTING};
+
+    #[test]
+    fn test_migrate_unversioned_config() {
+        let contents = r#"{
+            "username": "dkomeza",
+            "token": "gho_123",
+            "sort": "Alphabetical",
+            "protocol": "SSH",
+            "color": "BLUE",
+            "fancy": false
+        }"#;
+        let (config, migrated) = deserialize(contents).unwrap();
+
+        assert!(migrated);
+        assert_eq!(config.version, CURRENT_VERSION);
+        assert_eq!(config.username, "dkomeza");
+        assert_eq!(config.token, "gho_123");
+        assert!(config.sort == SORTING::Alphabetical);
+        assert!(config.protocol == PROTOCOL::SSH);
+        assert!(config.color == COLOR::BLUE);
+        assert!(!config.fancy);
+    }
+
+    #[test]
+    fn test_migrate_config_without_fancy() {
+        let contents = r#"{
+            "username": "dkomeza",
+            "token": "gho_123",
+            "sort": "LastUpdated",
+            "protocol": "HTTPS",
+            "color": "GREEN"
+        }"#;
+        let (config, migrated) = deserialize(contents).unwrap();
+
+        assert!(migrated);
+        assert_eq!(config.token, "gho_123");
+        assert!(config.color == COLOR::GREEN);
+        assert!(config.fancy);
+    }
+
+    #[test]
+    fn test_migrate_config_without_color_and_fancy() {
+        let contents =
+            r#"{"username": "dkomeza", "token": "", "sort": "LastUpdated", "protocol": "SSH"}"#;
+        let (config, migrated) = deserialize(contents).unwrap();
+
+        assert!(migrated);
+        assert!(config.protocol == PROTOCOL::SSH);
+        assert!(config.color == COLOR::NORMAL);
+        assert!(config.fancy);
+    }
+
+    #[test]
+    fn test_migrate_replaces_null_fields() {
+        let contents =
+            r#"{"username": "dkomeza", "token": null, "sort": "LastUpdated", "protocol": "SSH"}"#;
+        let (config, _) = deserialize(contents).unwrap();
+
+        assert_eq!(config.token, "");
+    }
+
+    #[test]
+    fn test_migrate_v0_to_v1_only_adds_v1_settings() {
+        let mut config = Map::new();
+        config.insert("username".into(), Value::from("dkomeza"));
+
+        migrate_v0_to_v1(&mut config);
+
+        assert_eq!(
+            Value::Object(config),
+            serde_json::json!({
+                "username": "dkomeza",
+                "token": "",
+                "sort": "LastUpdated",
+                "protocol": "HTTPS",
+                "color": "NORMAL",
+                "fancy": true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_migrate_v1_config() {
+        let contents = r#"{
+            "version": 1,
+            "username": "dkomeza",
+            "token": "gho_123",
+            "sort": "LastUpdated",
+            "protocol": "SSH",
+            "color": "NORMAL",
+            "fancy": true
+        }"#;
+        let mut value: Value = serde_json::from_str(contents).unwrap();
+
+        assert!(migrate(&mut value).unwrap());
+        assert_eq!(value["version"], CURRENT_VERSION);
+        assert_eq!(value["profiles"], serde_json::json!({}));
+
+        let config: Config = serde_json::from_value(value).unwrap();
+        assert_eq!(config.token, "gho_123");
+        assert!(config.profiles.is_empty());
+        assert_eq!(config.host, "github.com");
+    }
+
+    #[test]
+    fn test_migrate_v2_config() {
+        let contents = r#"{
+            "version": 2,
+            "username": "dkomeza",
+            "token": "gho_123",
+            "sort": "LastUpdated",
+            "protocol": "SSH",
+            "color": "NORMAL",
+            "fancy": true,
+            "profiles": {"work": {"username": "dk-acme", "remotes": ["acme/*"]}}
+        }"#;
+        let (config, migrated) = deserialize(contents).unwrap();
+
+        assert!(migrated);
+        assert_eq!(config.host, "github.com");
+        assert_eq!(config.ca_bundle, "");
+        assert_eq!(config.profiles["work"].username.as_deref(), Some("dk-acme"));
+        assert_eq!(config.profiles["work"].host, None);
+        assert!(config.theme.is_empty());
+    }
+
+    #[test]
+    fn test_migrate_v3_config() {
+        let contents = r#"{
+            "version": 3,
+            "username": "dkomeza",
+            "token": "gho_123",
+            "sort": "LastUpdated",
+            "protocol": "SSH",
+            "color": "MAGENTA",
+            "fancy": true,
+            "host": "github.com",
+            "profiles": {}
+        }"#;
+        let mut value: Value = serde_json::from_str(contents).unwrap();
+
+        assert!(migrate(&mut value).unwrap());
+        assert_eq!(value["theme"], serde_json::json!({}));
+
+        let config: Config = serde_json::from_value(value).unwrap();
+        assert!(config.color == COLOR::MAGENTA);
+    }
+
+    #[test]
+    fn test_migrate_v4_config() {
+        let contents = r#"{
+            "version": 4,
+            "username": "dkomeza",
+            "token": "gho_123",
+            "sort": "LastUpdated",
+            "protocol": "SSH",
+            "color": "NORMAL",
+            "fancy": true,
+            "host": "github.com",
+            "profiles": {},
+            "theme": {}
+        }"#;
+        let mut value: Value = serde_json::from_str(contents).unwrap();
+
+        assert!(migrate(&mut value).unwrap());
+        assert_eq!(value["update_check"], true);
+
+        let config: Config = serde_json::from_value(value).unwrap();
+        assert!(config.update_check);
+        assert_eq!(config.update_channel, CHANNEL::STABLE);
+    }
+
+    #[test]
+    fn test_current_config_is_not_migrated() {
+        let config = Config {
+            username: "dkomeza".into(),
+            ..Default::default()
+        };
+        let contents = serde_json::to_string(&config).unwrap();
+
+        let (loaded, migrated) = deserialize(&contents).unwrap();
+
+        assert!(!migrated);
+        assert_eq!(loaded.username, "dkomeza");
+    }
+
+    #[test]
+    fn test_newer_config_is_loaded() {
+        let contents = r#"{
+            "version": 99,
+            "username": "dkomeza",
+            "token": "gho_123",
+            "sort": "LastUpdated",
+            "protocol": "HTTPS",
+            "color": "NORMAL",
+            "fancy": true,
+            "some_future_setting": [1, 2, 3]
+        }"#;
+        let (config, migrated) = deserialize(contents).unwrap();
+
+        assert!(!migrated);
+        assert_eq!(config.version, 99);
+        assert_eq!(config.token, "gho_123");
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert!(deserialize("[]").is_err());
+        assert!(deserialize("{").is_err());
+        assert!(deserialize(r#"{"sort": "Random"}"#).is_err());
+    }
+}
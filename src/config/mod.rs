@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
 use std::time::SystemTime;
 
 use crate::view;
@@ -6,27 +8,59 @@ use serde::{Deserialize, Serialize};
 
 mod config;
 pub mod defines;
+mod fields;
 mod git;
-mod github;
+pub mod github;
+pub mod journal;
+pub mod layers;
+pub mod migrations;
+pub mod paths;
+pub mod profiles;
 pub mod update;
 pub mod utils;
 
 pub use config::load_config;
+pub use fields::KEYS;
 pub use github::login;
 
 #[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct Config {
+    /// The version of the config file format (see `migrations`)
+    pub version: u64,
     pub username: String,
     pub token: String,
     pub sort: defines::SORTING,
     pub protocol: defines::PROTOCOL,
     pub color: defines::COLOR,
     pub fancy: bool,
+    /// The GitHub host (ex. `github.com` or `github.acme.com` for GitHub Enterprise Server)
+    pub host: String,
+    /// A PEM file with extra CA certificates to trust (ex. for a GHES with a private CA)
+    pub ca_bundle: String,
+    /// The OAuth app used to log in, empty for the tgh app on github.com
+    pub client_id: String,
+    pub profiles: BTreeMap<String, profiles::Profile>,
+    /// Style overrides for the output roles (ex. `"hash": "bold 208"`, see `view::theme`)
+    pub theme: BTreeMap<String, String>,
+    /// Whether to check for a new version in the background, at most once a day
+    pub update_check: bool,
+    /// The releases checked and installed by `tgh update`
+    pub update_channel: defines::CHANNEL,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Metadata {
+    /// When the releases were last checked for a new version
     pub last_checked: String,
+    /// The version of the latest release found by the last check, on `latest_channel`
+    #[serde(default)]
+    pub latest_version: Option<String>,
+    #[serde(default)]
+    pub latest_channel: Option<defines::CHANNEL>,
+    /// Whether the new version found by the last check was shown
+    #[serde(default)]
+    pub notified: bool,
 }
 
 impl Default for Metadata {
@@ -34,6 +68,9 @@ impl Default for Metadata {
         Metadata {
             last_checked: chrono::DateTime::<chrono::Utc>::from(SystemTime::UNIX_EPOCH)
                 .to_rfc3339(),
+            latest_version: None,
+            latest_channel: None,
+            notified: false,
         }
     }
 }
@@ -44,7 +81,7 @@ pub async fn check_prerequisites() {
     match git::validate_git_install() {
         Ok(_) => {}
         Err(err) => {
-            view::printer(&err.to_string());
+            view::printer(view::unindent(&err.to_string()));
             std::process::exit(1);
         }
     }
@@ -53,41 +90,50 @@ pub async fn check_prerequisites() {
     match check_git_config() {
         Ok(_) => {}
         Err(err) => {
-            view::printer(&err.to_string());
+            view::printer(view::unindent(&err.to_string()));
             std::process::exit(1);
         }
     }
 
     // Check for a config file
     if !utils::config_exists() {
-        view::printer("\n$b$cr `error`: Config file not found. Creating a new one...\n");
-        config::create_config();
-    } else if !utils::validate_config_file() {
-        view::printer("\n$b$cr `error`: Config file is invalid. Creating a new one...\n");
+        view::printer("\n$@error `error`: Config file not found. Creating a new one...\n");
         config::create_config();
+    } else {
+        utils::upgrade_config_file();
+
+        if !utils::validate_config_file() {
+            view::printer("\n$@error `error`: Config file is invalid. Creating a new one...\n");
+            config::backup_invalid_config();
+            config::create_config();
+        }
     }
 
     // Check for a GitHub token
     if !github::check_token() {
-        view::printer("\n$b$cr `error`: GitHub token not found. Logging in...\n");
+        view::printer("\n$@error `error`: GitHub token not found. Logging in...\n");
         login().await;
 
         std::thread::sleep(std::time::Duration::from_secs(1));
     }
 
-    if utils::should_check_for_updates() {
-        match update::check_for_updates().await {
-            Ok(msg) => {
-                if msg.len() > 0 {
-                    view::printer(msg);
-                }
-            }
-            Err(err) => {
-                view::printer(&format!(
-                    "\n$b$cr `error`: Failed to check for updates: {}\n",
-                    err.to_string()
-                ));
+    let config = load_config();
+    if config.update_check {
+        let mut metadata = utils::read_metadata();
+
+        // The notice is shown once per check, on stderr so it's never a part of the output,
+        // and only in a terminal so it isn't written to a log
+        if let Some(notice) = update::cached_notice(&metadata, config.update_channel) {
+            if !metadata.notified && std::io::stderr().is_terminal() {
+                eprint!("{}", view::render(notice));
+
+                metadata.notified = true;
+                utils::save_metadata_file(metadata.clone());
             }
         }
+
+        if utils::should_check_for_updates(&metadata, config.update_channel) {
+            update::check_in_background(config.update_channel);
+        }
     }
 }
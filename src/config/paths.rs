@@ -0,0 +1,223 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// The locations of the files tgh reads and writes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Paths {
+    /// `$XDG_CONFIG_HOME/tgh/config.json`, or the file passed with `--config` / `TGH_CONFIG`
+    pub config_file: PathBuf,
+    /// `$XDG_CONFIG_HOME/tgh`, where older versions also kept the state files
+    pub config_dir: PathBuf,
+    /// `$XDG_STATE_HOME/tgh`, for the metadata and the operation journal
+    /// (`state` next to the file passed with `--config`, so it's kept apart from the default one)
+    pub state_dir: PathBuf,
+}
+
+#[derive(Debug)]
+pub enum PathError {
+    /// The home directory couldn't be found, and no XDG directory was set
+    NoHomeDirectory,
+    CreateDirectory {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+impl std::fmt::Display for PathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PathError::NoHomeDirectory => write!(
+                f,
+                "Couldn't find the home directory, set XDG_CONFIG_HOME and XDG_STATE_HOME (or use --config)"
+            ),
+            PathError::CreateDirectory { path, source } => {
+                write!(f, "Failed to create {}: {}", path.display(), source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PathError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PathError::NoHomeDirectory => None,
+            PathError::CreateDirectory { source, .. } => Some(source),
+        }
+    }
+}
+
+static PATHS: OnceLock<Paths> = OnceLock::new();
+
+/// Resolves the paths from the environment, with an optional `--config` override.
+/// Has to be called before any file is read.
+pub fn init(config_override: Option<PathBuf>) -> Result<(), PathError> {
+    let config_override =
+        config_override.or_else(|| non_empty_var("TGH_CONFIG").map(PathBuf::from));
+    let paths = resolve(non_empty_var, home::home_dir(), config_override)?;
+
+    let _ = PATHS.set(paths);
+
+    Ok(())
+}
+
+/// Returns the resolved paths, they're resolved from the environment if `init` wasn't called.
+pub fn get() -> Result<&'static Paths, PathError> {
+    if let Some(paths) = PATHS.get() {
+        return Ok(paths);
+    }
+
+    let paths = resolve(non_empty_var, home::home_dir(), None)?;
+
+    Ok(PATHS.get_or_init(|| paths))
+}
+
+fn non_empty_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// Returns the XDG base directory, or the fallback inside the home directory.
+/// Relative paths are ignored, as required by the XDG spec.
+fn base_dir(
+    value: Option<String>,
+    home: &Option<PathBuf>,
+    fallback: &str,
+) -> Result<PathBuf, PathError> {
+    match value.map(PathBuf::from).filter(|path| path.is_absolute()) {
+        Some(path) => Ok(path),
+        None => home
+            .as_ref()
+            .map(|home| home.join(fallback))
+            .ok_or(PathError::NoHomeDirectory),
+    }
+}
+
+fn resolve(
+    var: impl Fn(&str) -> Option<String>,
+    home: Option<PathBuf>,
+    config_override: Option<PathBuf>,
+) -> Result<Paths, PathError> {
+    // An explicit config file keeps everything next to it, and works without a home directory
+    if let Some(file) = config_override {
+        let config_dir = file.parent().map(Path::to_path_buf).unwrap_or_default();
+
+        return Ok(Paths {
+            state_dir: config_dir.join("state"),
+            config_file: file,
+            config_dir,
+        });
+    }
+
+    let config_dir = base_dir(var("XDG_CONFIG_HOME"), &home, ".config")?.join("tgh");
+    let state_dir = base_dir(var("XDG_STATE_HOME"), &home, ".local/state")?.join("tgh");
+
+    Ok(Paths {
+        config_file: config_dir.join("config.json"),
+        config_dir,
+        state_dir,
+    })
+}
+
+fn create_dir(path: &Path) -> Result<(), PathError> {
+    std::fs::create_dir_all(path).map_err(|source| PathError::CreateDirectory {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Creates the directory containing the config file.
+pub fn create_config_dir() -> Result<(), PathError> {
+    match get()?.config_file.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => create_dir(parent),
+        _ => Ok(()),
+    }
+}
+
+pub fn create_state_dir() -> Result<(), PathError> {
+    create_dir(&get()?.state_dir)
+}
+
+/// Returns the path of a file in the state directory.
+/// Files left in the config directory by older versions are moved over on first use.
+pub fn state_file(name: &str) -> Result<PathBuf, PathError> {
+    let paths = get()?;
+    let path = paths.state_dir.join(name);
+    let legacy = paths.config_dir.join(name);
+
+    if !path.exists() && legacy.exists() && create_state_dir().is_ok() {
+        let _ = std::fs::rename(&legacy, &path);
+    }
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(vars: &'static [(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        move |name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn test_default_paths() {
+        let paths = resolve(env(&[]), Some("/home/user".into()), None).unwrap();
+
+        assert_eq!(paths.config_dir, PathBuf::from("/home/user/.config/tgh"));
+        assert_eq!(
+            paths.config_file,
+            PathBuf::from("/home/user/.config/tgh/config.json")
+        );
+        assert_eq!(
+            paths.state_dir,
+            PathBuf::from("/home/user/.local/state/tgh")
+        );
+    }
+
+    #[test]
+    fn test_xdg_paths() {
+        let vars = env(&[
+            ("XDG_CONFIG_HOME", "/xdg/config"),
+            ("XDG_STATE_HOME", "/xdg/state"),
+        ]);
+        let paths = resolve(vars, None, None).unwrap();
+
+        assert_eq!(
+            paths.config_file,
+            PathBuf::from("/xdg/config/tgh/config.json")
+        );
+        assert_eq!(paths.state_dir, PathBuf::from("/xdg/state/tgh"));
+    }
+
+    #[test]
+    fn test_relative_xdg_paths_are_ignored() {
+        let vars = env(&[("XDG_CONFIG_HOME", "config"), ("XDG_STATE_HOME", "./state")]);
+        let paths = resolve(vars, Some("/home/user".into()), None).unwrap();
+
+        assert_eq!(paths.config_dir, PathBuf::from("/home/user/.config/tgh"));
+        assert_eq!(
+            paths.state_dir,
+            PathBuf::from("/home/user/.local/state/tgh")
+        );
+    }
+
+    #[test]
+    fn test_config_override() {
+        let vars = env(&[("XDG_STATE_HOME", "/xdg/state")]);
+        let paths = resolve(vars, None, Some("/ci/tgh.json".into())).unwrap();
+
+        assert_eq!(paths.config_file, PathBuf::from("/ci/tgh.json"));
+        assert_eq!(paths.config_dir, PathBuf::from("/ci"));
+        assert_eq!(paths.state_dir, PathBuf::from("/ci/state"));
+    }
+
+    #[test]
+    fn test_missing_home_directory() {
+        let result = resolve(env(&[]), None, None);
+
+        assert!(matches!(result, Err(PathError::NoHomeDirectory)));
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::defines::PROTOCOL;
+
+/// The settings of a GitHub account, which override the top-level settings while it's active.
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+#[serde(default)]
+pub struct Profile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<PROTOCOL>,
+    /// The commit style, `true` for gitmoji labels
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fancy: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ca_bundle: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    /// Remote URL patterns (ex. `acme/*` or `*github.com:acme/*`) that select this profile
+    pub remotes: Vec<String>,
+    /// Directories (and their subdirectories) that select this profile
+    pub directories: Vec<String>,
+}
+
+/// The fields of a profile which can be read and changed with `tgh config`.
+pub const PROFILE_KEYS: [&str; 9] = [
+    "username",
+    "token",
+    "protocol",
+    "fancy",
+    "host",
+    "ca_bundle",
+    "client_id",
+    "remotes",
+    "directories",
+];
+
+impl Profile {
+    /// Returns the settings this profile overrides, as `(key, value)` pairs.
+    pub fn values(&self) -> Vec<(String, String)> {
+        let mut values = Vec::new();
+
+        if let Some(username) = &self.username {
+            values.push(("username".to_string(), username.clone()));
+        }
+        if let Some(token) = &self.token {
+            values.push(("token".to_string(), token.clone()));
+        }
+        if let Some(protocol) = &self.protocol {
+            values.push(("protocol".to_string(), protocol.as_str().to_string()));
+        }
+        if let Some(fancy) = &self.fancy {
+            values.push(("fancy".to_string(), fancy.to_string()));
+        }
+        if let Some(host) = &self.host {
+            values.push(("host".to_string(), host.clone()));
+        }
+        if let Some(ca_bundle) = &self.ca_bundle {
+            values.push(("ca_bundle".to_string(), ca_bundle.clone()));
+        }
+        if let Some(client_id) = &self.client_id {
+            values.push(("client_id".to_string(), client_id.clone()));
+        }
+
+        values
+    }
+}
+
+/// Matches the text against a pattern, where `*` matches any sequence of characters.
+pub fn matches_pattern(pattern: &str, text: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+
+    if !text.starts_with(first) || !text[first.len()..].ends_with(last) {
+        return false;
+    }
+
+    let mut rest = &text[first.len()..text.len() - last.len()];
+
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    true
+}
+
+fn matches_remote(pattern: &str, url: &str) -> bool {
+    if matches_pattern(pattern, url) {
+        return true;
+    }
+
+    // Also match against `owner/repo`, so the same pattern works for SSH and HTTPS remotes
+    match crate::functions::parse_github_url(url) {
+        Some((owner, repo)) => {
+            matches_pattern(pattern, &format!("{}/{}", owner, repo))
+                || matches_pattern(pattern, &owner)
+        }
+        None => false,
+    }
+}
+
+fn expand_home(directory: &str, home: &Option<PathBuf>) -> PathBuf {
+    match (directory.strip_prefix("~/"), home) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(directory),
+    }
+}
+
+/// Picks the profile to use.
+/// An explicitly requested profile wins, then the first profile matching a remote URL,
+/// then the first profile matching the current directory.
+pub fn select_profile(
+    profiles: &BTreeMap<String, Profile>,
+    requested: Option<&str>,
+    remotes: &[String],
+    directory: &Path,
+    home: &Option<PathBuf>,
+) -> Result<Option<String>, String> {
+    if let Some(name) = requested {
+        if !profiles.contains_key(name) {
+            let names: Vec<&str> = profiles.keys().map(String::as_str).collect();
+            return Err(format!(
+                "Unknown profile `{}`, expected one of: {}",
+                name,
+                names.join(", ")
+            ));
+        }
+
+        return Ok(Some(name.to_string()));
+    }
+
+    let by_remote = profiles.iter().find(|(_, profile)| {
+        profile
+            .remotes
+            .iter()
+            .any(|pattern| remotes.iter().any(|url| matches_remote(pattern, url)))
+    });
+
+    if let Some((name, _)) = by_remote {
+        return Ok(Some(name.clone()));
+    }
+
+    let by_directory = profiles.iter().find(|(_, profile)| {
+        profile
+            .directories
+            .iter()
+            .any(|prefix| directory.starts_with(expand_home(prefix, home)))
+    });
+
+    Ok(by_directory.map(|(name, _)| name.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profiles() -> BTreeMap<String, Profile> {
+        let mut profiles = BTreeMap::new();
+
+        profiles.insert(
+            "personal".to_string(),
+            Profile {
+                username: Some("dkomeza".into()),
+                directories: vec!["~/projects".into()],
+                ..Default::default()
+            },
+        );
+        profiles.insert(
+            "work".to_string(),
+            Profile {
+                username: Some("dk-acme".into()),
+                protocol: Some(PROTOCOL::SSH),
+                fancy: Some(false),
+                remotes: vec!["acme/*".into()],
+                directories: vec!["/work".into()],
+                ..Default::default()
+            },
+        );
+
+        profiles
+    }
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("acme/*", "acme/api"));
+        assert!(matches_pattern(
+            "*github.com:acme/*",
+            "git@github.com:acme/api.git"
+        ));
+        assert!(matches_pattern("a*c*e", "abcde"));
+        assert!(matches_pattern("acme", "acme"));
+        assert!(!matches_pattern("acme/*", "other/acme"));
+        assert!(!matches_pattern("a*c*e", "abde"));
+        assert!(!matches_pattern("ab*ba", "aba"));
+    }
+
+    #[test]
+    fn test_select_by_remote() {
+        let home = Some(PathBuf::from("/home/user"));
+        let remotes = vec!["git@github.com:acme/api.git".to_string()];
+
+        // The remote wins over the directory
+        let profile = select_profile(
+            &profiles(),
+            None,
+            &remotes,
+            Path::new("/home/user/projects/api"),
+            &home,
+        );
+
+        assert_eq!(profile, Ok(Some("work".to_string())));
+    }
+
+    #[test]
+    fn test_select_by_directory() {
+        let home = Some(PathBuf::from("/home/user"));
+        let remotes = vec!["https://github.com/dkomeza/tiny-git-helper".to_string()];
+
+        let profile = select_profile(
+            &profiles(),
+            None,
+            &remotes,
+            Path::new("/home/user/projects/tgh"),
+            &home,
+        );
+        assert_eq!(profile, Ok(Some("personal".to_string())));
+
+        // `/workspace` is not inside `/work`
+        let profile = select_profile(&profiles(), None, &[], Path::new("/workspace"), &home);
+        assert_eq!(profile, Ok(None));
+    }
+
+    #[test]
+    fn test_select_requested() {
+        let profile = select_profile(&profiles(), Some("work"), &[], Path::new("/"), &None);
+        assert_eq!(profile, Ok(Some("work".to_string())));
+
+        let profile = select_profile(&profiles(), Some("other"), &[], Path::new("/"), &None);
+        assert!(profile.is_err());
+    }
+
+    #[test]
+    fn test_profile_values() {
+        let values = profiles()["work"].values();
+
+        assert_eq!(
+            values,
+            vec![
+                ("username".to_string(), "dk-acme".to_string()),
+                ("protocol".to_string(), "ssh".to_string()),
+                ("fancy".to_string(), "false".to_string()),
+            ]
+        );
+    }
+}
@@ -1,124 +1,1128 @@
-use self_update::backends::github::{ReleaseList, Update};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
 
-pub async fn check_for_updates() -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-    let update_check = tokio::task::spawn_blocking(|| tokio_check_for_updates())
+use clap::Parser;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use super::defines::CHANNEL;
+use super::Metadata;
+use crate::view::{escape, printer};
+
+// tgh itself is released on github.com, whatever GitHub host is configured
+const REPO_OWNER: &str = "dkomeza";
+const REPO_NAME: &str = "tiny-git-helper";
+const GITHUB_API_URL: &str = "https://api.github.com";
+
+const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The asset with the SHA-256 checksums of the other assets, in the format of `sha256sum`.
+const CHECKSUMS_ASSET: &str = "SHA256SUMS";
+/// The minisign signature of the checksums asset.
+const SIGNATURE_ASSET: &str = "SHA256SUMS.minisig";
+/// The minisign public key of the releases, given when tgh is built (ex. by the release workflow).
+/// Without it, the downloads are only checked against the checksums of the release.
+const PUBLIC_KEY: Option<&str> = option_env!("TGH_UPDATE_PUBLIC_KEY");
+
+/// The name of the binary in the release archives.
+const BIN_NAME: &str = "tgh";
+
+/// How long the background check may take, it's given up after that (ex. when offline).
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// The check started by `check_in_background`, awaited before tgh exits.
+static BACKGROUND_CHECK: Mutex<Option<tokio::task::JoinHandle<()>>> = Mutex::new(None);
+
+#[derive(Parser)]
+pub struct UpdateOptions {
+    /// Only check for a new version, without installing it
+    #[clap(long)]
+    pub check: bool,
+
+    /// The releases to update from (stable or beta), instead of the `update_channel` setting
+    #[clap(long, value_name = "CHANNEL")]
+    pub channel: Option<CHANNEL>,
+
+    /// Install a specific version (ex. `0.2.0`), even if it's older than the current one
+    #[clap(long, value_name = "VERSION")]
+    pub version: Option<String>,
+
+    /// Restore the version replaced by the last update
+    #[clap(long, conflicts_with_all = ["check", "channel", "version"])]
+    pub rollback: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub prerelease: bool,
+    /// The release notes, in Markdown
+    #[serde(default)]
+    pub body: Option<String>,
+    pub assets: Vec<Asset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Asset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+impl Release {
+    /// The version of the release, from its tag (ex. `v0.2.0` -> `0.2.0`).
+    pub fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+
+    fn semver(&self) -> Option<semver::Version> {
+        semver::Version::parse(self.version()).ok()
+    }
+
+    /// Whether the release is on the channel, the pre-releases are only on the beta channel.
+    fn is_on(&self, channel: CHANNEL) -> bool {
+        match channel {
+            CHANNEL::STABLE => {
+                !self.prerelease && self.semver().is_some_and(|version| version.pre.is_empty())
+            }
+            CHANNEL::BETA => true,
+        }
+    }
+
+    fn asset(&self, name: &str) -> Option<&Asset> {
+        self.assets.iter().find(|asset| asset.name == name)
+    }
+
+    /// The binary (or the archive with the binary) built for the target, ex. `x86_64-unknown-linux-gnu`.
+    fn binary_for(&self, target: &str) -> Option<&Asset> {
+        self.assets
+            .iter()
+            .find(|asset| asset.name.contains(target) && !asset.name.ends_with(".minisig"))
+    }
+}
+
+/// Where the releases are fetched from, and the key they are signed with.
+pub struct ReleaseSource {
+    pub api_url: String,
+    pub owner: String,
+    pub repo: String,
+    pub public_key: Option<String>,
+}
+
+impl ReleaseSource {
+    /// The releases of tgh on github.com.
+    pub fn github() -> Self {
+        ReleaseSource {
+            api_url: GITHUB_API_URL.to_string(),
+            owner: REPO_OWNER.to_string(),
+            repo: REPO_NAME.to_string(),
+            // An unset variable of the release workflow is given as an empty key
+            public_key: PUBLIC_KEY
+                .filter(|key| !key.trim().is_empty())
+                .map(String::from),
+        }
+    }
+
+    fn releases_url(&self) -> String {
+        format!(
+            "{}/repos/{}/{}/releases?per_page=100",
+            self.api_url, self.owner, self.repo
+        )
+    }
+}
+
+#[derive(Debug)]
+pub enum UpdateError {
+    Request(String),
+    /// The release has no binary for the platform or no signature
+    MissingAsset(String),
+    /// The release was published without checksums (ex. before they were added), with its tag
+    Unverifiable(String),
+    /// The checksums of the release don't list the asset
+    NoChecksum(String),
+    ChecksumMismatch {
+        asset: String,
+        expected: String,
+        actual: String,
+    },
+    /// The checksums aren't signed with the release key
+    Signature(String),
+    Install(String),
+    /// No update has kept a previous version yet
+    NoBackup,
+}
+
+impl std::fmt::Display for UpdateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            UpdateError::Request(err) => write!(f, "{}", err),
+            UpdateError::MissingAsset(asset) => write!(f, "The release has no {}", asset),
+            UpdateError::Unverifiable(tag) => write!(
+                f,
+                "{} was released without {}, so its download can't be verified. Install it by hand from https://github.com/{}/{}/releases/tag/{}",
+                tag, CHECKSUMS_ASSET, REPO_OWNER, REPO_NAME, tag
+            ),
+            UpdateError::NoChecksum(asset) => {
+                write!(f, "{} has no checksum in {}", asset, CHECKSUMS_ASSET)
+            }
+            UpdateError::ChecksumMismatch {
+                asset,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "The checksum of {} doesn't match (expected {}, got {})",
+                asset, expected, actual
+            ),
+            UpdateError::Signature(err) => {
+                write!(
+                    f,
+                    "The signature of {} is invalid: {}",
+                    CHECKSUMS_ASSET, err
+                )
+            }
+            UpdateError::Install(err) => write!(f, "Failed to install the binary: {}", err),
+            UpdateError::NoBackup => write!(f, "No previous version was kept by an update"),
+        }
+    }
+}
+
+impl std::error::Error for UpdateError {}
+
+impl From<reqwest::Error> for UpdateError {
+    fn from(err: reqwest::Error) -> Self {
+        UpdateError::Request(err.to_string())
+    }
+}
+
+impl From<super::paths::PathError> for UpdateError {
+    fn from(err: super::paths::PathError) -> Self {
+        UpdateError::Install(err.to_string())
+    }
+}
+
+impl From<std::io::Error> for UpdateError {
+    fn from(err: std::io::Error) -> Self {
+        UpdateError::Install(err.to_string())
+    }
+}
+
+/// A release asset, checked against the checksums of the release.
+pub struct VerifiedAsset {
+    pub name: String,
+    pub bytes: Vec<u8>,
+}
+
+pub async fn fetch_releases(
+    client: &reqwest::Client,
+    source: &ReleaseSource,
+) -> Result<Vec<Release>, UpdateError> {
+    let response = client
+        .get(source.releases_url())
+        .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(response.json().await?)
+}
+
+/// Returns the latest release on the channel.
+pub fn latest_release(releases: &[Release], channel: CHANNEL) -> Option<&Release> {
+    releases
+        .iter()
+        .filter(|release| release.is_on(channel))
+        .filter_map(|release| Some((release.semver()?, release)))
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, release)| release)
+}
+
+/// Returns the release of the version (ex. `0.2.0` or `v0.2.0`), whatever its channel.
+pub fn find_release<'a>(releases: &'a [Release], version: &str) -> Option<&'a Release> {
+    let version = semver::Version::parse(version.trim().trim_start_matches('v')).ok()?;
+
+    releases
+        .iter()
+        .find(|release| release.semver().as_ref() == Some(&version))
+}
+
+pub fn is_newer(version: &str, current: &str) -> bool {
+    match (
+        semver::Version::parse(version),
+        semver::Version::parse(current),
+    ) {
+        (Ok(version), Ok(current)) => version > current,
+        _ => false,
+    }
+}
+
+/// The markup telling about a new version, and the command installing it.
+fn update_notice(version: &str, command: &str) -> String {
+    let update_msg = format!(
+        r#"
+        $@success `📦 New Update Available`
+         &> $cy `{}` $cw `➜` $@success `{}`
+         &> Run $@highlight `{}` $cw `to upgrade`
+        "#,
+        CURRENT_VERSION, version, command
+    );
+
+    crate::view::unindent(&update_msg)
+}
+
+/// Returns the notice of the newer version found by the last check of the channel, if any.
+/// It's shown from the metadata, without a request.
+pub fn cached_notice(metadata: &Metadata, channel: CHANNEL) -> Option<String> {
+    let latest = metadata.latest_version.as_deref()?;
+
+    match metadata.latest_channel == Some(channel) && is_newer(latest, CURRENT_VERSION) {
+        true => Some(update_notice(latest, "tgh update")),
+        false => None,
+    }
+}
+
+/// Fetches the version of the latest release on the channel, giving up after the timeout.
+async fn fetch_latest(
+    client: &reqwest::Client,
+    source: &ReleaseSource,
+    channel: CHANNEL,
+    timeout: Duration,
+) -> Result<Option<String>, UpdateError> {
+    let releases = tokio::time::timeout(timeout, fetch_releases(client, source))
         .await
-        .expect("Blocking task panicked");
+        .map_err(|_| UpdateError::Request("The releases didn't load in time".into()))??;
 
-    update_check
+    Ok(latest_release(&releases, channel).map(|release| release.version().to_string()))
 }
 
-fn tokio_check_for_updates() -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-    let current_version = env!("CARGO_PKG_VERSION");
+/// Records a check of the channel, with the latest version it found.
+/// A failed check (ex. when offline or rate limited) keeps the version found before,
+/// and still counts as a check, so it's only tried again the next day.
+fn record_check(
+    metadata: &mut Metadata,
+    channel: CHANNEL,
+    latest: Result<Option<String>, UpdateError>,
+) {
+    metadata.last_checked = chrono::Utc::now().to_rfc3339();
+    metadata.notified = false;
+
+    match latest {
+        Ok(latest) => metadata.latest_version = latest,
+        // The version found before is from another channel
+        Err(_) if metadata.latest_channel != Some(channel) => metadata.latest_version = None,
+        Err(_) => {}
+    }
+
+    metadata.latest_channel = Some(channel);
+}
 
-    let releases = ReleaseList::configure()
-        .repo_owner("dkomeza")
-        .repo_name("tiny-git-helper")
-        .build()
-        .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?
-        .fetch()
-        .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
+/// Saves the check in the metadata, so the next runs can show its result without a request.
+fn save_check(channel: CHANNEL, latest: Result<Option<String>, UpdateError>) {
+    let mut metadata = super::utils::read_metadata();
+    record_check(&mut metadata, channel, latest);
 
-    let latest_release = releases
+    super::utils::save_metadata_file(metadata);
+}
+
+/// Checks for a new version in a background task, the next runs show the result.
+/// Failures are silent, the check isn't worth an error on every command.
+pub fn check_in_background(channel: CHANNEL) {
+    let client = super::github::download_client();
+
+    let check = tokio::spawn(async move {
+        let source = ReleaseSource::github();
+        let latest = fetch_latest(&client, &source, channel, CHECK_TIMEOUT).await;
+
+        save_check(channel, latest);
+    });
+
+    *BACKGROUND_CHECK.lock().unwrap() = Some(check);
+}
+
+/// Waits for the background check, which gives up after `CHECK_TIMEOUT`.
+pub async fn wait_for_background_check() {
+    let check = BACKGROUND_CHECK.lock().unwrap().take();
+
+    if let Some(check) = check {
+        let _ = check.await;
+    }
+}
+
+/// Returns the releases newer than `from`, up to the installed release, newest first.
+/// The releases of other channels are skipped (ex. the betas before a stable release).
+pub fn releases_between<'a>(
+    releases: &'a [Release],
+    from: &str,
+    to: &Release,
+    channel: CHANNEL,
+) -> Vec<&'a Release> {
+    let mut between: Vec<(semver::Version, &Release)> = releases
         .iter()
-        .filter(|r| !r.version.contains("alpha") && !r.version.contains("beta"))
-        .max_by(|a, b| {
-            let version_a =
-                semver::Version::parse(&a.version).unwrap_or(semver::Version::new(0, 0, 0));
-            let version_b =
-                semver::Version::parse(&b.version).unwrap_or(semver::Version::new(0, 0, 0));
-            version_a.cmp(&version_b)
-        });
+        .filter(|release| release.tag_name == to.tag_name || release.is_on(channel))
+        .filter(|release| {
+            is_newer(release.version(), from) && !is_newer(release.version(), to.version())
+        })
+        .filter_map(|release| Some((release.semver()?, release)))
+        .collect();
+    between.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+    between.into_iter().map(|(_, release)| release).collect()
+}
+
+/// Renders the notes of the releases, with the Markdown headings in bold and bullets for the lists.
+/// The rest of the Markdown is printed as it is.
+pub fn render_notes(releases: &[&Release]) -> String {
+    let mut notes = String::from("\n$b `📝 Release notes`\n");
 
-    if let Some(release) = latest_release {
-        let current = semver::Version::parse(current_version)
-            .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
-        let latest = semver::Version::parse(&release.version)
-            .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
-
-        if latest > current {
-            let update_msg = format!(
-                r#"
-                $cg$b `📦 New Update Available`
-                 &> $cy `{}` $cw `➜` $cg$b `{}`
-                 &> Run $cc$i `tgh update` $cw `to upgrade`
-                "#,
-                current_version, release.version
-            );
-            return Ok(update_msg);
+    for release in releases {
+        notes.push_str(&format!("\n$@highlight `{}`\n", escape(&release.tag_name)));
+
+        let body = release.body.as_deref().unwrap_or_default().trim();
+        if body.is_empty() {
+            notes.push_str("&> $s `No release notes`\n");
+            continue;
+        }
+
+        for line in body.lines() {
+            let line = line.trim_end();
+            let content = line.trim_start();
+            let indent = &line[..line.len() - content.len()];
+
+            // `## Title`, but not `#hashtag`
+            let heading = content
+                .trim_start_matches('#')
+                .strip_prefix(' ')
+                .filter(|_| content.starts_with('#'));
+            let item = content
+                .strip_prefix("- ")
+                .or_else(|| content.strip_prefix("* "));
+
+            let markup = match (heading, item) {
+                _ if content.is_empty() => String::new(),
+                (Some(heading), _) => format!("&> $b `{}`", escape(heading.trim())),
+                (None, Some(item)) => format!("&> {}• {}", indent, escape(item)),
+                (None, None) => format!("&> {}", escape(line)),
+            };
+            notes.push_str(&markup);
+            notes.push('\n');
         }
     }
 
-    Ok("".into())
+    notes
 }
 
-pub async fn perform_self_update() {
-    use crate::view::printer;
-    let current_ver = env!("CARGO_PKG_VERSION");
+async fn download(client: &reqwest::Client, asset: &Asset) -> Result<Vec<u8>, UpdateError> {
+    let response = client
+        .get(&asset.browser_download_url)
+        .send()
+        .await?
+        .error_for_status()?;
 
-    printer(format!(
-        "\n$cb$b `🔍 Checking for updates...`\n&> $cw `Current version:` $cy `{}`\n",
-        current_ver
-    ));
+    Ok(response.bytes().await?.to_vec())
+}
+
+/// Parses the checksums written by `sha256sum`, ex. `<hash>  tgh-x86_64-apple-darwin.tar.gz`.
+/// Returns the hashes (in lowercase) by file name.
+pub fn parse_checksums(text: &str) -> HashMap<String, String> {
+    text.lines()
+        .filter_map(|line| {
+            let (hash, name) = line.trim().split_once(char::is_whitespace)?;
+            // `sha256sum --binary` marks the names with a `*`
+            let name = name.trim_start().trim_start_matches('*');
+
+            Some((name.to_string(), hash.to_lowercase()))
+        })
+        .collect()
+}
+
+pub fn sha256(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Verifies the minisign signature of the data, with a public key in base64.
+pub fn verify_signature(public_key: &str, data: &[u8], signature: &str) -> Result<(), UpdateError> {
+    let public_key = minisign_verify::PublicKey::from_base64(public_key.trim())
+        .map_err(|err| UpdateError::Signature(err.to_string()))?;
+    let signature = minisign_verify::Signature::decode(signature)
+        .map_err(|err| UpdateError::Signature(err.to_string()))?;
+
+    public_key
+        .verify(data, &signature, false)
+        .map_err(|err| UpdateError::Signature(err.to_string()))
+}
+
+/// Downloads the binary of the release for the target.
+/// The checksums are verified against the signature first (when the source has a key),
+/// then the binary against the checksums.
+pub async fn download_verified(
+    client: &reqwest::Client,
+    source: &ReleaseSource,
+    release: &Release,
+    target: &str,
+) -> Result<VerifiedAsset, UpdateError> {
+    let binary = release
+        .binary_for(target)
+        .ok_or_else(|| UpdateError::MissingAsset(format!("binary for {}", target)))?;
+    // The releases before the checksums can't be verified, so they aren't installed
+    let checksums = release
+        .asset(CHECKSUMS_ASSET)
+        .ok_or_else(|| UpdateError::Unverifiable(release.tag_name.clone()))?;
+    let checksums = download(client, checksums).await?;
+
+    if let Some(public_key) = &source.public_key {
+        let signature = release
+            .asset(SIGNATURE_ASSET)
+            .ok_or_else(|| UpdateError::MissingAsset(SIGNATURE_ASSET.to_string()))?;
+        let signature = download(client, signature).await?;
+
+        verify_signature(public_key, &checksums, &String::from_utf8_lossy(&signature))?;
+    }
+
+    let expected = match parse_checksums(&String::from_utf8_lossy(&checksums)).remove(&binary.name)
+    {
+        Some(expected) => expected,
+        None => return Err(UpdateError::NoChecksum(binary.name.clone())),
+    };
+
+    let bytes = download(client, binary).await?;
+    let actual = sha256(&bytes);
+    if actual != expected {
+        return Err(UpdateError::ChecksumMismatch {
+            asset: binary.name.clone(),
+            expected,
+            actual,
+        });
+    }
+
+    Ok(VerifiedAsset {
+        name: binary.name.clone(),
+        bytes,
+    })
+}
+
+fn bin_file_name() -> String {
+    format!("{}{}", BIN_NAME, std::env::consts::EXE_SUFFIX)
+}
+
+/// Writes the asset to the directory and extracts the binary from it (a plain binary is copied).
+fn extract_binary(asset: &VerifiedAsset, dir: &Path) -> Result<PathBuf, UpdateError> {
+    let archive = dir.join(&asset.name);
+    std::fs::write(&archive, &asset.bytes)?;
+
+    let into = dir.join("extracted");
+    std::fs::create_dir_all(&into)?;
+    self_update::Extract::from_source(&archive)
+        .extract_file(&into, bin_file_name())
+        .map_err(|err| UpdateError::Install(err.to_string()))?;
+
+    Ok(into.join(bin_file_name()))
+}
+
+/// The directory keeping the binary replaced by the last update, for `tgh update --rollback`.
+fn backup_dir() -> Result<PathBuf, UpdateError> {
+    Ok(super::paths::state_file("backup")?)
+}
 
-    let update_available = check_for_updates().await;
+/// Copies the binary to the backup directory, with its version.
+fn save_backup(binary: &Path, version: &str, dir: &Path) -> Result<(), UpdateError> {
+    std::fs::create_dir_all(dir)?;
+    std::fs::copy(binary, dir.join(bin_file_name()))?;
+    std::fs::write(dir.join("version"), version)?;
 
-    match update_available {
-        Ok(msg) => {
-            if msg.is_empty() {
+    Ok(())
+}
+
+/// Returns the backed up binary and its version.
+fn read_backup(dir: &Path) -> Result<(PathBuf, String), UpdateError> {
+    let binary = dir.join(bin_file_name());
+    if !binary.is_file() {
+        return Err(UpdateError::NoBackup);
+    }
+
+    let version = std::fs::read_to_string(dir.join("version")).unwrap_or_default();
+
+    Ok((binary, version.trim().to_string()))
+}
+
+/// Replaces the running binary with the one of the asset, keeping the current one as the backup.
+fn install(asset: &VerifiedAsset, backup: &Path) -> Result<(), UpdateError> {
+    let temp_dir = self_update::TempDir::new()?;
+    let binary = extract_binary(asset, temp_dir.path())?;
+
+    save_backup(&std::env::current_exe()?, CURRENT_VERSION, backup)?;
+    self_update::self_replace::self_replace(&binary)?;
+
+    Ok(())
+}
+
+/// Swaps the running binary with the backup, so a second rollback restores the update.
+/// Returns the restored version.
+fn rollback(backup: &Path) -> Result<String, UpdateError> {
+    let (binary, version) = read_backup(backup)?;
+
+    let temp_dir = self_update::TempDir::new()?;
+    let restored = temp_dir.path().join(bin_file_name());
+    std::fs::copy(&binary, &restored)?;
+
+    save_backup(&std::env::current_exe()?, CURRENT_VERSION, backup)?;
+    self_update::self_replace::self_replace(&restored)?;
+
+    Ok(version)
+}
+
+pub async fn perform_self_update(options: UpdateOptions) {
+    if options.rollback {
+        let result = tokio::task::spawn_blocking(|| rollback(&backup_dir()?))
+            .await
+            .expect("Blocking task panicked");
+
+        match result {
+            Ok(version) => {
                 printer(format!(
-                    "$cg$b `✔ You are already up to date.`\n&> $cw `Version:` $cg `{}`\n",
-                    current_ver
+                    "\n$@success `✔ Rolled back` $cw `from` $cy `{}` $cw `to` $cg `{}`\n&> $cw `Run` $@highlight `tgh update --rollback` $cw `again to undo it.`\n",
+                    CURRENT_VERSION, version
+                ));
+            }
+            Err(err) => {
+                printer(format!(
+                    "\n$@error `✖ Rollback Failed`\n&> $cr `Reason:` $cw `{}`\n",
+                    err
                 ));
-                return;
-            } else {
-                printer("\n$cy$b `⬇ Update found! Starting download...`\n");
             }
         }
+        return;
+    }
+
+    let channel = match options.channel {
+        Some(channel) => channel,
+        None => super::load_config().update_channel,
+    };
+
+    printer(format!(
+        "\n$cb$b `🔍 Checking for updates...`\n&> $cw `Current version:` $cy `{}`\n&> $cw `Channel:` $cy `{}`\n",
+        CURRENT_VERSION,
+        channel.as_str()
+    ));
+
+    let client = super::github::download_client();
+    let source = ReleaseSource::github();
+
+    let releases = match fetch_releases(&client, &source).await {
+        Ok(releases) => releases,
         Err(e) => {
             printer(format!(
-                "\n$cr$b `✖ Failed to check for updates`\n&> $cr `Error:` $cw `{}`\n",
+                "\n$@error `✖ Failed to check for updates`\n&> $cr `Error:` $cw `{}`\n",
                 e
             ));
             return;
         }
+    };
+
+    let latest = latest_release(&releases, channel);
+    save_check(
+        channel,
+        Ok(latest.map(|release| release.version().to_string())),
+    );
+
+    let release = match &options.version {
+        Some(version) => match find_release(&releases, version) {
+            Some(release) if release.version() == CURRENT_VERSION => {
+                printer(format!(
+                    "$@success `✔ Version {} is already installed.`\n",
+                    CURRENT_VERSION
+                ));
+                return;
+            }
+            Some(release) => release,
+            None => {
+                printer(format!(
+                    "\n$@error `✖ No release of version {}`\n",
+                    version.trim()
+                ));
+                return;
+            }
+        },
+        None => match latest.filter(|release| is_newer(release.version(), CURRENT_VERSION)) {
+            Some(release) => release,
+            None => {
+                printer(format!(
+                    "$@success `✔ You are already up to date.`\n&> $cw `Version:` $cg `{}`\n",
+                    CURRENT_VERSION
+                ));
+                return;
+            }
+        },
+    };
+
+    if options.check {
+        let mut command = "tgh update".to_string();
+        if let Some(channel) = options.channel {
+            command.push_str(&format!(" --channel {}", channel.as_str()));
+        }
+        if options.version.is_some() {
+            command.push_str(&format!(" --version {}", release.version()));
+        }
+
+        printer(update_notice(release.version(), &command));
+        return;
     }
 
-    let update_result = tokio::task::spawn_blocking(|| execute_update_logic())
-        .await
-        .expect("Blocking task panicked");
+    printer("\n$@warning `⬇ Update found! Starting download...`\n");
+
+    let update_result =
+        match download_verified(&client, &source, release, self_update::get_target()).await {
+            Ok(asset) => {
+                let verified = match source.public_key {
+                    Some(_) => "checksum and signature",
+                    None => "checksum",
+                };
+                printer(format!(
+                    "&> $cw `Verified the {} of` $cy `{}`\n",
+                    verified, asset.name
+                ));
+
+                tokio::task::spawn_blocking(move || install(&asset, &backup_dir()?))
+                    .await
+                    .expect("Blocking task panicked")
+            }
+            Err(err) => Err(err),
+        };
 
-    // 4. Report Final Status
+    // Report Final Status
     match update_result {
-        Ok(new_version) => {
+        Ok(()) => {
             printer(format!(
-                "\n$cg$b `✨ Update Successful!`\n&> $cw `New version:` $cg `{}`\n&> $cw `Please restart the terminal to use the new version.`\n",
-                new_version
+                "\n$@success `✨ Update Successful!`\n&> $cw `New version:` $cg `{}`\n&> $cw `Please restart the terminal to use the new version.`\n&> $cw `Run` $@highlight `tgh update --rollback` $cw `to go back to {}.`\n",
+                release.version(),
+                CURRENT_VERSION
             ));
+
+            let notes = releases_between(&releases, CURRENT_VERSION, release, channel);
+            if !notes.is_empty() {
+                printer(render_notes(&notes));
+            }
         }
         Err(err) => {
             printer(format!(
-                "\n$cr$b `✖ Update Failed`\n&> $cr `Reason:` $cw `{}`\n",
+                "\n$@error `✖ Update Failed`\n&> $cr `Reason:` $cw `{}`\n",
                 err
             ));
         }
     }
 }
 
-// The internal blocking logic
-fn execute_update_logic() -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-    let status = Update::configure()
-        .repo_owner("dkomeza")
-        .repo_name("tiny-git-helper")
-        .bin_name("tgh")
-        .show_download_progress(true)
-        .current_version(env!("CARGO_PKG_VERSION"))
-        .no_confirm(true)
-        .build()
-        .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?
-        .update()
-        .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    const TARGET: &str = "x86_64-unknown-linux-gnu";
+    const BINARY: &[u8] = b"tgh 9.9.9\n";
+    const CHECKSUMS: &str =
+        "9ba42b0fe19de0472de1ca670bdd5a3fa75b1e42ed015d7a408dd65187dcd497  tgh-x86_64-unknown-linux-gnu\n";
+
+    /// A test key, and the signature of `CHECKSUMS` made with it.
+    const PUBLIC_KEY: &str = "RWR0Z2h0ZXN0Mb31k7ij1i++HVVTIwGkrD4vreLoqXTFOKBA5B14tfau";
+    const SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RUR0Z2h0ZXN0MX6AD5wtxbayB9kWWaAqsgfC7aOYlVH7DMBZv/UJmED+aiZ8538NQb8I8kLiPGm1fSZh4fRjpNC65b59IH/nxwU=
+trusted comment: timestamp:1760000000\tfile:SHA256SUMS
+tNpq3ED7AzZO8175MllK2t/ENrp3Ys20wkX+XwWYobMVctDZ2IyX1UzCQo/PZYx6E75KOf6OBrLdQwmygr3KAg==
+";
+
+    /// Starts a server standing in for the releases API, with a `v9.9.9` release of the assets.
+    /// Returns the source of the releases, verified with the test key.
+    fn release_server(assets: Vec<(&'static str, Vec<u8>)>) -> ReleaseSource {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let host = format!("http://{}", listener.local_addr().unwrap());
+
+        let release = serde_json::json!([{
+            "tag_name": "v9.9.9",
+            "assets": assets.iter().map(|(name, _)| serde_json::json!({
+                "name": name,
+                "browser_download_url": format!("{}/download/{}", host, name),
+            })).collect::<Vec<_>>(),
+        }]);
+
+        let mut files: HashMap<String, Vec<u8>> = assets
+            .into_iter()
+            .map(|(name, bytes)| (format!("/download/{}", name), bytes))
+            .collect();
+        files.insert(
+            "/repos/dkomeza/tiny-git-helper/releases".into(),
+            release.to_string().into_bytes(),
+        );
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+
+                // Skip the headers, the requests have no bodies
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+
+                let path = request_line.split(' ').nth(1).unwrap_or_default();
+                let path = path.split('?').next().unwrap_or_default();
+
+                let (status, body) = match files.get(path) {
+                    Some(body) => ("200 OK", body.clone()),
+                    None => ("404 Not Found", b"Not Found".to_vec()),
+                };
+
+                let header = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    body.len()
+                );
+                let _ = stream.write_all(header.as_bytes());
+                let _ = stream.write_all(&body);
+            }
+        });
+
+        ReleaseSource {
+            api_url: host,
+            owner: REPO_OWNER.into(),
+            repo: REPO_NAME.into(),
+            public_key: Some(PUBLIC_KEY.into()),
+        }
+    }
+
+    async fn fetch_update(source: &ReleaseSource) -> Result<VerifiedAsset, UpdateError> {
+        let client = reqwest::Client::new();
+        let releases = fetch_releases(&client, source).await?;
+        let release = latest_release(&releases, CHANNEL::STABLE).unwrap();
+
+        download_verified(&client, source, release, TARGET).await
+    }
+
+    #[test]
+    fn test_parse_checksums() {
+        let checksums = parse_checksums(
+            "ABC123  tgh-x86_64-apple-darwin.tar.gz\ndef456 *tgh-x86_64-pc-windows-msvc.zip\n\n",
+        );
+
+        assert_eq!(checksums.len(), 2);
+        assert_eq!(checksums["tgh-x86_64-apple-darwin.tar.gz"], "abc123");
+        assert_eq!(checksums["tgh-x86_64-pc-windows-msvc.zip"], "def456");
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        assert_eq!(sha256(BINARY), &CHECKSUMS[..64]);
+        assert!(verify_signature(PUBLIC_KEY, CHECKSUMS.as_bytes(), SIGNATURE).is_ok());
+
+        let tampered = CHECKSUMS.replace("9ba4", "0000");
+        assert!(matches!(
+            verify_signature(PUBLIC_KEY, tampered.as_bytes(), SIGNATURE),
+            Err(UpdateError::Signature(_))
+        ));
+        assert!(verify_signature("not a key", CHECKSUMS.as_bytes(), SIGNATURE).is_err());
+    }
+
+    fn release(tag: &str, prerelease: bool) -> Release {
+        Release {
+            tag_name: tag.into(),
+            prerelease,
+            body: None,
+            assets: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_latest_release() {
+        let releases = vec![
+            release("v0.1.7", false),
+            release("v0.3.0-beta.1", false),
+            release("v0.2.5", true),
+            release("v0.2.0", false),
+            release("nightly", true),
+        ];
 
-    // Return the new version string
-    Ok(status.version().to_string())
+        let latest = |channel| latest_release(&releases, channel).map(Release::version);
+        assert_eq!(latest(CHANNEL::STABLE), Some("0.2.0"));
+        assert_eq!(latest(CHANNEL::BETA), Some("0.3.0-beta.1"));
+
+        assert_eq!(
+            find_release(&releases, "v0.2.5").map(Release::version),
+            Some("0.2.5")
+        );
+        assert!(find_release(&releases, "0.2.1").is_none());
+        assert!(find_release(&releases, "latest").is_none());
+
+        assert!(is_newer("0.2.0", "0.1.7"));
+        assert!(is_newer("0.2.0", "0.2.0-beta.1"));
+        assert!(!is_newer("0.2.0-beta.1", "0.2.0"));
+        assert!(!is_newer("nightly", "0.1.7"));
+    }
+
+    #[test]
+    fn test_cached_notice() {
+        let metadata = |latest: &str, channel| Metadata {
+            latest_version: Some(latest.into()),
+            latest_channel: Some(channel),
+            ..Default::default()
+        };
+
+        let notice = cached_notice(&metadata("999.0.0", CHANNEL::STABLE), CHANNEL::STABLE);
+        assert!(notice.unwrap().contains("999.0.0"));
+
+        // The result of another channel, or of an older check, isn't shown
+        assert!(cached_notice(&metadata("999.0.0", CHANNEL::BETA), CHANNEL::STABLE).is_none());
+        assert!(cached_notice(&metadata("0.0.1", CHANNEL::STABLE), CHANNEL::STABLE).is_none());
+        assert!(cached_notice(&Metadata::default(), CHANNEL::STABLE).is_none());
+    }
+
+    #[test]
+    fn test_record_check() {
+        let mut metadata = Metadata {
+            notified: true,
+            ..Default::default()
+        };
+
+        record_check(&mut metadata, CHANNEL::STABLE, Ok(Some("0.2.0".into())));
+        assert_eq!(metadata.latest_version.as_deref(), Some("0.2.0"));
+        // The result of a new check is shown again
+        assert!(!metadata.notified);
+        assert!(!crate::config::utils::should_check_for_updates(
+            &metadata,
+            CHANNEL::STABLE
+        ));
+
+        // A failed check keeps the version, and isn't retried until the next day
+        metadata.last_checked = "2024-01-01T00:00:00+00:00".into();
+        let failure = || Err(UpdateError::Request("offline".into()));
+        record_check(&mut metadata, CHANNEL::STABLE, failure());
+        assert_eq!(metadata.latest_version.as_deref(), Some("0.2.0"));
+        assert!(!crate::config::utils::should_check_for_updates(
+            &metadata,
+            CHANNEL::STABLE
+        ));
+
+        assert!(crate::config::utils::should_check_for_updates(
+            &metadata,
+            CHANNEL::BETA
+        ));
+        record_check(&mut metadata, CHANNEL::BETA, failure());
+        assert_eq!(metadata.latest_version, None);
+        assert_eq!(metadata.latest_channel, Some(CHANNEL::BETA));
+    }
+
+    #[test]
+    fn test_releases_between() {
+        let releases = vec![
+            release("v0.1.7", false),
+            release("v0.1.8", false),
+            release("v0.2.0-beta.1", true),
+            release("v0.2.0", false),
+            release("v0.3.0", false),
+        ];
+
+        let tags = |to: usize, channel| -> Vec<&str> {
+            releases_between(&releases, "0.1.7", &releases[to], channel)
+                .iter()
+                .map(|release| release.tag_name.as_str())
+                .collect()
+        };
+
+        assert_eq!(tags(3, CHANNEL::STABLE), vec!["v0.2.0", "v0.1.8"]);
+        assert_eq!(
+            tags(3, CHANNEL::BETA),
+            vec!["v0.2.0", "v0.2.0-beta.1", "v0.1.8"]
+        );
+        assert_eq!(tags(2, CHANNEL::STABLE), vec!["v0.2.0-beta.1", "v0.1.8"]);
+        assert!(tags(0, CHANNEL::STABLE).is_empty());
+    }
+
+    #[test]
+    fn test_render_notes() {
+        let notes = Release {
+            body: Some(
+                "## What's new\r\n\n- Faster `tgh stats`\n  * 50% less $memory\n#hashtag\n".into(),
+            ),
+            ..release("v0.2.0", false)
+        };
+        let empty = release("v0.1.8", false);
+
+        assert_eq!(
+            render_notes(&[&notes, &empty]),
+            [
+                "\n$b `📝 Release notes`\n",
+                "\n$@highlight `v0.2.0`\n",
+                "&> $b `What's new`\n",
+                "\n",
+                "&> • Faster \\`tgh stats\\`\n",
+                "&>   • 50% less \\$memory\n",
+                "&> #hashtag\n",
+                "\n$@highlight `v0.1.8`\n",
+                "&> $s `No release notes`\n",
+            ]
+            .concat()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_latest() {
+        let source = release_server(Vec::new());
+        let client = reqwest::Client::new();
+        let timeout = Duration::from_secs(5);
+
+        let latest = fetch_latest(&client, &source, CHANNEL::STABLE, timeout).await;
+        assert_eq!(latest.unwrap().as_deref(), Some("9.9.9"));
+
+        // A server which never answers, the check gives up
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let source = ReleaseSource {
+            api_url: format!("http://{}", listener.local_addr().unwrap()),
+            ..source
+        };
+        let timeout = Duration::from_millis(200);
+
+        let latest = fetch_latest(&client, &source, CHANNEL::STABLE, timeout).await;
+        assert!(matches!(latest, Err(UpdateError::Request(_))));
+    }
+
+    #[tokio::test]
+    async fn test_download_verified() {
+        let source = release_server(vec![
+            ("tgh-x86_64-unknown-linux-gnu", BINARY.to_vec()),
+            ("tgh-aarch64-apple-darwin", b"other".to_vec()),
+            (CHECKSUMS_ASSET, CHECKSUMS.as_bytes().to_vec()),
+            (SIGNATURE_ASSET, SIGNATURE.as_bytes().to_vec()),
+        ]);
+
+        let asset = fetch_update(&source).await.unwrap();
+        assert_eq!(asset.name, "tgh-x86_64-unknown-linux-gnu");
+        assert_eq!(asset.bytes, BINARY);
+
+        let dir = self_update::TempDir::new().unwrap();
+        let binary = extract_binary(&asset, dir.path()).unwrap();
+        assert_eq!(std::fs::read(binary).unwrap(), BINARY);
+    }
+
+    #[tokio::test]
+    async fn test_tampered_binary() {
+        let source = release_server(vec![
+            ("tgh-x86_64-unknown-linux-gnu", b"tgh 6.6.6\n".to_vec()),
+            (CHECKSUMS_ASSET, CHECKSUMS.as_bytes().to_vec()),
+            (SIGNATURE_ASSET, SIGNATURE.as_bytes().to_vec()),
+        ]);
+
+        assert!(matches!(
+            fetch_update(&source).await,
+            Err(UpdateError::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_tampered_checksums() {
+        let tampered = format!("{}  tgh-x86_64-unknown-linux-gnu\n", sha256(b"tgh 6.6.6\n"));
+        let source = release_server(vec![
+            ("tgh-x86_64-unknown-linux-gnu", b"tgh 6.6.6\n".to_vec()),
+            (CHECKSUMS_ASSET, tampered.clone().into_bytes()),
+            (SIGNATURE_ASSET, SIGNATURE.as_bytes().to_vec()),
+        ]);
+
+        assert!(matches!(
+            fetch_update(&source).await,
+            Err(UpdateError::Signature(_))
+        ));
+
+        // Without a key, only the checksums are verified
+        let source = ReleaseSource {
+            public_key: None,
+            ..release_server(vec![
+                ("tgh-x86_64-unknown-linux-gnu", b"tgh 6.6.6\n".to_vec()),
+                (CHECKSUMS_ASSET, tampered.into_bytes()),
+            ])
+        };
+        assert!(fetch_update(&source).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_missing_assets() {
+        let source = release_server(vec![
+            ("tgh-x86_64-unknown-linux-gnu", BINARY.to_vec()),
+            (CHECKSUMS_ASSET, CHECKSUMS.as_bytes().to_vec()),
+        ]);
+        assert!(matches!(
+            fetch_update(&source).await,
+            Err(UpdateError::MissingAsset(asset)) if asset == SIGNATURE_ASSET
+        ));
+
+        // An older release, without checksums
+        let source = release_server(vec![("tgh-x86_64-unknown-linux-gnu", BINARY.to_vec())]);
+        assert!(matches!(
+            fetch_update(&source).await,
+            Err(UpdateError::Unverifiable(tag)) if tag == "v9.9.9"
+        ));
+
+        let source = release_server(vec![
+            ("tgh-x86_64-unknown-linux-gnu", BINARY.to_vec()),
+            (
+                CHECKSUMS_ASSET,
+                b"abc123  tgh-aarch64-apple-darwin\n".to_vec(),
+            ),
+            (SIGNATURE_ASSET, SIGNATURE.as_bytes().to_vec()),
+        ]);
+        let source = ReleaseSource {
+            public_key: None,
+            ..source
+        };
+        assert!(matches!(
+            fetch_update(&source).await,
+            Err(UpdateError::NoChecksum(_))
+        ));
+    }
+
+    #[test]
+    fn test_extract_tar_gz() {
+        let dir = self_update::TempDir::new().unwrap();
+        let build = dir.path().join("build");
+        std::fs::create_dir(&build).unwrap();
+        std::fs::write(build.join(bin_file_name()), BINARY).unwrap();
+
+        // The Linux releases are gzipped tarballs, made like the release workflow does
+        let archive = dir.path().join("archive.tar.gz");
+        let status = std::process::Command::new("tar")
+            .arg("-czf")
+            .arg(&archive)
+            .arg("-C")
+            .arg(&build)
+            .arg(bin_file_name())
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let asset = VerifiedAsset {
+            name: format!("tgh-{}.tar.gz", TARGET),
+            bytes: std::fs::read(&archive).unwrap(),
+        };
+        let into = dir.path().join("download");
+        std::fs::create_dir(&into).unwrap();
+
+        let binary = extract_binary(&asset, &into).unwrap();
+        assert_eq!(std::fs::read(binary).unwrap(), BINARY);
+    }
+
+    #[test]
+    fn test_backup() {
+        let dir = self_update::TempDir::new().unwrap();
+        let backup = dir.path().join("backup");
+        assert!(matches!(read_backup(&backup), Err(UpdateError::NoBackup)));
+
+        let binary = dir.path().join("tgh-old");
+        std::fs::write(&binary, "tgh 0.1.7\n").unwrap();
+        save_backup(&binary, "0.1.7", &backup).unwrap();
+
+        let (saved, version) = read_backup(&backup).unwrap();
+        assert_eq!(version, "0.1.7");
+        assert_eq!(std::fs::read_to_string(saved).unwrap(), "tgh 0.1.7\n");
+    }
 }
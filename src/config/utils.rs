@@ -1,43 +1,38 @@
 use crate::config::defines;
 
-pub fn handle_config_folder() {
-    use home::home_dir;
-    use std::fs::create_dir_all;
+use crate::config::paths::{self, PathError};
 
-    let home = home_dir().unwrap();
-    let config_path = format!("{}/.config/tgh", home.display());
-
-    create_dir_all(config_path).unwrap();
+pub fn handle_config_folder() -> Result<(), PathError> {
+    paths::create_config_dir()
 }
 
-fn get_config_path() -> String {
-    use home::home_dir;
-
-    let home = home_dir().unwrap();
-    let config_path = format!("{}/.config/tgh/config.json", home.display());
-
-    config_path
+pub fn get_config_path() -> Result<String, PathError> {
+    Ok(paths::get()?.config_file.display().to_string())
 }
 
-fn get_metadata_path() -> String {
-    use home::home_dir;
-
-    let home = home_dir().unwrap();
-    let metadata_path = format!("{}/.config/tgh/metadata.json", home.display());
+fn get_metadata_path() -> Result<String, PathError> {
+    Ok(paths::state_file("metadata.json")?.display().to_string())
+}
 
-    metadata_path
+pub fn get_journal_path() -> Result<String, PathError> {
+    Ok(paths::state_file("journal.json")?.display().to_string())
 }
 
 pub fn config_exists() -> bool {
     use std::path::Path;
 
-    let config_path = get_config_path();
-    let config_path = Path::new(&config_path);
+    match get_config_path() {
+        Ok(config_path) => Path::new(&config_path).exists(),
+        Err(_) => false,
+    }
+}
 
-    config_path.exists()
+/// Reads the file at the path, if the path could be resolved.
+pub fn read_path(path: Result<String, PathError>) -> Result<String, std::io::Error> {
+    read_file_content(path.map_err(std::io::Error::other)?)
 }
 
-fn read_file_content(path: String) -> Result<String, std::io::Error> {
+pub fn read_file_content(path: String) -> Result<String, std::io::Error> {
     use std::fs::File;
     use std::io::prelude::*;
 
@@ -50,7 +45,7 @@ fn read_file_content(path: String) -> Result<String, std::io::Error> {
 }
 
 pub fn read_config() -> crate::config::Config {
-    let config_contents = read_file_content(get_config_path());
+    let config_contents = read_path(get_config_path());
 
     let conf = match config_contents {
         Err(_) => {
@@ -59,15 +54,13 @@ pub fn read_config() -> crate::config::Config {
         Ok(contents) => contents,
     };
 
-    let config: crate::config::Config = serde_json::from_str(&conf).unwrap();
+    let (config, _) = crate::config::migrations::deserialize(&conf).unwrap();
 
     config
 }
 
 pub fn read_metadata() -> crate::config::Metadata {
-    let metadata_path = get_metadata_path();
-
-    let metadata_contents = read_file_content(metadata_path);
+    let metadata_contents = read_path(get_metadata_path());
 
     let metadata = match metadata_contents {
         Err(_) => {
@@ -82,54 +75,106 @@ pub fn read_metadata() -> crate::config::Metadata {
 }
 
 pub fn validate_config_file() -> bool {
-    use crate::config::Config;
-
-    let config_contents = match read_file_content(get_config_path()) {
+    let config_contents = match read_path(get_config_path()) {
         Ok(contents) => contents,
         Err(_) => {
             return false;
         }
     };
 
-    if config_contents.len() == 0 {
-        return false;
-    }
+    parse_config(&config_contents).is_ok()
+}
 
-    let config = serde_json::from_str(&config_contents);
+/// Parses and validates the contents of a config file.
+pub fn parse_config(contents: &str) -> Result<crate::config::Config, String> {
+    use crate::config::Config;
 
-    if config.is_err() {
-        return false;
+    if contents.trim().is_empty() {
+        return Err("The config file is empty".into());
     }
 
-    let config: Config = config.unwrap();
+    let (config, _): (Config, _) = crate::config::migrations::deserialize(contents)?;
+
+    validate_config(&config)?;
 
+    Ok(config)
+}
+
+pub fn validate_config(config: &crate::config::Config) -> Result<(), String> {
     if config.username.len() == 0 {
-        return false;
+        return Err("`username` can't be empty".into());
     }
 
     if config.sort < defines::SORTING::LastUpdated || config.sort > defines::SORTING::Alphabetical {
-        return false;
+        return Err("`sort` has an invalid value".into());
     }
 
     if config.protocol < defines::PROTOCOL::SSH || config.protocol > defines::PROTOCOL::HTTPS {
-        return false;
+        return Err("`protocol` has an invalid value".into());
     }
 
     if config.color < defines::COLOR::NORMAL || config.color > defines::COLOR::GRAY {
-        return false;
+        return Err("`color` has an invalid value".into());
+    }
+
+    Ok(())
+}
+
+/// Upgrades the config file to the current version, after backing it up.
+/// Does nothing if the file is already up to date (or can't be parsed).
+pub fn upgrade_config_file() {
+    use crate::config::migrations;
+
+    let contents = match read_path(get_config_path()) {
+        Ok(contents) => contents,
+        Err(_) => return,
+    };
+
+    let (config, migrated) = match migrations::deserialize(&contents) {
+        Ok(result) => result,
+        Err(_) => return,
+    };
+
+    if !migrated {
+        return;
     }
 
-    if config.fancy != true && config.fancy != false {
-        return false;
+    let version = serde_json::from_str(&contents)
+        .map(|value| migrations::get_version(&value))
+        .unwrap_or_default();
+
+    match backup_config_file(&format!("v{}", version)) {
+        Ok(_) => save_config_file(config),
+        Err(err) => crate::out::print_error(&format!(
+            "Failed to back up the config file, it was not upgraded: {}\n",
+            err
+        )),
     }
+}
 
-    true
+/// Copies the config file to `config.json.<suffix>.bak`, next to the original.
+///
+/// ### Returns
+/// The path of the backup.
+pub fn backup_config_file(suffix: &str) -> Result<String, std::io::Error> {
+    let config_path = get_config_path().map_err(std::io::Error::other)?;
+    let backup_path = format!("{}.{}.bak", config_path, suffix);
+
+    std::fs::copy(config_path, &backup_path)?;
+
+    Ok(backup_path)
 }
 
 pub fn save_config_file(config: crate::config::Config) {
     use std::{fs::File, io::prelude::*};
 
-    let config_path = get_config_path();
+    let config_path = match get_config_path() {
+        Ok(config_path) => config_path,
+        Err(err) => {
+            crate::out::print_error(&err.to_string());
+            return;
+        }
+    };
 
     let mut config_file = File::create(config_path).unwrap();
 
@@ -329,13 +374,30 @@ pub fn get_labels() -> Vec<CommitLabel> {
     labels
 }
 
-pub fn should_check_for_updates() -> bool {
-    let metadata = read_metadata();
+/// Finds the commit label a message starts with (ex. `✨ Add a feature` -> New feature).
+pub fn find_label(message: &str) -> Option<CommitLabel> {
+    // Emojis may be written with or without the variation selector (ex. `⬆️` and `⬆`)
+    let message = message.trim_start().replace('\u{fe0f}', "");
+
+    get_labels()
+        .into_iter()
+        .find(|label| message.starts_with(&label.emoji.replace('\u{fe0f}', "")))
+}
+
+/// Whether the last check for updates is older than a day, or was made on another channel.
+pub fn should_check_for_updates(
+    metadata: &crate::config::Metadata,
+    channel: defines::CHANNEL,
+) -> bool {
+    if metadata.latest_channel != Some(channel) {
+        return true;
+    }
 
     let now = chrono::Utc::now();
-    let last_checked = chrono::DateTime::parse_from_rfc3339(&metadata.last_checked)
-        .unwrap()
-        .to_utc();
+    let last_checked = match chrono::DateTime::parse_from_rfc3339(&metadata.last_checked) {
+        Ok(last_checked) => last_checked.to_utc(),
+        Err(_) => return true,
+    };
 
     let time_diff = now.signed_duration_since(last_checked).num_days();
 
@@ -349,7 +411,18 @@ pub fn should_check_for_updates() -> bool {
 pub fn save_metadata_file(metadata: crate::config::Metadata) {
     use std::{fs::File, io::prelude::*};
 
-    let metadata_path = get_metadata_path();
+    if let Err(err) = paths::create_state_dir() {
+        crate::out::print_error(&err.to_string());
+        return;
+    }
+
+    let metadata_path = match get_metadata_path() {
+        Ok(metadata_path) => metadata_path,
+        Err(err) => {
+            crate::out::print_error(&err.to_string());
+            return;
+        }
+    };
 
     let mut metadata_file = match (File::create(metadata_path)) {
         Ok(file) => file,
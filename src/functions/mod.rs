@@ -13,3 +13,113 @@ pub fn is_git_repo() -> bool {
 
     true
 }
+
+/// Runs git with the given arguments.
+///
+/// ### Returns
+/// The stdout of the command (with the trailing newline removed), or the stderr if the command failed.
+pub fn git(args: &[&str]) -> Result<String, String> {
+    use std::process::Command;
+
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|err| err.to_string())?;
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .trim_end_matches('\n')
+        .to_string())
+}
+
+/// Opens the given file in the user's editor (`$VISUAL`, `$EDITOR`, or a platform default)
+/// and waits for it to be closed.
+pub fn open_editor(path: &std::path::Path) -> Result<(), String> {
+    use std::process::Command;
+
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| {
+            if cfg!(target_os = "windows") {
+                "notepad".into()
+            } else {
+                "vi".into()
+            }
+        });
+
+    // The editor may contain arguments, ex. `code --wait`
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().ok_or("No editor configured")?;
+
+    let status = Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .map_err(|err| format!("Failed to launch `{}`: {}", editor, err))?;
+
+    if !status.success() {
+        return Err(format!("`{}` exited with an error", editor));
+    }
+
+    Ok(())
+}
+
+/// Returns the owner and name of the GitHub repository the remote points to.
+pub fn get_github_repository(remote: &str) -> Option<(String, String)> {
+    let url = git(&["remote", "get-url", remote]).ok()?;
+
+    parse_github_url(&url)
+}
+
+/// Parses the owner and repository name from a remote URL.
+/// Supports `git@host:owner/repo.git`, `ssh://git@host/owner/repo` and `https://host/owner/repo.git`.
+pub fn parse_github_url(url: &str) -> Option<(String, String)> {
+    let url = url.trim().trim_end_matches('/');
+    let url = url.strip_suffix(".git").unwrap_or(url);
+
+    let path = match url.split_once("://") {
+        Some((_, rest)) => rest.split_once('/')?.1,
+        None => url.split_once(':')?.1,
+    };
+
+    let mut parts = path.rsplitn(2, '/');
+    let repo = parts.next()?;
+    let owner = parts.next()?.rsplit('/').next()?;
+
+    if owner.is_empty() || repo.is_empty() {
+        return None;
+    }
+
+    Some((owner.to_string(), repo.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_github_url() {
+        let expected = Some(("dkomeza".to_string(), "tiny-git-helper".to_string()));
+
+        assert_eq!(
+            parse_github_url("git@github.com:dkomeza/tiny-git-helper.git"),
+            expected
+        );
+        assert_eq!(
+            parse_github_url("https://github.com/dkomeza/tiny-git-helper.git"),
+            expected
+        );
+        assert_eq!(
+            parse_github_url("https://github.com/dkomeza/tiny-git-helper/"),
+            expected
+        );
+        assert_eq!(
+            parse_github_url("ssh://git@github.com:22/dkomeza/tiny-git-helper"),
+            expected
+        );
+        assert_eq!(parse_github_url("not a url"), None);
+    }
+}
@@ -13,6 +13,18 @@ mod view;
 struct Cli {
     #[clap(subcommand)]
     subcmd: Option<SubCommand>,
+
+    /// Use a different config file (can also be set with `TGH_CONFIG`)
+    #[clap(long = "config", value_name = "PATH", global = true)]
+    config_file: Option<std::path::PathBuf>,
+
+    /// Use the given account profile, instead of matching one by the remote or directory
+    #[clap(long, value_name = "NAME", global = true)]
+    profile: Option<String>,
+
+    /// Override a setting for this run (ex. `-c sort=alphabetical`)
+    #[clap(short = 'c', value_name = "KEY=VALUE", global = true)]
+    overrides: Vec<String>,
 }
 
 #[derive(Subcommand)]
@@ -23,15 +35,64 @@ enum SubCommand {
     #[clap(name = "ca", about = "Commit all files")]
     CommitAll(modules::commit::CommitOptions),
 
+    #[clap(name = "amend", about = "Amend or reword the last commit")]
+    Amend(modules::amend::AmendOptions),
+    #[clap(name = "fixup", about = "Squash staged changes into an earlier commit")]
+    Fixup(modules::amend::FixupOptions),
+
+    #[clap(name = "history", about = "Browse the commit history")]
+    History(modules::history::CommitHistoryOptions),
+    #[clap(name = "blame", about = "Show who last changed every line of a file")]
+    Blame(modules::blame::BlameOptions),
+    #[clap(name = "stats", about = "Show statistics of the repository")]
+    Stats(modules::stats::StatsOptions),
+
+    #[clap(name = "undo", about = "Undo the last tgh operation")]
+    Undo(modules::undo::UndoOptions),
+
+    #[clap(name = "conflicts", about = "Resolve merge or rebase conflicts")]
+    Conflicts(modules::conflicts::ConflictsOptions),
+
+    #[clap(
+        name = "changelog",
+        about = "Generate a changelog from the commit history"
+    )]
+    Changelog(modules::changelog::ChangelogOptions),
+    #[clap(
+        name = "release",
+        about = "Tag a new version and create a GitHub release"
+    )]
+    Release(modules::release::ReleaseOptions),
+
+    #[clap(name = "config", about = "View and change the settings")]
+    Config(modules::settings::ConfigOptions),
+
     #[clap(name = "update", about = "Update tgh to the latest version")]
-    Update,
+    Update(config::update::UpdateOptions),
 }
 
 #[tokio::main]
 async fn main() {
     let args = Cli::parse();
 
-    config::check_prerequisites().await;
+    if let Err(err) = config::paths::init(args.config_file.clone()) {
+        out::print_error(&err.to_string());
+        std::process::exit(1);
+    }
+
+    config::layers::set_profile(args.profile.clone());
+
+    if let Err(err) = config::layers::set_overrides(&args.overrides) {
+        out::print_error(&err);
+        std::process::exit(1);
+    }
+
+    view::theme::init();
+
+    // The config command has to work without a valid config (and without prompting)
+    if !matches!(args.subcmd, Some(SubCommand::Config(_))) {
+        config::check_prerequisites().await;
+    }
 
     let subcmd = match args.subcmd {
         Some(subcmd) => subcmd,
@@ -48,10 +109,42 @@ async fn main() {
         SubCommand::CommitFiles(options) => {
             modules::commit::commit_specific_files(options);
         }
-        SubCommand::Update => {
-            config::update::perform_self_update().await;
+        SubCommand::Amend(options) => {
+            modules::amend::amend_commit(options);
+        }
+        SubCommand::Fixup(options) => {
+            modules::amend::fixup_commit(options);
+        }
+        SubCommand::History(options) => {
+            modules::history::commit_history(options);
+        }
+        SubCommand::Blame(options) => {
+            modules::blame::blame_file(options);
+        }
+        SubCommand::Stats(options) => {
+            modules::stats::show_stats(options);
+        }
+        SubCommand::Undo(options) => {
+            modules::undo::undo_last_operation(options);
+        }
+        SubCommand::Conflicts(options) => {
+            modules::conflicts::resolve_conflicts(options);
+        }
+        SubCommand::Changelog(options) => {
+            modules::changelog::generate_changelog(options);
+        }
+        SubCommand::Release(options) => {
+            modules::release::create_release(options).await;
+        }
+        SubCommand::Config(options) => {
+            modules::settings::handle_config_command(options);
+        }
+        SubCommand::Update(options) => {
+            config::update::perform_self_update(options).await;
         }
     }
 
     view::clean_up();
+
+    config::update::wait_for_background_check().await;
 }
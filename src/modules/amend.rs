@@ -0,0 +1,38 @@
+use clap::Parser;
+
+mod functions;
+mod views;
+
+pub use views::{amend_commit, fixup_commit};
+
+#[derive(Parser)]
+pub struct AmendOptions {
+    /// Stage all tracked changes before amending
+    #[clap(short, long)]
+    pub all: bool,
+
+    /// Reword the commit message
+    #[clap(short, long)]
+    pub reword: bool,
+
+    /// New commit message (optional, implies --reword and skips the fancy commit message menu)
+    pub commit_message: Option<String>,
+}
+
+#[derive(Parser)]
+pub struct FixupOptions {
+    /// Stage all tracked changes before creating the fixup commit
+    #[clap(short, long)]
+    pub all: bool,
+
+    /// The commit to fix up (optional, skips the history browser)
+    pub hash: Option<String>,
+
+    /// Number of commits to show in the history browser
+    #[clap(short, long, default_value = "20")]
+    pub limit: usize,
+
+    /// Only create the `fixup!` commit, without running the autosquash rebase
+    #[clap(long)]
+    pub no_rebase: bool,
+}
@@ -0,0 +1,82 @@
+use crate::functions::git;
+
+/// Checks if there are any staged changes.
+pub fn has_staged_changes() -> Result<bool, String> {
+    use std::process::Command;
+
+    let output = Command::new("git")
+        .args(["diff", "--cached", "--quiet"])
+        .output()
+        .map_err(|err| err.to_string())?;
+
+    // `--quiet` exits with 1 when there are differences
+    match output.status.code() {
+        Some(0) => Ok(false),
+        Some(1) => Ok(true),
+        _ => Err(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+    }
+}
+
+pub fn stage_tracked_files() -> Result<(), String> {
+    git(&["add", "--update"]).map(|_| ())
+}
+
+/// Resolves a revision (ex. a short hash or `HEAD~2`) to a full commit hash.
+pub fn resolve_commit(revision: &str) -> Result<String, String> {
+    git(&[
+        "rev-parse",
+        "--verify",
+        "--quiet",
+        &format!("{}^{{commit}}", revision),
+    ])
+    .map_err(|_| format!("Commit `{}` not found", revision))
+}
+
+/// Checks if the commit is reachable from any remote-tracking branch.
+pub fn is_pushed(hash: &str) -> bool {
+    match git(&["branch", "--remotes", "--contains", hash]) {
+        Ok(branches) => !branches.trim().is_empty(),
+        Err(_) => false,
+    }
+}
+
+fn is_root_commit(hash: &str) -> bool {
+    git(&["rev-parse", "--verify", "--quiet", &format!("{}^", hash)]).is_err()
+}
+
+pub fn amend(message: Option<String>) -> Result<(), String> {
+    match message {
+        Some(message) => git(&["commit", "--amend", "--message", &message]),
+        None => git(&["commit", "--amend", "--no-edit"]),
+    }
+    .map(|_| ())
+}
+
+pub fn create_fixup(hash: &str) -> Result<(), String> {
+    git(&["commit", &format!("--fixup={}", hash)]).map(|_| ())
+}
+
+/// Squashes the `fixup!` commits into their targets, without opening an editor.
+pub fn autosquash(hash: &str) -> Result<(), String> {
+    use std::process::Command;
+
+    let mut command = Command::new("git");
+    command
+        .env("GIT_SEQUENCE_EDITOR", "true")
+        .env("GIT_EDITOR", "true")
+        .args(["rebase", "--interactive", "--autosquash", "--autostash"]);
+
+    if is_root_commit(hash) {
+        command.arg("--root");
+    } else {
+        command.arg(format!("{}^", hash));
+    }
+
+    let output = command.output().map_err(|err| err.to_string())?;
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,184 @@
+use super::{functions, AmendOptions, FixupOptions};
+use crate::config::journal;
+use crate::modules::commit::{ask_commit_message, CommitOptions};
+use crate::view::{self, input, input::ReturnType};
+
+pub fn amend_commit(options: AmendOptions) {
+    check_repository();
+
+    let before = journal::snapshot();
+
+    if options.all {
+        stage_tracked_files();
+    }
+
+    let reword = options.reword || options.commit_message.is_some();
+
+    if !reword && !has_staged_changes() {
+        crate::out::print_error("Nothing to amend. Stage some changes or use --reword");
+        std::process::exit(0);
+    }
+
+    let head = match functions::resolve_commit("HEAD") {
+        Ok(head) => head,
+        Err(_) => {
+            crate::out::print_error("There are no commits to amend");
+            std::process::exit(1);
+        }
+    };
+
+    if !confirm_rewrite(&head) {
+        return;
+    }
+
+    let message = if reword {
+        let commit_options = CommitOptions {
+            commit_message: options.commit_message,
+            ..Default::default()
+        };
+
+        match ask_commit_message(&commit_options) {
+            Ok(message) => Some(message),
+            Err(ReturnType::Cancel) => return,
+            Err(ReturnType::Exit) => std::process::exit(1),
+        }
+    } else {
+        None
+    };
+
+    match functions::amend(message) {
+        Ok(_) => {
+            journal::record("amend", before, false);
+            view::printer("\n$@success `✔ Amended the last commit.`\n");
+        }
+        Err(err) => {
+            crate::out::print_error("Failed to amend the commit");
+            crate::out::print_error(&err);
+            std::process::exit(1);
+        }
+    }
+}
+
+pub fn fixup_commit(options: FixupOptions) {
+    use crate::modules::history::{select_commit, CommitHistoryOptions};
+
+    check_repository();
+
+    let before = journal::snapshot();
+
+    if options.all {
+        stage_tracked_files();
+    }
+
+    if !has_staged_changes() {
+        crate::out::print_error("No staged changes to add to the fixup commit");
+        std::process::exit(0);
+    }
+
+    let revision = match options.hash {
+        Some(hash) => hash,
+        None => {
+            let history_options = CommitHistoryOptions {
+                limit: Some(options.limit),
+                ..Default::default()
+            };
+
+            match select_commit(history_options) {
+                Some(commit) => commit.hash,
+                None => return,
+            }
+        }
+    };
+
+    let target = match functions::resolve_commit(&revision) {
+        Ok(target) => target,
+        Err(err) => {
+            crate::out::print_error(&err);
+            std::process::exit(1);
+        }
+    };
+
+    if !confirm_rewrite(&target) {
+        return;
+    }
+
+    if let Err(err) = functions::create_fixup(&target) {
+        crate::out::print_error("Failed to create the fixup commit");
+        crate::out::print_error(&err);
+        std::process::exit(1);
+    }
+
+    if options.no_rebase {
+        journal::record("fixup", before, false);
+        view::printer(format!(
+            "\n$@success `✔ Created a fixup commit for {}.`\n",
+            &target[..7]
+        ));
+        return;
+    }
+
+    match functions::autosquash(&target) {
+        Ok(_) => {
+            journal::record("fixup", before, false);
+            view::printer(format!(
+                "\n$@success `✔ Squashed the changes into {}.`\n",
+                &target[..7]
+            ));
+        }
+        Err(err) => {
+            crate::out::print_error("The autosquash rebase stopped");
+            crate::out::print_error(&err);
+            view::printer(
+                "\n$cw `Resolve the conflicts with` $@highlight `tgh conflicts` $cw `to continue the rebase.`\n",
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+fn check_repository() {
+    if !crate::functions::is_git_repo() {
+        crate::out::print_error("Not a git repository");
+        std::process::exit(1);
+    }
+}
+
+fn stage_tracked_files() {
+    if let Err(err) = functions::stage_tracked_files() {
+        crate::out::print_error("Failed to stage files");
+        crate::out::print_error(&err);
+        std::process::exit(1);
+    }
+}
+
+fn has_staged_changes() -> bool {
+    match functions::has_staged_changes() {
+        Ok(staged) => staged,
+        Err(err) => {
+            crate::out::print_error("Failed to check the staged changes");
+            crate::out::print_error(&err);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Warns the user if the commit has already been pushed, and asks whether to continue.
+fn confirm_rewrite(hash: &str) -> bool {
+    if !functions::is_pushed(hash) {
+        return true;
+    }
+
+    view::printer(view::unindent(&format!(
+        r#"
+        $@warning `⚠ Commit {} has already been pushed.`
+        &> $cw `Rewriting it will require a force push, and may affect others working on the branch.`
+        "#,
+        &hash[..7]
+    )));
+
+    match input::list("Rewrite it anyway? ", vec!["No", "Yes"]) {
+        Ok(choice) => choice == "Yes",
+        Err(ReturnType::Cancel) => false,
+        Err(ReturnType::Exit) => std::process::exit(1),
+    }
+}
@@ -0,0 +1,16 @@
+use clap::Parser;
+
+mod functions;
+mod views;
+
+pub use views::blame_file;
+
+#[derive(Parser)]
+pub struct BlameOptions {
+    /// The file to blame
+    pub file: String,
+
+    /// Blame the file as it was in a commit, branch or tag
+    #[clap(short, long)]
+    pub rev: Option<String>,
+}
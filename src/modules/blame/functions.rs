@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, FixedOffset, TimeZone};
+
+/// The hash of the lines which aren't committed yet.
+pub const NOT_COMMITTED: &str = "0000000000000000000000000000000000000000";
+
+/// The colors of the gutter, from the oldest to the newest commits.
+const AGE_COLORS: [&str; 5] = ["cb", "cc", "cg", "cy", "cr"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlameCommit {
+    pub hash: String,
+    pub author: String,
+    pub email: String,
+    pub author_date: DateTime<FixedOffset>,
+    pub summary: String,
+    /// The parent commit and the path of the file in it, `None` for the first commit of the file
+    pub previous: Option<(String, String)>,
+}
+
+impl BlameCommit {
+    pub fn short_hash(&self) -> &str {
+        &self.hash[..self.hash.len().min(8)]
+    }
+
+    pub fn is_committed(&self) -> bool {
+        self.hash != NOT_COMMITTED
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlameLine {
+    pub hash: String,
+    /// The number of the line in the commit which last changed it
+    pub original_number: usize,
+    pub number: usize,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Blame {
+    pub commits: HashMap<String, BlameCommit>,
+    pub lines: Vec<BlameLine>,
+}
+
+impl Blame {
+    pub fn commit(&self, line: &BlameLine) -> &BlameCommit {
+        &self.commits[&line.hash]
+    }
+
+    /// The color of the commit's age, compared to the other commits of the file.
+    pub fn age_color(&self, commit: &BlameCommit) -> &'static str {
+        let times = self
+            .commits
+            .values()
+            .map(|commit| commit.author_date.timestamp());
+        let (oldest, newest) = (times.clone().min(), times.max());
+
+        match (oldest, newest) {
+            (Some(oldest), Some(newest)) if newest > oldest => {
+                let age = (commit.author_date.timestamp() - oldest) as f64;
+                let bucket = age / (newest - oldest) as f64 * (AGE_COLORS.len() - 1) as f64;
+
+                AGE_COLORS[bucket.round() as usize]
+            }
+            _ => AGE_COLORS[AGE_COLORS.len() - 1],
+        }
+    }
+}
+
+/// Parses the timezone of `author-tz` (ex. `+0130`), UTC when it's malformed.
+fn parse_offset(tz: &str) -> FixedOffset {
+    let utc = FixedOffset::east_opt(0).unwrap();
+    let sign = match tz.chars().next() {
+        Some('+') => 1,
+        Some('-') => -1,
+        _ => return utc,
+    };
+    let digits = &tz[1..];
+
+    match (
+        digits.get(..2).map(str::parse::<i32>),
+        digits.get(2..).map(str::parse::<i32>),
+    ) {
+        (Some(Ok(hours)), Some(Ok(minutes))) => {
+            FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)).unwrap_or(utc)
+        }
+        _ => utc,
+    }
+}
+
+/// Parses the output of `git blame --porcelain`.
+/// The fields of a commit are only given for its first line, the other lines only have the hash.
+pub fn parse_porcelain(output: &str) -> Blame {
+    let mut blame = Blame::default();
+    let mut current: Option<(String, usize, usize)> = None;
+    // The fields of the commit being read, before its first line
+    let mut fields: HashMap<&str, &str> = HashMap::new();
+
+    for line in output.lines() {
+        if let Some(text) = line.strip_prefix('\t') {
+            let (hash, original_number, number) = match current.take() {
+                Some(current) => current,
+                None => continue,
+            };
+
+            if !blame.commits.contains_key(&hash) {
+                let offset = parse_offset(fields.get("author-tz").unwrap_or(&""));
+                let time = fields
+                    .get("author-time")
+                    .and_then(|time| time.parse().ok())
+                    .unwrap_or(0);
+
+                blame.commits.insert(
+                    hash.clone(),
+                    BlameCommit {
+                        hash: hash.clone(),
+                        author: fields.get("author").unwrap_or(&"").to_string(),
+                        email: fields
+                            .get("author-mail")
+                            .unwrap_or(&"")
+                            .trim_matches(|c| c == '<' || c == '>')
+                            .to_string(),
+                        author_date: offset.timestamp_opt(time, 0).single().unwrap_or_default(),
+                        summary: fields.get("summary").unwrap_or(&"").to_string(),
+                        previous: fields.get("previous").and_then(|previous| {
+                            let (hash, path) = previous.split_once(' ')?;
+                            Some((hash.to_string(), path.to_string()))
+                        }),
+                    },
+                );
+            }
+            fields.clear();
+
+            blame.lines.push(BlameLine {
+                hash,
+                original_number,
+                number,
+                text: text.replace('\t', "    "),
+            });
+            continue;
+        }
+
+        let mut parts = line.split(' ');
+        let header = parts.next().unwrap_or_default();
+        let numbers: Vec<usize> = parts.clone().filter_map(|part| part.parse().ok()).collect();
+
+        if header.len() == 40 && header.chars().all(|c| c.is_ascii_hexdigit()) && numbers.len() >= 2
+        {
+            current = Some((header.to_string(), numbers[0], numbers[1]));
+        } else if let Some((key, value)) = line.split_once(' ') {
+            fields.insert(key, value);
+        }
+    }
+
+    blame
+}
+
+/// Blames the file, as it was in the revision (or in the working tree).
+pub fn blame(file: &str, rev: Option<&str>) -> Result<Blame, String> {
+    blame_in(".", file, rev)
+}
+
+/// Returns the path of a file given from the top of the repository (ex. the `previous` path
+/// of a commit) relative to the current directory, `git blame` doesn't take `:(top)` paths.
+pub fn path_from_top(path: &str) -> Result<String, String> {
+    path_from_top_in(".", path)
+}
+
+fn blame_in(dir: &str, file: &str, rev: Option<&str>) -> Result<Blame, String> {
+    let mut args = vec!["-C", dir, "blame", "--porcelain"];
+    args.extend(rev);
+    args.extend(["--", file]);
+
+    crate::functions::git(&args).map(|output| parse_porcelain(&output))
+}
+
+fn path_from_top_in(dir: &str, path: &str) -> Result<String, String> {
+    let cdup = crate::functions::git(&["-C", dir, "rev-parse", "--show-cdup"])?;
+
+    Ok(format!("{}{}", cdup.trim(), path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PORCELAIN: &str = "\
+1111111111111111111111111111111111111111 1 1 2
+author Ada
+author-mail <ada@example.com>
+author-time 1700000000
+author-tz +0100
+committer Ada
+summary First commit
+boundary
+filename src/lib.rs
+\tfn main() {
+1111111111111111111111111111111111111111 2 2
+\t\tlet a = 1;
+2222222222222222222222222222222222222222 2 3 1
+author Bob
+author-mail <bob@example.com>
+author-time 1710000000
+author-tz -0230
+summary Second commit
+previous 1111111111111111111111111111111111111111 src/main.rs
+filename src/lib.rs
+\t}
+";
+
+    #[test]
+    fn test_parse_porcelain() {
+        let blame = parse_porcelain(PORCELAIN);
+
+        assert_eq!(blame.lines.len(), 3);
+        assert_eq!(blame.lines[1].text, "    let a = 1;");
+        assert_eq!(blame.lines[2].original_number, 2);
+        assert_eq!(blame.lines[2].number, 3);
+
+        let first = blame.commit(&blame.lines[1]);
+        assert_eq!(first.author, "Ada");
+        assert_eq!(first.email, "ada@example.com");
+        assert_eq!(first.summary, "First commit");
+        assert_eq!(first.previous, None);
+        assert_eq!(first.author_date.to_rfc3339(), "2023-11-14T23:13:20+01:00");
+
+        let second = blame.commit(&blame.lines[2]);
+        assert_eq!(second.short_hash(), "22222222");
+        assert_eq!(
+            second.author_date.offset().local_minus_utc(),
+            -(2 * 3600 + 30 * 60)
+        );
+        assert_eq!(
+            second.previous,
+            Some((
+                "1111111111111111111111111111111111111111".to_string(),
+                "src/main.rs".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_age_color() {
+        let blame = parse_porcelain(PORCELAIN);
+
+        assert_eq!(blame.age_color(blame.commit(&blame.lines[0])), "cb");
+        assert_eq!(blame.age_color(blame.commit(&blame.lines[2])), "cr");
+    }
+
+    #[test]
+    fn test_blame_parent_in_subdirectory() {
+        let repo = tempfile::tempdir().unwrap();
+        let root = repo.path().to_str().unwrap();
+        let sub = repo.path().join("sub");
+        let sub = sub.to_str().unwrap();
+        let git = |args: &[&str]| {
+            let mut all = vec![
+                "-C",
+                root,
+                "-c",
+                "user.name=Ada",
+                "-c",
+                "user.email=ada@example.com",
+            ];
+            all.extend(args);
+            crate::functions::git(&all).unwrap();
+        };
+
+        std::fs::create_dir(sub).unwrap();
+        git(&["init", "-q"]);
+        std::fs::write(repo.path().join("sub/a.txt"), "one\n").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-q", "-m", "First"]);
+        std::fs::write(repo.path().join("sub/a.txt"), "two\n").unwrap();
+        git(&["commit", "-q", "-a", "-m", "Second"]);
+
+        let blame = blame_in(sub, "a.txt", Some("HEAD")).unwrap();
+        let (hash, path) = blame.commit(&blame.lines[0]).previous.clone().unwrap();
+        assert_eq!(path, "sub/a.txt");
+
+        // The previous path is from the top of the repository, not from the directory
+        let file = path_from_top_in(sub, &path).unwrap();
+        assert_eq!(file, "../sub/a.txt");
+
+        let parent = blame_in(sub, &file, Some(&hash)).unwrap();
+        assert_eq!(parent.lines[0].text, "one");
+    }
+}
@@ -0,0 +1,218 @@
+use crossterm::event::KeyCode;
+
+use crate::modules::history::{self, CommitHistoryOptions};
+use crate::view::escape;
+use crate::view::input::ReturnType;
+use crate::view::syntax::Highlighter;
+use crate::view::tui::{Action, Binding, ListView, Outcome};
+
+use super::functions::{blame, path_from_top, Blame, BlameCommit, BlameLine};
+use super::BlameOptions;
+
+/// The width of the author in the gutter, longer names are cut.
+const AUTHOR_WIDTH: usize = 12;
+
+/// A blamed revision of a file, and the selected line.
+struct Position {
+    file: String,
+    /// `None` for the working tree
+    rev: Option<String>,
+    line: usize,
+}
+
+pub fn blame_file(options: BlameOptions) {
+    let mut position = Position {
+        file: options.file,
+        rev: options.rev,
+        line: 0,
+    };
+    // The positions left by re-blaming at a parent, to go back to
+    let mut previous: Vec<Position> = Vec::new();
+
+    loop {
+        let blame = match blame(&position.file, position.rev.as_deref()) {
+            Ok(blame) => blame,
+            Err(err) => {
+                crate::out::print_error("Failed to blame the file");
+                crate::out::print_error(&err);
+                std::process::exit(1);
+            }
+        };
+
+        if blame.lines.is_empty() {
+            crate::out::print_error("\nThe file is empty\n");
+            return;
+        }
+
+        let (action, index) = match browse(&position, &blame) {
+            Some(Outcome::Custom(action, index)) => (action, index),
+            _ => return,
+        };
+        let line = &blame.lines[index];
+        position.line = index;
+
+        match action {
+            "history" => {
+                history::commit_history(CommitHistoryOptions {
+                    branch: Some(line.hash.clone()),
+                    ..Default::default()
+                });
+                return;
+            }
+            "parent" => {
+                if let Some((hash, path)) = &blame.commit(line).previous {
+                    let file = match path_from_top(path) {
+                        Ok(file) => file,
+                        Err(err) => {
+                            crate::out::print_error("Failed to find the file");
+                            crate::out::print_error(&err);
+                            std::process::exit(1);
+                        }
+                    };
+                    let parent = Position {
+                        file,
+                        rev: Some(hash.clone()),
+                        line: line.original_number.saturating_sub(1),
+                    };
+                    previous.push(std::mem::replace(&mut position, parent));
+                }
+            }
+            _ => {
+                if let Some(last) = previous.pop() {
+                    position = last;
+                }
+            }
+        }
+    }
+}
+
+/// Lets the user browse the lines, until a line is opened in the history, re-blamed at its
+/// parent or the user goes back. The diffs of the commits are shown in place.
+fn browse(position: &Position, blame: &Blame) -> Option<Outcome> {
+    let mut highlighter = Highlighter::for_path(&position.file);
+    let number_width = blame.lines.len().to_string().len();
+
+    let entries: Vec<(&BlameLine, String)> = blame
+        .lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            // The commit is only written on the first of its consecutive lines
+            let first = i == 0 || blame.lines[i - 1].hash != line.hash;
+
+            let row = format!(
+                "{} $s `{:>width$} │` {}",
+                gutter(blame, blame.commit(line), first),
+                line.number,
+                highlighter.markup(&line.text),
+                width = number_width
+            );
+
+            (line, row)
+        })
+        .collect();
+
+    let mut title = format!("Blame of {}", position.file);
+    match &position.rev {
+        // The parents are blamed at their full hash
+        Some(rev) if rev.len() == 40 => title.push_str(&format!(" at {}", &rev[..8])),
+        Some(rev) => title.push_str(&format!(" at {}", rev)),
+        None => {}
+    }
+
+    let mut view = ListView::new(
+        title,
+        &entries,
+        |(_, row): &(&BlameLine, String), _: &str| row.clone(),
+    )
+    .detail(|(line, _)| commit_details(blame.commit(line), line))
+    .searchable(|(line, _)| {
+        let commit = blame.commit(line);
+        format!("{} {} {}", line.text, commit.author, commit.summary)
+    })
+    .bind(Binding::new(KeyCode::Backspace, Action::Custom("back")).hint("⌫", "back"))
+    .bind(Binding::new(KeyCode::Char('p'), Action::Custom("parent")).hint("p", "parent"))
+    .bind(Binding::new(KeyCode::Char('d'), Action::Custom("diff")).hint("d", "diff"))
+    .bind(Binding::new(KeyCode::Enter, Action::Custom("history")).hint("Enter", "history"))
+    .select(position.line);
+
+    loop {
+        match view.run() {
+            Ok(Outcome::Custom("diff", index)) => {
+                let commit = blame.commit(entries[index].0);
+                if !commit.is_committed() {
+                    continue;
+                }
+
+                let title = format!("{} {}", commit.short_hash(), commit.summary);
+                if let Err(ReturnType::Exit) = history::show_diff(&commit.hash, &title) {
+                    std::process::exit(1);
+                }
+            }
+            // The lines which aren't committed have no history
+            Ok(Outcome::Custom("history" | "parent", index))
+                if !blame.commit(entries[index].0).is_committed() => {}
+            Ok(outcome) => return Some(outcome),
+            Err(ReturnType::Cancel) => return None,
+            Err(ReturnType::Exit) => std::process::exit(1),
+        }
+    }
+}
+
+/// The markup of the hash, the author and the date of the commit, colored by its age.
+/// It's blank when `first` is false, to show the blocks of lines of the same commit.
+fn gutter(blame: &Blame, commit: &BlameCommit, first: bool) -> String {
+    let width = 8 + AUTHOR_WIDTH + 10 + 2;
+
+    match (first, commit.is_committed()) {
+        (false, _) => " ".repeat(width),
+        (true, false) => format!("$s `{:<width$}`", "Not committed yet", width = width),
+        (true, true) => {
+            let color = blame.age_color(commit);
+            let author: String = commit.author.chars().take(AUTHOR_WIDTH).collect();
+
+            format!(
+                "${color} `{}` ${color} `{:<author_width$}` ${color} `{}`",
+                commit.short_hash(),
+                escape(&author),
+                commit.author_date.format("%Y-%m-%d"),
+                color = color,
+                author_width = AUTHOR_WIDTH
+            )
+        }
+    }
+}
+
+fn commit_details(commit: &BlameCommit, line: &BlameLine) -> Vec<String> {
+    if !commit.is_committed() {
+        return vec!["$s `Not committed yet`".into()];
+    }
+
+    let mut details = vec![
+        format!("$@hash `{}`", commit.hash),
+        format!(
+            "$@author `{} <{}>`",
+            escape(&commit.author),
+            escape(&commit.email)
+        ),
+        format!(
+            "$@date `{}`",
+            commit.author_date.format("%a %b %-d %H:%M:%S %Y %z")
+        ),
+        String::new(),
+        format!("$b `{}`", escape(&commit.summary)),
+        String::new(),
+        format!("$s `Line {} in this commit`", line.original_number),
+    ];
+
+    match &commit.previous {
+        Some((hash, path)) => details.push(format!(
+            "$s `Parent {} ({})`",
+            &hash[..hash.len().min(8)],
+            escape(path)
+        )),
+        None => details.push("$s `The file starts in this commit`".into()),
+    }
+
+    details
+}
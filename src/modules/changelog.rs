@@ -0,0 +1,36 @@
+use clap::{Parser, ValueEnum};
+
+mod functions;
+mod views;
+
+pub use functions::{get_commits, group_entries, parse_entry, render_sections, Commit, Entry};
+pub use views::generate_changelog;
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum ChangelogFormat {
+    Markdown,
+    Json,
+}
+
+#[derive(Parser)]
+pub struct ChangelogOptions {
+    /// Start of the range (exclusive), defaults to the last tag before `--to`
+    #[clap(long)]
+    pub from: Option<String>,
+
+    /// End of the range
+    #[clap(long, default_value = "HEAD")]
+    pub to: String,
+
+    /// Output format
+    #[clap(long, value_enum, default_value = "markdown")]
+    pub format: ChangelogFormat,
+
+    /// Title of the release (defaults to the tag at `--to`, or "Unreleased")
+    #[clap(long)]
+    pub title: Option<String>,
+
+    /// Prepend the entries to a changelog file, skipping the ones already in it
+    #[clap(long, num_args = 0..=1, default_missing_value = "CHANGELOG.md", conflicts_with = "format")]
+    pub prepend: Option<String>,
+}
@@ -0,0 +1,404 @@
+use serde::Serialize;
+
+use crate::config::utils::{find_label, get_labels, CommitLabel};
+use crate::functions::git;
+
+#[derive(Clone, Debug)]
+pub struct Commit {
+    pub hash: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Entry {
+    pub hash: String,
+    /// The label title, `None` for commits without a label or conventional type
+    pub label: Option<String>,
+    pub emoji: Option<String>,
+    pub scope: Option<String>,
+    pub description: String,
+    pub breaking: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Section {
+    pub title: String,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Serialize)]
+pub struct Release {
+    pub title: String,
+    pub date: String,
+    pub sections: Vec<Section>,
+}
+
+/// Maps conventional commit types to the matching commit labels.
+const CONVENTIONAL_TYPES: [(&str, &str); 11] = [
+    ("feat", "New feature"),
+    ("fix", "Bug fix"),
+    ("docs", "Documentation"),
+    ("perf", "Performance"),
+    ("style", "Cosmetic"),
+    ("test", "Tests"),
+    ("refactor", "Refactor code"),
+    ("build", "Adding CI build system"),
+    ("ci", "Continuous Integration"),
+    ("chore", "General update"),
+    ("revert", "Reverting changes"),
+];
+
+pub fn get_commits(range: &str) -> Result<Vec<Commit>, String> {
+    let output = git(&["log", "--no-merges", "--format=%H%x1f%s%x1f%b%x1e", range])?;
+
+    Ok(parse_commits(&output))
+}
+
+/// Parses the output of `git log --format=%H%x1f%s%x1f%b%x1e`.
+fn parse_commits(output: &str) -> Vec<Commit> {
+    output
+        .split('\x1e')
+        .filter_map(|record| {
+            let mut fields = record.trim_start_matches('\n').split('\x1f');
+
+            Some(Commit {
+                hash: fields.next().filter(|hash| !hash.is_empty())?.to_string(),
+                subject: fields.next()?.to_string(),
+                body: fields.next().unwrap_or_default().trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Returns the last tag reachable from the given revision, excluding the revision itself.
+pub fn get_previous_tag(revision: &str) -> Option<String> {
+    git(&[
+        "describe",
+        "--tags",
+        "--abbrev=0",
+        &format!("{}^", revision),
+    ])
+    .ok()
+}
+
+/// Returns the tag pointing exactly at the revision, if any.
+pub fn get_exact_tag(revision: &str) -> Option<String> {
+    git(&["describe", "--tags", "--exact-match", revision]).ok()
+}
+
+pub fn get_commit_date(revision: &str) -> String {
+    git(&["log", "-1", "--format=%cs", revision]).unwrap_or_default()
+}
+
+fn find_label_by_name(name: &str) -> Option<CommitLabel> {
+    get_labels()
+        .into_iter()
+        .find(|label| label.label.trim() == name)
+}
+
+/// Parses a conventional commit subject (ex. `feat(parser)!: add escapes`).
+/// Returns the type, scope, breaking flag and description.
+fn parse_conventional(subject: &str) -> Option<(String, Option<String>, bool, String)> {
+    let (header, description) = subject.split_once(": ")?;
+    let (header, breaking) = match header.strip_suffix('!') {
+        Some(header) => (header, true),
+        None => (header, false),
+    };
+
+    let (kind, scope) = match header.split_once('(') {
+        Some((kind, scope)) => (kind, Some(scope.strip_suffix(')')?.to_string())),
+        None => (header, None),
+    };
+
+    if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    Some((
+        kind.to_lowercase(),
+        scope,
+        breaking,
+        description.trim().to_string(),
+    ))
+}
+
+/// Classifies a commit by its gitmoji label or conventional commit type.
+pub fn parse_entry(commit: &Commit) -> Entry {
+    let short_hash = commit.hash[..7.min(commit.hash.len())].to_string();
+    let breaking_body = commit.body.contains("BREAKING CHANGE");
+
+    if let Some(label) = find_label(&commit.subject) {
+        let description = commit
+            .subject
+            .trim_start()
+            .trim_start_matches(label.emoji.trim_end_matches('\u{fe0f}'))
+            .trim_start_matches('\u{fe0f}')
+            .trim()
+            .to_string();
+
+        return Entry {
+            hash: short_hash,
+            breaking: label.label == "Breaking changes" || breaking_body,
+            label: Some(label.label.trim().to_string()),
+            emoji: Some(label.emoji),
+            scope: None,
+            description,
+        };
+    }
+
+    if let Some((kind, scope, breaking, description)) = parse_conventional(&commit.subject) {
+        let label = CONVENTIONAL_TYPES
+            .iter()
+            .find(|(name, _)| *name == kind)
+            .and_then(|(_, label)| find_label_by_name(label));
+
+        if let Some(label) = label {
+            return Entry {
+                hash: short_hash,
+                label: Some(label.label.trim().to_string()),
+                emoji: Some(label.emoji),
+                scope,
+                description,
+                breaking: breaking || breaking_body,
+            };
+        }
+    }
+
+    Entry {
+        hash: short_hash,
+        label: None,
+        emoji: None,
+        scope: None,
+        description: commit.subject.trim().to_string(),
+        breaking: breaking_body,
+    }
+}
+
+/// Groups the entries into sections, in the order of the commit labels.
+pub fn group_entries(entries: Vec<Entry>) -> Vec<Section> {
+    let mut sections: Vec<Section> = get_labels()
+        .into_iter()
+        .map(|label| Section {
+            title: format!("{} {}", label.emoji, label.label.trim()),
+            entries: Vec::new(),
+        })
+        .collect();
+    let mut other = Section {
+        title: "Other changes".into(),
+        entries: Vec::new(),
+    };
+
+    for entry in entries {
+        let title = match (&entry.emoji, &entry.label) {
+            (Some(emoji), Some(label)) => format!("{} {}", emoji, label),
+            _ => {
+                other.entries.push(entry);
+                continue;
+            }
+        };
+
+        match sections.iter_mut().find(|section| section.title == title) {
+            Some(section) => section.entries.push(entry),
+            None => other.entries.push(entry),
+        }
+    }
+
+    sections.push(other);
+    sections.retain(|section| !section.entries.is_empty());
+
+    sections
+}
+
+fn render_entry(entry: &Entry) -> String {
+    let mut line = String::from("- ");
+
+    if entry.breaking {
+        line.push_str("**BREAKING** ");
+    }
+    if let Some(scope) = &entry.scope {
+        line.push_str(&format!("**{}:** ", scope));
+    }
+
+    line.push_str(&format!("{} ({})", entry.description, entry.hash));
+
+    line
+}
+
+/// Renders the sections as Markdown, with `###` headings.
+pub fn render_sections(sections: &[Section]) -> String {
+    sections
+        .iter()
+        .map(|section| {
+            let entries: Vec<String> = section.entries.iter().map(render_entry).collect();
+            format!("### {}\n\n{}\n", section.title, entries.join("\n"))
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+pub fn render_markdown(release: &Release) -> String {
+    let heading = match release.date.is_empty() {
+        true => format!("## {}", release.title),
+        false => format!("## {} ({})", release.title, release.date),
+    };
+
+    format!("{}\n\n{}", heading, render_sections(&release.sections))
+}
+
+pub fn render_json(release: &Release) -> String {
+    serde_json::to_string_pretty(release).unwrap()
+}
+
+/// Removes the entries which are already in the existing changelog (matched by their hash).
+pub fn remove_existing_entries(release: &mut Release, existing: &str) {
+    for section in release.sections.iter_mut() {
+        section
+            .entries
+            .retain(|entry| !existing.contains(&format!("({})", entry.hash)));
+    }
+
+    release
+        .sections
+        .retain(|section| !section.entries.is_empty());
+}
+
+/// Inserts the new release at the top of the changelog, below the main `#` heading (if any).
+pub fn prepend_release(existing: &str, release: &str) -> String {
+    if existing.trim().is_empty() {
+        return format!("# Changelog\n\n{}", release);
+    }
+
+    if existing.starts_with("# ") {
+        // Keep the heading and any introduction above the first release
+        let insert_at = existing
+            .find("\n## ")
+            .map(|i| i + 1)
+            .unwrap_or(existing.len());
+        let (head, tail) = existing.split_at(insert_at);
+        let head = head.trim_end();
+
+        return match tail.is_empty() {
+            true => format!("{}\n\n{}", head, release),
+            false => format!("{}\n\n{}\n{}", head, release, tail),
+        };
+    }
+
+    format!("{}\n{}", release, existing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(hash: &str, subject: &str) -> Commit {
+        Commit {
+            hash: hash.into(),
+            subject: subject.into(),
+            body: "".into(),
+        }
+    }
+
+    #[test]
+    fn test_parse_commits() {
+        let output = "aaa\x1f✨ Feature\x1fSome body\n\x1e\nbbb\x1f🐛 Fix\x1f\x1e\n";
+        let commits = parse_commits(output);
+
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].hash, "aaa");
+        assert_eq!(commits[0].body, "Some body");
+        assert_eq!(commits[1].subject, "🐛 Fix");
+    }
+
+    #[test]
+    fn test_parse_gitmoji_entry() {
+        let entry = parse_entry(&commit("0123456789", "✨ Add changelogs"));
+
+        assert_eq!(entry.hash, "0123456");
+        assert_eq!(entry.label.as_deref(), Some("New feature"));
+        assert_eq!(entry.description, "Add changelogs");
+        assert!(!entry.breaking);
+
+        // Variation selectors are optional
+        let entry = parse_entry(&commit("0123456789", "⬆ Bump serde"));
+        assert_eq!(entry.label.as_deref(), Some("Upgrading dependencies"));
+        assert_eq!(entry.description, "Bump serde");
+    }
+
+    #[test]
+    fn test_parse_conventional_entry() {
+        let entry = parse_entry(&commit("abcdef0", "feat(history)!: add search"));
+
+        assert_eq!(entry.label.as_deref(), Some("New feature"));
+        assert_eq!(entry.scope.as_deref(), Some("history"));
+        assert_eq!(entry.description, "add search");
+        assert!(entry.breaking);
+
+        let entry = parse_entry(&commit("abcdef0", "docs: fix typo"));
+        assert_eq!(entry.label.as_deref(), Some("Documentation"));
+
+        let entry = parse_entry(&commit("abcdef0", "wip: something"));
+        assert_eq!(entry.label, None);
+        assert_eq!(entry.description, "wip: something");
+    }
+
+    #[test]
+    fn test_group_and_render() {
+        let entries = vec![
+            parse_entry(&commit("1111111", "🐛 Fix crash")),
+            parse_entry(&commit("2222222", "feat: add tags")),
+            parse_entry(&commit("3333333", "Update README")),
+        ];
+        let sections = group_entries(entries);
+
+        assert_eq!(sections.len(), 3);
+        assert_eq!(sections[0].title, "✨ New feature");
+
+        let release = Release {
+            title: "v1.0.0".into(),
+            date: "2024-01-01".into(),
+            sections,
+        };
+
+        assert_eq!(
+            render_markdown(&release),
+            "## v1.0.0 (2024-01-01)\n\n\
+             ### ✨ New feature\n\n- add tags (2222222)\n\n\
+             ### 🐛 Bug fix\n\n- Fix crash (1111111)\n\n\
+             ### Other changes\n\n- Update README (3333333)\n"
+        );
+    }
+
+    #[test]
+    fn test_prepend_without_duplicates() {
+        let existing = "# Changelog\n\n## v1.0.0\n\n### 🐛 Bug fix\n\n- Fix crash (1111111)\n";
+        let mut release = Release {
+            title: "v1.1.0".into(),
+            date: "".into(),
+            sections: group_entries(vec![
+                parse_entry(&commit("1111111", "🐛 Fix crash")),
+                parse_entry(&commit("2222222", "✨ Add tags")),
+            ]),
+        };
+
+        remove_existing_entries(&mut release, existing);
+
+        assert_eq!(release.sections.len(), 1);
+
+        let content = prepend_release(existing, &render_markdown(&release));
+        assert_eq!(
+            content,
+            "# Changelog\n\n## v1.1.0\n\n### ✨ New feature\n\n- Add tags (2222222)\n\n\
+             ## v1.0.0\n\n### 🐛 Bug fix\n\n- Fix crash (1111111)\n"
+        );
+    }
+
+    #[test]
+    fn test_prepend_to_empty_file() {
+        assert_eq!(prepend_release("", "## v1\n"), "# Changelog\n\n## v1\n");
+        assert_eq!(
+            prepend_release("# Changelog\n", "## v1\n"),
+            "# Changelog\n\n## v1\n"
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use super::functions::{self, Release};
+use super::{ChangelogFormat, ChangelogOptions};
+use crate::view;
+
+pub fn generate_changelog(options: ChangelogOptions) {
+    if !crate::functions::is_git_repo() {
+        crate::out::print_error("Not a git repository");
+        std::process::exit(1);
+    }
+
+    let from = match options.from {
+        Some(from) => Some(from),
+        None => functions::get_previous_tag(&options.to),
+    };
+
+    let range = match &from {
+        Some(from) => format!("{}..{}", from, options.to),
+        None => options.to.clone(),
+    };
+
+    let commits = match functions::get_commits(&range) {
+        Ok(commits) => commits,
+        Err(err) => {
+            crate::out::print_error("Failed to read the commit history");
+            crate::out::print_error(&err);
+            std::process::exit(1);
+        }
+    };
+
+    let title = options
+        .title
+        .or_else(|| functions::get_exact_tag(&options.to))
+        .unwrap_or("Unreleased".into());
+
+    let mut release = Release {
+        title,
+        date: functions::get_commit_date(&options.to),
+        sections: functions::group_entries(commits.iter().map(functions::parse_entry).collect()),
+    };
+
+    let path = match options.prepend {
+        Some(path) => path,
+        None => {
+            match options.format {
+                ChangelogFormat::Markdown => print!("{}", functions::render_markdown(&release)),
+                ChangelogFormat::Json => println!("{}", functions::render_json(&release)),
+            }
+            return;
+        }
+    };
+
+    let existing = std::fs::read_to_string(&path).unwrap_or_default();
+
+    functions::remove_existing_entries(&mut release, &existing);
+
+    if release.sections.is_empty() {
+        view::printer(format!("\n$@success `✔ {} is already up to date.`\n", path));
+        return;
+    }
+
+    let count: usize = release.sections.iter().map(|s| s.entries.len()).sum();
+    let content = functions::prepend_release(&existing, &functions::render_markdown(&release));
+
+    match std::fs::write(&path, content) {
+        Ok(_) => {
+            view::printer(format!(
+                "\n$@success `✔ Added {} entries to {}.`\n",
+                count, path
+            ));
+        }
+        Err(err) => {
+            crate::out::print_error(&format!("Failed to write {}: {}", path, err));
+            std::process::exit(1);
+        }
+    }
+}
@@ -1,17 +1,21 @@
 use super::views::Repo;
+use crate::config::defines::PROTOCOL;
+
+/// The URL to clone the repository with, for the protocol.
+pub fn clone_url(repo: &Repo, protocol: PROTOCOL) -> &str {
+    match protocol {
+        PROTOCOL::SSH => &repo.ssh_url,
+        PROTOCOL::HTTPS => &repo.clone_url,
+    }
+}
 
 pub fn clone_repo(repo: Repo) {
-    use crate::config::{defines::PROTOCOL, load_config};
+    use crate::config::load_config;
     use spinners::{Spinner, Spinners};
     use std::process::Command;
 
     let config = load_config();
-
-    let url = if config.protocol == PROTOCOL::SSH {
-        repo.ssh_url
-    } else {
-        repo.clone_url
-    };
+    let url = clone_url(&repo, config.protocol);
 
     let mut spinner = Spinner::new(Spinners::Dots, "Cloning...".into());
 
@@ -47,30 +47,49 @@ async fn clone_private_repo() {
 fn clone_public_repo() {}
 
 async fn get_user_repos() -> Vec<Repo> {
-    let config = crate::config::load_config();
-    let url = "https://api.github.com/user/repos";
-    let token = config.token;
-    let user_agent = "tgh";
-    let client = reqwest::Client::new();
-    let mut headers = reqwest::header::HeaderMap::new();
-
-    headers.append(
-        "Authorization",
-        format!("Bearer {}", token).parse().unwrap(),
-    );
-    headers.append("Accept", "application/vnd.github+json".parse().unwrap());
-    headers.append("X-GitHub-Api-Version", "2022-11-28".parse().unwrap());
-    headers.append("User-Agent", user_agent.parse().unwrap());
-
-    let response: Vec<Repo> = client
-        .get(url)
-        .headers(headers)
-        .send()
+    fetch_user_repos(&crate::config::load_config())
         .await
         .unwrap()
-        .json()
-        .await
-        .unwrap();
+}
+
+/// Fetches the repositories of the user, from the host of the config.
+async fn fetch_user_repos(config: &crate::config::Config) -> Result<Vec<Repo>, reqwest::Error> {
+    use crate::config::github::{api_client_for, api_url_for};
+
+    let url = format!("{}/user/repos", api_url_for(config));
+
+    api_client_for(config).get(url).send().await?.json().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::defines::PROTOCOL;
+    use crate::config::github::tests::mock_server;
+
+    #[tokio::test]
+    async fn test_enterprise_clone_url() {
+        let (host, requests) = mock_server();
+        let config = crate::config::Config {
+            host: host.clone(),
+            token: "gho_123".into(),
+            ..Default::default()
+        };
 
-    return response;
+        let repos = fetch_user_repos(&config).await.unwrap();
+
+        assert_eq!(repos[0].full_name, "acme/tgh");
+        assert_eq!(
+            super::super::functions::clone_url(&repos[0], PROTOCOL::HTTPS),
+            format!("{}/acme/tgh.git", host)
+        );
+        assert_eq!(
+            super::super::functions::clone_url(&repos[0], PROTOCOL::SSH),
+            "git@ghe:acme/tgh.git"
+        );
+        assert_eq!(
+            *requests.lock().unwrap(),
+            vec!["/api/v3/user/repos gho_123"]
+        );
+    }
 }
@@ -3,9 +3,7 @@ use clap::Parser;
 mod functions;
 mod views;
 
-pub use views::commit_specific_files;
-
-use crate::view::input::ReturnType;
+pub use views::{ask_commit_message, commit_all_files, commit_specific_files};
 
 #[derive(Parser)]
 pub struct CommitOptions {
@@ -35,63 +33,3 @@ impl Default for CommitOptions {
         }
     }
 }
-
-pub fn commit_all_files(options: CommitOptions) {
-    functions::is_valid_commit();
-
-    match ask_commit_message(&options) {
-        Ok(msg) => {
-            functions::commit_all_files(msg, options.no_push);
-        }
-        Err(err) => match err {
-            ReturnType::Cancel => {
-                return;
-            }
-            ReturnType::Exit => {
-                std::process::exit(1);
-            }
-        },
-    }
-}
-
-fn ask_commit_message(options: &CommitOptions) -> Result<String, ReturnType> {
-    use crate::view::input;
-
-    if let Some(message) = &options.commit_message {
-        return Ok(message.clone());
-    }
-
-    let config = crate::config::load_config();
-
-    if options.force_fancy || (config.fancy && !options.skip_fancy) {
-        let mut message = String::new();
-        let labels = crate::config::utils::get_labels();
-
-        match input::list("Commit type: ", labels) {
-            Ok(label) => {
-                message.push_str(&label.emoji);
-            }
-            Err(err) => return Err(err),
-        }
-
-        match input::text("Commit message: ") {
-            Ok(msg) => {
-                message.push_str(&format!(" {}", msg));
-            }
-            Err(err) => return Err(err),
-        }
-
-        match input::text("Commit description (optional): ") {
-            Ok(desc) => {
-                if !desc.is_empty() {
-                    message.push_str(&format!("\n\n{}", desc));
-                }
-            }
-            Err(err) => return Err(err),
-        }
-
-        return Ok(message);
-    }
-
-    input::text("Enter commit message: ")
-}
@@ -81,6 +81,8 @@ fn commit_files(message: String, files: Vec<File>, no_push: bool) {
         files_to_add.push(file.name.clone());
     });
 
+    let before = crate::config::journal::snapshot();
+
     let mut spinner = Spinner::new(Spinners::Dots, "Committing...".into());
 
     let output = Command::new("git")
@@ -113,13 +115,17 @@ fn commit_files(message: String, files: Vec<File>, no_push: bool) {
     }
 
     if no_push {
+        crate::config::journal::record("commit", before, false);
         spinner.stop_with_symbol("✔");
         return;
     }
 
     let output = Command::new("git").arg("push").output().unwrap();
 
-    if !output.status.success() {
+    let pushed = output.status.success();
+    crate::config::journal::record("commit", before, pushed);
+
+    if !pushed {
         crate::out::print_error("Failed to push files");
 
         let out = String::from_utf8(output.stderr).unwrap();
@@ -1,136 +1,73 @@
+use super::functions::{self, File};
 use super::CommitOptions;
-use inquire::{list_option::ListOption, validator::Validation};
+use crate::view::input::{self, ReturnType};
 
-pub fn commit_specific_files(options: CommitOptions) {
-    use super::functions::{commit_specific_files, is_valid_commit};
+pub fn commit_all_files(options: CommitOptions) {
+    functions::is_valid_commit();
+
+    let message = exit_on_cancel(ask_commit_message(&options));
 
-    is_valid_commit();
+    functions::commit_all_files(message, options.no_push);
+}
 
-    let files = ask_files_to_commit();
+pub fn commit_specific_files(options: CommitOptions) {
+    functions::is_valid_commit();
 
-    let message = ask_commit_message(&options);
+    let files = exit_on_cancel(ask_files_to_commit());
+    let message = exit_on_cancel(ask_commit_message(&options));
 
-    commit_specific_files(files, message, options.no_push);
+    functions::commit_specific_files(files, message, options.no_push);
 }
 
-fn ask_commit_message(options: &CommitOptions) -> String {
-    use inquire::{Select, Text};
+/// Returns the answer, or exits if the prompt was canceled (Esc) or interrupted (Ctrl+C).
+fn exit_on_cancel<T>(result: Result<T, ReturnType>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(ReturnType::Cancel) => std::process::exit(0),
+        Err(ReturnType::Exit) => std::process::exit(1),
+    }
+}
 
-    let config = crate::config::load_config();
+fn ask_files_to_commit() -> Result<Vec<File>, ReturnType> {
+    input::multi_select("Files to commit: ", functions::get_files_to_commit())
+}
 
-    let mut message = String::new();
+/// Asks for the commit message, using the fancy commit prompts if enabled.
+/// The description is optional, and only asked for in the fancy mode.
+pub fn ask_commit_message(options: &CommitOptions) -> Result<String, ReturnType> {
+    if let Some(message) = &options.commit_message {
+        return Ok(message.clone());
+    }
 
-    let mut fancy = config.fancy;
+    let config = crate::config::load_config();
 
-    if options.force_fancy {
-        fancy = true;
+    if !(options.force_fancy || (config.fancy && !options.skip_fancy)) {
+        return input::text("Enter commit message: ");
     }
 
-    if options.skip_fancy {
-        fancy = false;
-    }
+    let labels = crate::config::utils::get_labels();
 
-    match fancy {
-        true => {
-            let labels = crate::config::utils::get_labels();
-
-            let icon = Select::new("Select label", labels).prompt();
-
-            match icon {
-                Ok(icon) => {
-                    message += &icon.emoji;
-                }
-                Err(_) => {
-                    crate::out::print_error("Error getting commit icon");
-                    std::process::exit(1);
-                }
-            }
-
-            let msg = Text::new("Commit message")
-                .with_help_message("Enter a commit message")
-                .prompt();
-
-            match msg {
-                Ok(msg) => {
-                    message += &format!(" {}", msg);
-                }
-                Err(_) => {
-                    crate::out::print_error("Error getting commit message");
-                    std::process::exit(1);
-                }
-            }
-
-            let description = Text::new("Commit description")
-                .with_help_message("Enter a commit description")
-                .prompt();
-
-            match description {
-                Ok(description) => {
-                    message += &format!("\n\n{}", description);
-                }
-                Err(_) => {
-                    crate::out::print_error("Error getting commit description");
-                    std::process::exit(1);
-                }
-            }
-        }
-        false => {
-            let msg = Text::new("Commit message")
-                .with_help_message("Enter a commit message")
-                .prompt();
-
-            match msg {
-                Ok(msg) => {
-                    message = msg;
-                }
-                Err(_) => {
-                    crate::out::print_error("Error getting commit message");
-                    std::process::exit(1);
-                }
-            }
-        }
-    }
+    let label = input::list("Commit type: ", labels)?;
+    let subject = input::text("Commit message: ")?;
+    let mut message = format!("{} {}", label.emoji, subject);
 
-    return message;
-}
-fn ask_files_to_commit() -> Vec<super::functions::File> {
-    use super::functions::get_files_to_commit;
-    use inquire::MultiSelect;
-
-    let mut files = Vec::new();
-
-    let changed_files = get_files_to_commit();
-
-    let choice = MultiSelect::new("Select files to commit", changed_files)
-        .with_validator(validate_file_selection)
-        .prompt();
-
-    match choice {
-        Ok(choice) => {
-            choice.iter().for_each(|file| {
-                files.push(file.clone());
-            });
-            if files.len() == 0 {
-                crate::out::print_error("You must select at least one file");
-                ask_files_to_commit();
-            }
-        }
-        Err(_) => {
-            crate::out::print_error("Error getting files to commit");
-            std::process::exit(1);
-        }
+    let description = input::multiline(
+        "Commit description (optional): ",
+        &description_template(&message),
+    )?;
+
+    if !description.is_empty() {
+        message.push_str(&format!("\n\n{}", description));
     }
 
-    return files;
+    Ok(message)
 }
-fn validate_file_selection(
-    files: &[ListOption<&super::functions::File>],
-) -> Result<inquire::validator::Validation, inquire::CustomUserError> {
-    if files.len() == 0 {
-        return Ok(Validation::Invalid(
-            "You must select at least one file".into(),
-        ));
-    }
 
-    return Ok(Validation::Valid);
+/// The comments shown below the commit description in the editor.
+fn description_template(subject: &str) -> String {
+    format!(
+        "Subject: {}\n\nWrite the commit description above, keep the lines under {} characters.",
+        subject,
+        crate::view::editor::BODY_GUIDE
+    )
 }
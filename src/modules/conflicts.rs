@@ -0,0 +1,14 @@
+use clap::Parser;
+
+mod functions;
+mod parser;
+mod views;
+
+pub use views::resolve_conflicts;
+
+#[derive(Parser)]
+pub struct ConflictsOptions {
+    /// Don't continue the rebase or merge after all conflicts are resolved
+    #[clap(long)]
+    pub no_continue: bool,
+}
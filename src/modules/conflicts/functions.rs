@@ -0,0 +1,212 @@
+use crate::functions::git;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConflictedFile {
+    pub path: String,
+    pub status: String,
+}
+
+impl ConflictedFile {
+    /// Describes the kind of conflict, based on the porcelain status code.
+    pub fn description(&self) -> &'static str {
+        match self.status.as_str() {
+            "UU" => "both modified",
+            "AA" => "both added",
+            "DD" => "both deleted",
+            "AU" => "added by us",
+            "UA" => "added by them",
+            "DU" => "deleted by us",
+            "UD" => "deleted by them",
+            _ => "unmerged",
+        }
+    }
+
+    /// Whether both sides of the conflict still have content (so the file has conflict markers).
+    pub fn has_content_conflict(&self) -> bool {
+        matches!(self.status.as_str(), "UU" | "AA")
+    }
+}
+
+impl std::fmt::Display for ConflictedFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.path, self.description())
+    }
+}
+
+/// The operation that left the repository with conflicts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operation {
+    Rebase,
+    Merge,
+    CherryPick,
+    Revert,
+}
+
+impl std::fmt::Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Operation::Rebase => "rebase",
+            Operation::Merge => "merge",
+            Operation::CherryPick => "cherry-pick",
+            Operation::Revert => "revert",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// Parses `git status --porcelain -z` output and returns the unmerged entries.
+/// The paths are relative to the root of the repository, and aren't quoted.
+pub fn parse_conflicted_files(status: &str) -> Vec<ConflictedFile> {
+    const UNMERGED: [&str; 7] = ["DD", "AU", "UD", "UA", "DU", "AA", "UU"];
+
+    let mut files = Vec::new();
+    let mut entries = status.split('\0');
+
+    while let Some(entry) = entries.next() {
+        if entry.len() < 4 {
+            continue;
+        }
+        let (code, path) = entry.split_at(3);
+
+        // A rename or copy is followed by the original path
+        if code.starts_with(['R', 'C']) {
+            entries.next();
+        }
+
+        let code = &code[..2];
+        if UNMERGED.contains(&code) {
+            files.push(ConflictedFile {
+                path: path.to_string(),
+                status: code.to_string(),
+            });
+        }
+    }
+
+    files
+}
+
+pub fn get_conflicted_files() -> Vec<ConflictedFile> {
+    match git(&["status", "--porcelain", "-z"]) {
+        Ok(status) => parse_conflicted_files(&status),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// The root of the repository, which the paths of the conflicted files are relative to.
+pub fn get_toplevel() -> Result<String, String> {
+    git(&["rev-parse", "--show-toplevel"])
+}
+
+fn git_path_exists(name: &str) -> bool {
+    match git(&["rev-parse", "--git-path", name]) {
+        Ok(path) => std::path::Path::new(&path).exists(),
+        Err(_) => false,
+    }
+}
+
+/// Detects which operation is currently in progress.
+pub fn get_operation() -> Option<Operation> {
+    if git_path_exists("rebase-merge") || git_path_exists("rebase-apply") {
+        return Some(Operation::Rebase);
+    }
+
+    if git_path_exists("MERGE_HEAD") {
+        return Some(Operation::Merge);
+    }
+
+    if git_path_exists("CHERRY_PICK_HEAD") {
+        return Some(Operation::CherryPick);
+    }
+
+    if git_path_exists("REVERT_HEAD") {
+        return Some(Operation::Revert);
+    }
+
+    None
+}
+
+pub fn mark_resolved(path: &str) -> Result<(), String> {
+    git(&["add", "--", path]).map(|_| ())
+}
+
+pub fn checkout_side(path: &str, ours: bool) -> Result<(), String> {
+    let side = if ours { "--ours" } else { "--theirs" };
+
+    git(&["checkout", side, "--", path]).map(|_| ())
+}
+
+pub fn remove_file(path: &str) -> Result<(), String> {
+    git(&["rm", "--quiet", "--", path]).map(|_| ())
+}
+
+/// Continues the operation, without opening an editor for the commit message.
+pub fn continue_operation(operation: Operation) -> Result<(), String> {
+    use std::process::Command;
+
+    let mut command = Command::new("git");
+    command.env("GIT_EDITOR", "true");
+
+    match operation {
+        Operation::Rebase => command.args(["rebase", "--continue"]),
+        Operation::Merge => command.args(["commit", "--no-edit"]),
+        Operation::CherryPick => command.args(["cherry-pick", "--continue"]),
+        Operation::Revert => command.args(["revert", "--continue"]),
+    };
+
+    let output = command.output().map_err(|err| err.to_string())?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        return Err(if stderr.is_empty() { stdout } else { stderr });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_conflicted_files() {
+        let status = [
+            "UU src/main.rs",
+            " M README.md",
+            "R  moved.rs",
+            "UU",
+            "AA new.rs",
+            "DU with space.rs",
+            "UU \u{e9}\"quoted\".txt",
+            "?? untracked",
+            "",
+        ]
+        .join("\0");
+        let files = parse_conflicted_files(&status);
+
+        assert_eq!(
+            files,
+            vec![
+                ConflictedFile {
+                    path: "src/main.rs".into(),
+                    status: "UU".into()
+                },
+                ConflictedFile {
+                    path: "new.rs".into(),
+                    status: "AA".into()
+                },
+                ConflictedFile {
+                    path: "with space.rs".into(),
+                    status: "DU".into()
+                },
+                ConflictedFile {
+                    path: "\u{e9}\"quoted\".txt".into(),
+                    status: "UU".into()
+                },
+            ]
+        );
+        assert!(files[0].has_content_conflict());
+        assert!(!files[2].has_content_conflict());
+    }
+}
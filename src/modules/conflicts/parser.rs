@@ -0,0 +1,376 @@
+const OURS_MARKER: &str = "<<<<<<<";
+const BASE_MARKER: &str = "|||||||";
+const SEPARATOR_MARKER: &str = "=======";
+const THEIRS_MARKER: &str = ">>>>>>>";
+
+/// A single conflict block, with the lines of each side (line endings included).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConflictBlock {
+    pub ours_label: String,
+    pub ours: Vec<String>,
+    /// Only present when the file was written with `merge.conflictStyle = diff3` (or `zdiff3`)
+    pub base_label: Option<String>,
+    pub base: Option<Vec<String>>,
+    pub theirs_label: String,
+    pub theirs: Vec<String>,
+    /// The whole block as it appears in the file, markers included
+    pub raw: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Text(Vec<String>),
+    Conflict(ConflictBlock),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Resolution {
+    Ours,
+    Theirs,
+    Both,
+    Custom(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    /// A marker appeared where it wasn't expected (1-based line number)
+    UnexpectedMarker(usize),
+    /// The file ended inside a conflict block
+    UnterminatedConflict,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ParseError::UnexpectedMarker(line) => {
+                write!(f, "unexpected conflict marker on line {}", line)
+            }
+            ParseError::UnterminatedConflict => write!(f, "unterminated conflict block"),
+        }
+    }
+}
+
+impl ConflictBlock {
+    fn resolve(&self, resolution: &Resolution) -> String {
+        match resolution {
+            Resolution::Ours => self.ours.concat(),
+            Resolution::Theirs => self.theirs.concat(),
+            Resolution::Both => {
+                let mut text = self.ours.concat();
+                if !text.is_empty() && !text.ends_with('\n') {
+                    text.push('\n');
+                }
+                text.push_str(&self.theirs.concat());
+                text
+            }
+            Resolution::Custom(text) => text.clone(),
+        }
+    }
+}
+
+/// Returns the label of the marker if the line is the given marker.
+fn match_marker<'a>(line: &'a str, marker: &str) -> Option<&'a str> {
+    let rest = line.strip_prefix(marker)?;
+    let rest = rest.trim_end_matches(['\n', '\r']);
+
+    if rest.is_empty() {
+        return Some("");
+    }
+
+    rest.strip_prefix(' ')
+}
+
+enum State {
+    Text,
+    Ours,
+    Base,
+    Theirs,
+}
+
+/// Parses a file containing git conflict markers into text and conflict segments.
+pub fn parse(content: &str) -> Result<Vec<Segment>, ParseError> {
+    let mut segments = Vec::new();
+    let mut text: Vec<String> = Vec::new();
+    let mut block: Option<ConflictBlock> = None;
+    let mut state = State::Text;
+
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        let line_number = index + 1;
+
+        match state {
+            State::Text => {
+                if let Some(label) = match_marker(line, OURS_MARKER) {
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut text)));
+                    }
+                    block = Some(ConflictBlock {
+                        ours_label: label.to_string(),
+                        ours: Vec::new(),
+                        base_label: None,
+                        base: None,
+                        theirs_label: String::new(),
+                        theirs: Vec::new(),
+                        raw: line.to_string(),
+                    });
+                    state = State::Ours;
+                } else {
+                    // The other markers are only special inside a block (ex. `=======` under a heading)
+                    text.push(line.to_string());
+                }
+            }
+            State::Ours | State::Base => {
+                let current = block.as_mut().unwrap();
+                current.raw.push_str(line);
+
+                if let Some(label) = match_marker(line, BASE_MARKER) {
+                    if matches!(state, State::Base) {
+                        return Err(ParseError::UnexpectedMarker(line_number));
+                    }
+                    current.base_label = Some(label.to_string());
+                    current.base = Some(Vec::new());
+                    state = State::Base;
+                } else if match_marker(line, SEPARATOR_MARKER).is_some() {
+                    state = State::Theirs;
+                } else if match_marker(line, OURS_MARKER).is_some()
+                    || match_marker(line, THEIRS_MARKER).is_some()
+                {
+                    return Err(ParseError::UnexpectedMarker(line_number));
+                } else if matches!(state, State::Base) {
+                    current.base.as_mut().unwrap().push(line.to_string());
+                } else {
+                    current.ours.push(line.to_string());
+                }
+            }
+            State::Theirs => {
+                block.as_mut().unwrap().raw.push_str(line);
+
+                if let Some(label) = match_marker(line, THEIRS_MARKER) {
+                    let mut finished = block.take().unwrap();
+                    finished.theirs_label = label.to_string();
+                    segments.push(Segment::Conflict(finished));
+                    state = State::Text;
+                } else if match_marker(line, OURS_MARKER).is_some()
+                    || match_marker(line, BASE_MARKER).is_some()
+                    || match_marker(line, SEPARATOR_MARKER).is_some()
+                {
+                    return Err(ParseError::UnexpectedMarker(line_number));
+                } else {
+                    block.as_mut().unwrap().theirs.push(line.to_string());
+                }
+            }
+        }
+    }
+
+    if block.is_some() {
+        return Err(ParseError::UnterminatedConflict);
+    }
+
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+
+    Ok(segments)
+}
+
+/// Returns the number of conflict blocks in the parsed segments.
+pub fn count_conflicts(segments: &[Segment]) -> usize {
+    segments
+        .iter()
+        .filter(|segment| matches!(segment, Segment::Conflict(_)))
+        .count()
+}
+
+/// Builds the resolved file content.
+/// `resolutions` is indexed by conflict block; blocks without a resolution keep their markers.
+pub fn resolve(segments: &[Segment], resolutions: &[Option<Resolution>]) -> String {
+    let mut content = String::new();
+    let mut conflict_index = 0;
+
+    for segment in segments {
+        match segment {
+            Segment::Text(lines) => content.push_str(&lines.concat()),
+            Segment::Conflict(block) => {
+                match resolutions.get(conflict_index).and_then(|r| r.as_ref()) {
+                    Some(resolution) => content.push_str(&block.resolve(resolution)),
+                    None => content.push_str(&block.raw),
+                }
+                conflict_index += 1;
+            }
+        }
+    }
+
+    content
+}
+
+/// Checks if the text still contains a part of a conflict block.
+/// Any opening, base or closing marker counts, but not a lone `=======` line,
+/// which is also a heading underline or a separator (ex. in Markdown).
+pub fn has_markers(content: &str) -> bool {
+    content.split_inclusive('\n').any(|line| {
+        match_marker(line, OURS_MARKER).is_some()
+            || match_marker(line, BASE_MARKER).is_some()
+            || match_marker(line, THEIRS_MARKER).is_some()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIMPLE: &str = "line 1
+<<<<<<< HEAD
+ours
+=======
+theirs
+>>>>>>> feature
+line 2
+";
+
+    const DIFF3: &str = "<<<<<<< HEAD
+ours 1
+ours 2
+||||||| merged common ancestors
+base
+=======
+theirs
+>>>>>>> origin/main
+";
+
+    #[test]
+    fn test_parse_without_conflicts() {
+        let segments = parse("a\nb\n").unwrap();
+
+        assert_eq!(
+            segments,
+            vec![Segment::Text(vec!["a\n".into(), "b\n".into()])]
+        );
+        assert_eq!(count_conflicts(&segments), 0);
+    }
+
+    #[test]
+    fn test_parse_simple_conflict() {
+        let segments = parse(SIMPLE).unwrap();
+
+        assert_eq!(segments.len(), 3);
+        assert_eq!(count_conflicts(&segments), 1);
+
+        match &segments[1] {
+            Segment::Conflict(block) => {
+                assert_eq!(block.ours_label, "HEAD");
+                assert_eq!(block.ours, vec!["ours\n".to_string()]);
+                assert_eq!(block.theirs_label, "feature");
+                assert_eq!(block.theirs, vec!["theirs\n".to_string()]);
+                assert_eq!(block.base, None);
+            }
+            _ => panic!("Expected a conflict segment"),
+        }
+    }
+
+    #[test]
+    fn test_parse_diff3_conflict() {
+        let segments = parse(DIFF3).unwrap();
+
+        match &segments[0] {
+            Segment::Conflict(block) => {
+                assert_eq!(block.ours.len(), 2);
+                assert_eq!(block.base_label.as_deref(), Some("merged common ancestors"));
+                assert_eq!(block.base, Some(vec!["base\n".to_string()]));
+                assert_eq!(block.theirs_label, "origin/main");
+            }
+            _ => panic!("Expected a conflict segment"),
+        }
+    }
+
+    #[test]
+    fn test_parse_keeps_crlf_and_missing_final_newline() {
+        let content = "a\r\n<<<<<<< HEAD\r\nx\r\n=======\r\ny\r\n>>>>>>> b\r\nend";
+        let segments = parse(content).unwrap();
+
+        assert_eq!(resolve(&segments, &[None]), content);
+        assert_eq!(
+            resolve(&segments, &[Some(Resolution::Theirs)]),
+            "a\r\ny\r\nend"
+        );
+    }
+
+    #[test]
+    fn test_parse_ignores_marker_like_lines() {
+        // Longer runs of marker characters are not markers
+        let content = "<<<<<<<< not a marker\n========\n";
+        let segments = parse(content).unwrap();
+
+        assert_eq!(count_conflicts(&segments), 0);
+    }
+
+    #[test]
+    fn test_parse_markers_outside_conflicts() {
+        let content = format!("Title\n=======\n>>>>>>> quoted\n{}", SIMPLE);
+        let segments = parse(&content).unwrap();
+
+        assert_eq!(count_conflicts(&segments), 1);
+        assert_eq!(
+            resolve(&segments, &[Some(Resolution::Ours)]),
+            "Title\n=======\n>>>>>>> quoted\nline 1\nours\nline 2\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse("<<<<<<< HEAD\na\n=======\nb\n"),
+            Err(ParseError::UnterminatedConflict)
+        );
+        assert_eq!(
+            parse("<<<<<<< HEAD\n<<<<<<< HEAD\n"),
+            Err(ParseError::UnexpectedMarker(2))
+        );
+    }
+
+    #[test]
+    fn test_resolve() {
+        let segments = parse(SIMPLE).unwrap();
+
+        assert_eq!(
+            resolve(&segments, &[Some(Resolution::Ours)]),
+            "line 1\nours\nline 2\n"
+        );
+        assert_eq!(
+            resolve(&segments, &[Some(Resolution::Theirs)]),
+            "line 1\ntheirs\nline 2\n"
+        );
+        assert_eq!(
+            resolve(&segments, &[Some(Resolution::Both)]),
+            "line 1\nours\ntheirs\nline 2\n"
+        );
+        assert_eq!(
+            resolve(&segments, &[Some(Resolution::Custom("merged\n".into()))]),
+            "line 1\nmerged\nline 2\n"
+        );
+    }
+
+    #[test]
+    fn test_unresolved_blocks_keep_markers() {
+        let segments = parse(SIMPLE).unwrap();
+        let content = resolve(&segments, &[None]);
+
+        assert_eq!(content, SIMPLE);
+        assert!(has_markers(&content));
+
+        let segments = parse(DIFF3).unwrap();
+        assert_eq!(resolve(&segments, &[]), DIFF3);
+    }
+
+    #[test]
+    fn test_has_markers() {
+        // Leftover parts of a block, including the base of a diff3 block
+        assert!(has_markers(SIMPLE));
+        assert!(has_markers("ours\n||||||| merged common ancestors\nbase\n"));
+        assert!(has_markers("<<<<<<< HEAD\nours\n"));
+        assert!(has_markers("theirs\n>>>>>>> feature\n"));
+
+        // A heading underline or a separator comment isn't a marker
+        assert!(!has_markers("Title\n=======\n\nText\n"));
+        assert!(!has_markers("let a = 1;\n// =======\n"));
+        assert!(!has_markers("a <<<<<<< b\n"));
+    }
+}
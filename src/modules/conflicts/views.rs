@@ -0,0 +1,324 @@
+use super::functions::{self, ConflictedFile};
+use super::parser::{self, ConflictBlock, Resolution, Segment};
+use super::ConflictsOptions;
+use crate::utils::out::{format_bold, format_color, format_dim, Color};
+use crate::view::{self, input, input::ReturnType};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Choice {
+    Ours,
+    Theirs,
+    Both,
+    Editor,
+    Skip,
+}
+
+impl std::fmt::Display for Choice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            Choice::Ours => "Keep ours",
+            Choice::Theirs => "Keep theirs",
+            Choice::Both => "Keep both (ours first)",
+            Choice::Editor => "Open in editor",
+            Choice::Skip => "Skip this file",
+        };
+
+        write!(f, "{}", text)
+    }
+}
+
+pub fn resolve_conflicts(options: ConflictsOptions) {
+    if !crate::functions::is_git_repo() {
+        crate::out::print_error("Not a git repository");
+        std::process::exit(1);
+    }
+
+    // The paths of the conflicted files are relative to the root of the repository
+    let moved = functions::get_toplevel()
+        .and_then(|root| std::env::set_current_dir(root).map_err(|err| err.to_string()));
+    if let Err(err) = moved {
+        crate::out::print_error(&format!("Failed to open the repository root: {}", err));
+        std::process::exit(1);
+    }
+
+    let operation = functions::get_operation();
+    let files = functions::get_conflicted_files();
+
+    if files.is_empty() {
+        match operation {
+            Some(operation) => {
+                view::printer(format!(
+                    "\n$@success `✔ No conflicts left.` $cw `A {} is in progress.`\n",
+                    operation
+                ));
+                if !options.no_continue {
+                    ask_continue(operation);
+                }
+            }
+            None => {
+                view::printer("\n$@success `✔ No conflicts to resolve.`\n");
+            }
+        }
+        return;
+    }
+
+    let during = match operation {
+        Some(operation) => format!(" during a {}", operation),
+        None => "".into(),
+    };
+    view::printer(format!(
+        "\n$@warning `{} conflicted file(s){}:`\n",
+        files.len(),
+        during
+    ));
+    for file in &files {
+        view::printer(format!(
+            "&> $cr `{}` $s `({})`\n",
+            file.path,
+            file.description()
+        ));
+    }
+
+    for file in &files {
+        let result = if file.has_content_conflict() {
+            resolve_file(file)
+        } else {
+            resolve_whole_file(file)
+        };
+
+        match result {
+            Ok(_) => {}
+            Err(ReturnType::Cancel) => {
+                view::printer(format!("$s `Skipped {}`\n", file.path));
+            }
+            Err(ReturnType::Exit) => std::process::exit(1),
+        }
+    }
+
+    let remaining = functions::get_conflicted_files();
+
+    if !remaining.is_empty() {
+        view::printer(format!(
+            "\n$@warning `{} file(s) still have conflicts.` $cw `Run` $@highlight `tgh conflicts` $cw `again when you're ready.`\n",
+            remaining.len()
+        ));
+        return;
+    }
+
+    view::printer("\n$@success `✔ All conflicts resolved.`\n");
+
+    if let Some(operation) = operation {
+        if !options.no_continue {
+            ask_continue(operation);
+        }
+    }
+}
+
+/// Resolves a file with conflict markers, block by block.
+fn resolve_file(file: &ConflictedFile) -> Result<(), ReturnType> {
+    let content = match std::fs::read_to_string(&file.path) {
+        Ok(content) => content,
+        Err(err) => {
+            crate::out::print_error(&format!("Failed to read {}: {}", file.path, err));
+            return Err(ReturnType::Cancel);
+        }
+    };
+
+    let segments = match parser::parse(&content) {
+        Ok(segments) => segments,
+        Err(err) => {
+            crate::out::print_error(&format!("Failed to parse {}: {}", file.path, err));
+            return Err(ReturnType::Cancel);
+        }
+    };
+
+    let total = parser::count_conflicts(&segments);
+    let mut resolutions: Vec<Option<Resolution>> = Vec::new();
+
+    for block in segments.iter().filter_map(|segment| match segment {
+        Segment::Conflict(block) => Some(block),
+        Segment::Text(_) => None,
+    }) {
+        print_block(file, block, resolutions.len() + 1, total);
+
+        let resolution = ask_resolution(file, block)?;
+        resolutions.push(Some(resolution));
+    }
+
+    let resolved = parser::resolve(&segments, &resolutions);
+
+    if let Err(err) = std::fs::write(&file.path, resolved) {
+        crate::out::print_error(&format!("Failed to write {}: {}", file.path, err));
+        return Err(ReturnType::Cancel);
+    }
+
+    if let Err(err) = functions::mark_resolved(&file.path) {
+        crate::out::print_error(&err);
+        return Err(ReturnType::Cancel);
+    }
+
+    view::printer(format!("$cg `✔ Resolved {}`\n", file.path));
+
+    Ok(())
+}
+
+fn ask_resolution(file: &ConflictedFile, block: &ConflictBlock) -> Result<Resolution, ReturnType> {
+    let choices = vec![
+        Choice::Ours,
+        Choice::Theirs,
+        Choice::Both,
+        Choice::Editor,
+        Choice::Skip,
+    ];
+
+    loop {
+        match input::list("Resolve with: ", choices.clone())? {
+            Choice::Ours => return Ok(Resolution::Ours),
+            Choice::Theirs => return Ok(Resolution::Theirs),
+            Choice::Both => return Ok(Resolution::Both),
+            Choice::Skip => return Err(ReturnType::Cancel),
+            Choice::Editor => match edit_block(file, block) {
+                Ok(text) => return Ok(Resolution::Custom(text)),
+                Err(err) => crate::out::print_error(&err),
+            },
+        }
+    }
+}
+
+/// Opens the conflict block in the user's editor, and returns the edited text.
+fn edit_block(file: &ConflictedFile, block: &ConflictBlock) -> Result<String, String> {
+    let extension = std::path::Path::new(&file.path)
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    let file = tempfile::Builder::new()
+        .prefix("tgh-conflict-")
+        .suffix(&extension)
+        .tempfile()
+        .map_err(|err| err.to_string())?;
+
+    std::fs::write(file.path(), &block.raw).map_err(|err| err.to_string())?;
+
+    crate::functions::open_editor(file.path())?;
+    let text = std::fs::read_to_string(file.path()).map_err(|err| err.to_string())?;
+
+    if parser::has_markers(&text) {
+        return Err("The edited block still contains conflict markers.".into());
+    }
+
+    Ok(text)
+}
+
+/// Resolves a conflict where at least one side deleted or didn't have the file.
+fn resolve_whole_file(file: &ConflictedFile) -> Result<(), ReturnType> {
+    view::printer(format!(
+        "\n$b `{}` $cy `{}`\n",
+        file.path,
+        file.description()
+    ));
+
+    let choices = vec![Choice::Ours, Choice::Theirs, Choice::Skip];
+
+    let ours = match input::list("Resolve with: ", choices)? {
+        Choice::Ours => true,
+        Choice::Theirs => false,
+        _ => return Err(ReturnType::Cancel),
+    };
+
+    // If the chosen side doesn't have the file, resolving means deleting it
+    let result = match functions::checkout_side(&file.path, ours) {
+        Ok(_) => functions::mark_resolved(&file.path),
+        Err(_) => functions::remove_file(&file.path),
+    };
+
+    match result {
+        Ok(_) => {
+            view::printer(format!("$cg `✔ Resolved {}`\n", file.path));
+            Ok(())
+        }
+        Err(err) => {
+            crate::out::print_error(&err);
+            Err(ReturnType::Cancel)
+        }
+    }
+}
+
+fn print_block(file: &ConflictedFile, block: &ConflictBlock, index: usize, total: usize) {
+    println!();
+    println!(
+        "{}",
+        format_bold(&format!("Conflict {}/{} in {}", index, total, file.path))
+    );
+
+    println!(
+        "{}",
+        format_bold(&format_color(
+            &format!("<<<<<<< ours ({})", block.ours_label),
+            Color::Green
+        ))
+    );
+    for line in &block.ours {
+        println!(
+            "{}",
+            format_color(line.trim_end_matches('\n'), Color::Green)
+        );
+    }
+
+    if let Some(base) = &block.base {
+        println!(
+            "{}",
+            format_bold(&format_color(
+                &format!(
+                    "||||||| base ({})",
+                    block.base_label.as_deref().unwrap_or("")
+                ),
+                Color::Yellow
+            ))
+        );
+        for line in base {
+            println!("{}", format_dim(line.trim_end_matches('\n')));
+        }
+    }
+
+    println!("{}", format_bold("======="));
+    for line in &block.theirs {
+        println!("{}", format_color(line.trim_end_matches('\n'), Color::Blue));
+    }
+    println!(
+        "{}",
+        format_bold(&format_color(
+            &format!(">>>>>>> theirs ({})", block.theirs_label),
+            Color::Blue
+        ))
+    );
+}
+
+fn ask_continue(operation: functions::Operation) {
+    let prompt = format!("Continue the {}? ", operation);
+
+    match input::list(&prompt, vec!["Yes", "No"]) {
+        Ok("Yes") => {}
+        Ok(_) | Err(ReturnType::Cancel) => return,
+        Err(ReturnType::Exit) => std::process::exit(1),
+    }
+
+    match functions::continue_operation(operation) {
+        Ok(_) => {
+            // A rebase can stop again on the next commit
+            let remaining = functions::get_conflicted_files();
+            if remaining.is_empty() {
+                view::printer(format!("$@success `✔ {} continued.`\n", operation));
+            } else {
+                view::printer(format!(
+                    "$@warning `The {} stopped with new conflicts.` $cw `Run` $@highlight `tgh conflicts` $cw `again.`\n",
+                    operation
+                ));
+            }
+        }
+        Err(err) => {
+            crate::out::print_error(&format!("Failed to continue the {}:", operation));
+            crate::out::print_error(&err);
+            std::process::exit(1);
+        }
+    }
+}
@@ -1,12 +1,13 @@
 use clap::{ArgGroup, Parser};
 
 mod functions;
+mod graph;
 mod views;
 
-pub use views::commit_history;
+pub use views::{commit_history, select_commit, show_diff};
 
 #[derive(Parser)]
-#[clap(group(ArgGroup::new("fileGroup").args(&["file", "limit", "author", "branch", "all"]).conflicts_with("hash")))]
+#[clap(group(ArgGroup::new("fileGroup").args(&["file", "limit", "author", "branch", "all", "since", "until", "grep", "label"]).multiple(true).conflicts_with("hash")))]
 pub struct CommitHistoryOptions {
     /// Limit the number of commits to show
     #[clap(short, long, default_value = "10")]
@@ -32,7 +33,41 @@ pub struct CommitHistoryOptions {
     #[clap(short, long)]
     pub file: Option<String>,
 
+    /// Show the commits more recent than a date (ex. `2024-01-31` or `2 weeks ago`)
+    #[clap(long)]
+    pub since: Option<String>,
+
+    /// Show the commits older than a date
+    #[clap(long)]
+    pub until: Option<String>,
+
+    /// Show the commits with a message matching the pattern
+    #[clap(long)]
+    pub grep: Option<String>,
+
+    /// Show the commits with a label, given as its name, emoji or conventional type (ex. `fix` or `🐛`)
+    #[clap(long)]
+    pub label: Option<String>,
+
     /// Show the commit with the given hash
     #[clap(long)]
     pub hash: Option<String>,
 }
+
+impl Default for CommitHistoryOptions {
+    fn default() -> Self {
+        Self {
+            limit: Some(10),
+            author: None,
+            branch: None,
+            all: false,
+            diff: false,
+            file: None,
+            since: None,
+            until: None,
+            grep: None,
+            label: None,
+            hash: None,
+        }
+    }
+}
@@ -1,3 +1,150 @@
+use chrono::{DateTime, FixedOffset};
+
+/// The fields of a commit for `git log -z --format`, separated by NUL.
+/// With `-z` the commits are ended by NUL too, so the output is read as groups of `FIELDS` values.
+/// NUL can't be a part of a commit message, so any subject or body is parsed as it is.
+pub const LOG_FORMAT: &str =
+    "%H%x00%h%x00%P%x00%D%x00%an%x00%ae%x00%aI%x00%cI%x00%cr%x00%s%x00%b%x00%(trailers:only,unfold)";
+
+/// The number of fields of `LOG_FORMAT`.
+const FIELDS: usize = 12;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Commit {
+    pub hash: String,
+    pub short_hash: String,
+    /// The hashes of the parents, rewritten to the shown commits with `--parents`
+    pub parents: Vec<String>,
+    /// The branches and tags pointing to the commit (ex. `HEAD -> main`, `tag: v1.0`)
+    pub refs: Vec<String>,
+    pub author: String,
+    pub email: String,
+    pub author_date: DateTime<FixedOffset>,
+    pub commit_date: DateTime<FixedOffset>,
+    /// The commit date relative to now (ex. `2 days ago`)
+    pub relative_date: String,
+    pub subject: String,
+    pub body: String,
+    /// The trailers at the end of the message (ex. `Co-authored-by`), as keys and values
+    pub trailers: Vec<(String, String)>,
+}
+
+impl Commit {
+    /// The author date, formatted like `git show`.
+    pub fn date(&self) -> String {
+        self.author_date
+            .format("%a %b %-d %H:%M:%S %Y %z")
+            .to_string()
+    }
+}
+
+/// Parses the output of `git log -z --format=LOG_FORMAT`, the malformed records are skipped.
+pub fn parse_log(output: &str) -> Vec<Commit> {
+    let fields: Vec<&str> = output.split('\0').collect();
+
+    fields
+        .chunks_exact(FIELDS)
+        .filter_map(parse_record)
+        .collect()
+}
+
+/// Runs `git log` with the arguments (including `-z --format=LOG_FORMAT`), and returns the first
+/// `limit` commits accepted by the filter. The output is parsed as git writes it,
+/// and git is stopped once enough commits are found (ex. for a filter git can't apply itself).
+pub fn read_log(
+    args: &[&str],
+    limit: usize,
+    filter: impl Fn(&Commit) -> bool,
+) -> Result<Vec<Commit>, String> {
+    use std::io::{BufRead, BufReader, Read};
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new("git")
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| err.to_string())?;
+
+    let mut commits = Vec::new();
+    let mut reader = BufReader::new(child.stdout.take().unwrap());
+    let mut record: Vec<String> = Vec::with_capacity(FIELDS);
+    let mut field = Vec::new();
+
+    while commits.len() < limit {
+        field.clear();
+        match reader.read_until(b'\0', &mut field) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(err) => return Err(err.to_string()),
+        }
+
+        let value = field.strip_suffix(b"\0").unwrap_or(&field);
+        record.push(String::from_utf8_lossy(value).into_owned());
+
+        if record.len() < FIELDS {
+            continue;
+        }
+
+        let fields: Vec<&str> = record.iter().map(String::as_str).collect();
+        if let Some(commit) = parse_record(&fields).filter(|commit| filter(commit)) {
+            commits.push(commit);
+        }
+        record.clear();
+    }
+
+    // The rest of the history isn't needed
+    if commits.len() == limit {
+        let _ = child.kill();
+        let _ = child.wait();
+        return Ok(commits);
+    }
+
+    let status = child.wait().map_err(|err| err.to_string())?;
+    if !status.success() {
+        let mut stderr = String::new();
+        let _ = child.stderr.take().unwrap().read_to_string(&mut stderr);
+        return Err(stderr.trim().to_string());
+    }
+
+    Ok(commits)
+}
+
+/// Parses the `FIELDS` values of a commit.
+fn parse_record(fields: &[&str]) -> Option<Commit> {
+    if fields.len() != FIELDS || fields[0].is_empty() {
+        return None;
+    }
+
+    Some(Commit {
+        hash: fields[0].to_string(),
+        short_hash: fields[1].to_string(),
+        parents: fields[2].split_whitespace().map(str::to_string).collect(),
+        refs: fields[3]
+            .split(", ")
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect(),
+        author: fields[4].to_string(),
+        email: fields[5].to_string(),
+        author_date: DateTime::parse_from_rfc3339(fields[6]).ok()?,
+        commit_date: DateTime::parse_from_rfc3339(fields[7]).ok()?,
+        relative_date: fields[8].to_string(),
+        subject: fields[9].to_string(),
+        body: fields[10].trim_end().to_string(),
+        trailers: parse_trailers(fields[11]),
+    })
+}
+
+/// Parses the `Key: value` lines of `%(trailers:only,unfold)`.
+fn parse_trailers(text: &str) -> Vec<(String, String)> {
+    text.lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .filter(|(key, _)| !key.is_empty() && !key.contains(' '))
+        .collect()
+}
+
 pub fn get_current_branch() -> String {
     use std::process::Command;
 
@@ -18,3 +165,206 @@ pub fn get_current_branch() -> String {
 
     branch.replace("*", "")
 }
+
+/// Returns the markup lines describing the commit, for the detail pane of the history.
+pub fn commit_details(commit: &Commit) -> Vec<String> {
+    use crate::view::escape;
+
+    let mut details = vec![
+        format!("$@hash `{}`", commit.hash),
+        format!(
+            "$@author `{} <{}>`",
+            escape(&commit.author),
+            escape(&commit.email)
+        ),
+        format!("$@date `{}`", commit.date()),
+        String::new(),
+        format!("$b `{}`", escape(&commit.subject)),
+    ];
+
+    if !commit.body.is_empty() {
+        details.push(String::new());
+        details.extend(commit.body.lines().map(escape));
+    }
+
+    match crate::functions::git(&["show", "--stat", "--color=never", "--format=", &commit.hash]) {
+        Ok(stat) => {
+            details.push(String::new());
+            details.extend(stat.lines().map(|line| escape(line.trim_end())));
+        }
+        Err(_) => details.push("$@error `Failed to load the changes`".into()),
+    }
+
+    details
+}
+
+/// Checks if the commit subject has the label, given as a label name, an emoji or a conventional type.
+/// The label of the commit comes from its emoji or its conventional type (see `changelog::parse_entry`).
+pub fn has_label(subject: &str, query: &str) -> bool {
+    use crate::modules::changelog::{parse_entry, Commit};
+
+    let entry = parse_entry(&Commit {
+        hash: String::new(),
+        subject: subject.to_string(),
+        body: String::new(),
+    });
+
+    let (label, emoji) = match (entry.label, entry.emoji) {
+        (Some(label), Some(emoji)) => (label, emoji),
+        _ => return false,
+    };
+
+    // A conventional type is matched by the label it maps to
+    let query = query.trim();
+    let wanted = parse_entry(&Commit {
+        hash: String::new(),
+        subject: format!("{}: x", query),
+        body: String::new(),
+    })
+    .label;
+
+    let without_selector = |emoji: &str| emoji.replace('\u{fe0f}', "");
+
+    label.eq_ignore_ascii_case(query)
+        || without_selector(&emoji) == without_selector(query)
+        || wanted.is_some_and(|wanted| wanted == label)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a `git log -z --format=LOG_FORMAT` record.
+    fn record(
+        hash: &str,
+        parents: &str,
+        refs: &str,
+        subject: &str,
+        body: &str,
+        trailers: &str,
+    ) -> String {
+        [
+            hash,
+            &hash[..7],
+            parents,
+            refs,
+            "Jane Doe",
+            "jane@example.com",
+            "2024-03-01T12:30:00+01:00",
+            "2024-03-02T08:00:00Z",
+            "2 days ago",
+            subject,
+            body,
+            trailers,
+        ]
+        .join("\0")
+            + "\0"
+    }
+
+    #[test]
+    fn test_parse_log() {
+        let output = [
+            record(
+                "1111111111",
+                "2222222222 3333333333",
+                "HEAD -> main, tag: v1.0, origin/main",
+                "Merge branch 'feat' -_- into main",
+                "",
+                "",
+            ),
+            record("2222222222", "", "", "🎉 Initial commit", "", ""),
+        ]
+        .concat();
+
+        let commits = parse_log(&output);
+        assert_eq!(commits.len(), 2);
+
+        let merge = &commits[0];
+        assert_eq!(merge.short_hash, "1111111");
+        assert_eq!(merge.parents, vec!["2222222222", "3333333333"]);
+        assert_eq!(merge.refs, vec!["HEAD -> main", "tag: v1.0", "origin/main"]);
+        assert_eq!(merge.subject, "Merge branch 'feat' -_- into main");
+        assert_eq!(merge.date(), "Fri Mar 1 12:30:00 2024 +0100");
+        assert_eq!(merge.commit_date.to_rfc3339(), "2024-03-02T08:00:00+00:00");
+
+        // A root commit has no parents
+        assert!(commits[1].parents.is_empty());
+        assert!(commits[1].refs.is_empty());
+    }
+
+    #[test]
+    fn test_parse_message() {
+        let body = "First paragraph\n\n  indented `code` & $HOME\n\nCo-authored-by: Bob <bob@example.com>\n";
+        let trailers =
+            "Co-authored-by: Bob <bob@example.com>\nSigned-off-by: Jane Doe <jane@example.com>\n";
+        let output = record(
+            "4444444444",
+            "1111111111",
+            "",
+            "fix: handle \x1f and | separators",
+            body,
+            trailers,
+        );
+
+        let commit = &parse_log(&output)[0];
+
+        assert_eq!(commit.subject, "fix: handle \x1f and | separators");
+        assert!(commit.body.starts_with("First paragraph\n\n  indented"));
+        assert!(commit.body.ends_with("<bob@example.com>"));
+        assert_eq!(
+            commit.trailers,
+            vec![
+                (
+                    "Co-authored-by".to_string(),
+                    "Bob <bob@example.com>".to_string()
+                ),
+                (
+                    "Signed-off-by".to_string(),
+                    "Jane Doe <jane@example.com>".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_record_separator() {
+        // The record separator can be a part of a message, the commits are delimited by NUL
+        let output = [
+            record("7777777777", "", "", "a \x1e b", "body\x1e\n", ""),
+            record("8888888888", "", "", "\x1e", "", ""),
+        ]
+        .concat();
+
+        let commits = parse_log(&output);
+
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].subject, "a \x1e b");
+        assert_eq!(commits[0].body, "body\x1e");
+        assert_eq!(commits[1].hash, "8888888888");
+        assert_eq!(commits[1].subject, "\x1e");
+    }
+
+    #[test]
+    fn test_parse_malformed() {
+        // Missing fields, an invalid date and the empty value after the last separator
+        let missing = "5555555555\x005555555\0";
+        let bad_date = record("6666666666", "", "", "subject", "", "")
+            .replace("2024-03-01T12:30:00+01:00", "yesterday");
+
+        assert!(parse_log(missing).is_empty());
+        assert!(parse_log(&bad_date).is_empty());
+        assert!(parse_log("").is_empty());
+    }
+
+    #[test]
+    fn test_has_label() {
+        assert!(has_label("🐛 Fix the parser", "fix"));
+        assert!(has_label("🐛 Fix the parser", "bug FIX"));
+        assert!(has_label("fix(parser): handle escapes", "🐛"));
+        assert!(has_label("⬆ Bump serde", "⬆️"));
+
+        assert!(!has_label("✨ Add search", "fix"));
+        assert!(!has_label("Update the readme", "docs"));
+        assert!(!has_label("fix(parser): handle escapes", "parser"));
+    }
+}
@@ -0,0 +1,247 @@
+use std::collections::HashSet;
+
+/// The colors of the lanes, a lane keeps its color while it's drawn.
+const LANE_COLORS: [&str; 6] = ["cr", "cg", "cy", "cb", "cm", "cc"];
+
+/// A row of the commit graph, drawn next to the commit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphRow {
+    /// The glyph of every lane, and the character joining it to the next lane
+    pub cells: Vec<(char, char)>,
+    /// The lane of the commit
+    pub column: usize,
+}
+
+impl GraphRow {
+    /// The row with every lane in its color, padded to `width` lanes.
+    pub fn markup(&self, width: usize) -> String {
+        let mut markup = String::new();
+
+        for (lane, (glyph, connector)) in self.cells.iter().enumerate() {
+            match glyph {
+                ' ' => markup.push(' '),
+                '●' => markup.push_str("$b `●`"),
+                _ => markup.push_str(&format!("${} `{}`", lane_color(lane), glyph)),
+            }
+
+            match connector {
+                ' ' => markup.push(' '),
+                // The horizontal lines have the color of the commit's lane
+                _ => markup.push_str(&format!("${} `{}`", lane_color(self.column), connector)),
+            }
+        }
+
+        markup + &"  ".repeat(width.saturating_sub(self.cells.len()))
+    }
+}
+
+fn lane_color(lane: usize) -> &'static str {
+    LANE_COLORS[lane % LANE_COLORS.len()]
+}
+
+/// Returns the first free lane, adding one when they're all taken.
+fn free_lane(lanes: &mut Vec<Option<String>>) -> usize {
+    match lanes.iter().position(Option::is_none) {
+        Some(lane) => lane,
+        None => {
+            lanes.push(None);
+            lanes.len() - 1
+        }
+    }
+}
+
+/// Picks the glyph of a lane at the left end, the right end or inside a horizontal line.
+fn pick(left: bool, right: bool, glyphs: [char; 3]) -> char {
+    match (left, right) {
+        (true, _) => glyphs[0],
+        (_, true) => glyphs[1],
+        _ => glyphs[2],
+    }
+}
+
+/// Lays out the commits (hashes and parent hashes, children before parents) in lanes.
+///
+/// Every lane waits for a commit: a commit is drawn in the first lane waiting for it,
+/// the other lanes waiting for it end there, and its first parent takes over its lane.
+/// The other parents of a merge join the lane already waiting for them, or start a new one.
+/// Parents which aren't in the list (ex. past the limit) don't get a lane.
+pub fn layout(commits: &[(String, Vec<String>)]) -> Vec<GraphRow> {
+    let known: HashSet<&str> = commits.iter().map(|(hash, _)| hash.as_str()).collect();
+    let mut lanes: Vec<Option<String>> = Vec::new();
+    let mut rows = Vec::with_capacity(commits.len());
+
+    for (hash, parents) in commits {
+        let parents: Vec<&String> = parents
+            .iter()
+            .filter(|parent| known.contains(parent.as_str()))
+            .collect();
+
+        let before = lanes.clone();
+        let waiting = |lane: &Option<String>| lane.as_ref() == Some(hash);
+
+        let column = match lanes.iter().position(waiting) {
+            Some(column) => column,
+            None => free_lane(&mut lanes),
+        };
+        let ending: Vec<usize> = (0..lanes.len())
+            .filter(|lane| *lane != column && waiting(&lanes[*lane]))
+            .collect();
+
+        lanes[column] = parents.first().map(|parent| parent.to_string());
+
+        let (mut starting, mut joining) = (Vec::new(), Vec::new());
+        for parent in parents.iter().skip(1) {
+            match lanes.iter().position(|lane| lane.as_ref() == Some(parent)) {
+                Some(lane) => joining.push(lane),
+                None => {
+                    let lane = free_lane(&mut lanes);
+                    lanes[lane] = Some(parent.to_string());
+                    starting.push(lane);
+                }
+            }
+        }
+
+        for lane in &ending {
+            lanes[*lane] = None;
+        }
+
+        let targets = ending.iter().chain(&starting).chain(&joining);
+        let low = targets.clone().copied().chain([column]).min().unwrap();
+        let high = targets.copied().chain([column]).max().unwrap();
+        let passing = |lane: usize| {
+            before.get(lane).is_some_and(Option::is_some)
+                && lanes.get(lane).is_some_and(Option::is_some)
+        };
+
+        let cells = (0..lanes.len().max(before.len()))
+            .map(|lane| {
+                // The lanes inside the horizontal line are joined from both sides
+                let (left, right) = (lane == low, lane == high);
+                let glyph = if lane == column {
+                    '●'
+                } else if ending.contains(&lane) {
+                    pick(left, right, ['╰', '╯', '┴'])
+                } else if starting.contains(&lane) {
+                    pick(left, right, ['╭', '╮', '┬'])
+                } else if joining.contains(&lane) {
+                    pick(left, right, ['├', '┤', '┼'])
+                } else if (low..=high).contains(&lane) {
+                    if passing(lane) {
+                        '┼'
+                    } else {
+                        '─'
+                    }
+                } else if passing(lane) {
+                    '│'
+                } else {
+                    ' '
+                };
+                let connector = match (low..high).contains(&lane) {
+                    true => '─',
+                    false => ' ',
+                };
+
+                (glyph, connector)
+            })
+            .collect();
+
+        while lanes.last().is_some_and(Option::is_none) {
+            lanes.pop();
+        }
+
+        rows.push(GraphRow { cells, column });
+    }
+
+    for row in rows.iter_mut() {
+        while row.cells.last() == Some(&(' ', ' ')) {
+            row.cells.pop();
+        }
+    }
+
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lays out a graph written as `hash:parent,parent` entries and returns the drawn rows.
+    fn draw(graph: &[&str]) -> Vec<String> {
+        let commits: Vec<(String, Vec<String>)> = graph
+            .iter()
+            .map(|entry| {
+                let (hash, parents) = entry.split_once(':').unwrap_or((entry, ""));
+                let parents = parents
+                    .split(',')
+                    .filter(|parent| !parent.is_empty())
+                    .map(str::to_string)
+                    .collect();
+
+                (hash.to_string(), parents)
+            })
+            .collect();
+
+        layout(&commits).iter().map(plain).collect()
+    }
+
+    fn plain(row: &GraphRow) -> String {
+        let row: String = row
+            .cells
+            .iter()
+            .flat_map(|(glyph, connector)| [*glyph, *connector])
+            .collect();
+
+        row.trim_end().to_string()
+    }
+
+    #[test]
+    fn test_linear() {
+        assert_eq!(draw(&["c:b", "b:a", "a"]), vec!["●", "●", "●"]);
+    }
+
+    #[test]
+    fn test_branch_and_merge() {
+        assert_eq!(
+            draw(&["m:a,b", "a:c", "b:c", "c"]),
+            vec!["●─╮", "● │", "│ ●", "●─╯"]
+        );
+    }
+
+    #[test]
+    fn test_branch_tips() {
+        // Two branches forking from `a`, `y` isn't waited for by any lane
+        assert_eq!(draw(&["x:a", "y:a", "a"]), vec!["●", "│ ●", "●─╯"]);
+    }
+
+    #[test]
+    fn test_crossing_lanes() {
+        // The merge of `c` crosses the lane waiting for `b`
+        assert_eq!(
+            draw(&["x:a,b", "a:z,c", "b:z", "c:z", "z"]),
+            vec!["●─╮", "●─┼─╮", "│ ● │", "│ │ ●", "●─┴─╯"]
+        );
+    }
+
+    #[test]
+    fn test_merge_into_waiting_lane() {
+        // `m` merges `b`, which the first lane is already waiting for
+        assert_eq!(
+            draw(&["x:b", "m:a,b", "b:a", "a"]),
+            vec!["●", "├─●", "● │", "●─╯"]
+        );
+    }
+
+    #[test]
+    fn test_octopus_merge() {
+        assert_eq!(
+            draw(&["m:a,b,c", "c:a", "b:a", "a"]),
+            vec!["●─┬─╮", "│ │ ●", "│ ● │", "●─┴─╯"]
+        );
+    }
+
+    #[test]
+    fn test_unknown_parents() {
+        // The parents past the limit don't leave lanes open
+        assert_eq!(draw(&["m:a,z", "a:y"]), vec!["●", "●"]);
+    }
+}
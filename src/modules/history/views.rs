@@ -1,288 +1,280 @@
-use crossterm::{
-    cursor::{MoveLeft, MoveUp},
-    terminal::{self, disable_raw_mode, enable_raw_mode},
-};
+use crossterm::event::KeyCode;
+use fuzzy_matcher::skim::SkimMatcherV2;
 
-use crate::utils::out::{format_bold, format_color, format_dim, format_underline};
+use crate::utils::out::{format_bold, format_color, format_role, format_underline};
+use crate::view::input::{highlight_matches, ReturnType};
+use crate::view::theme::{self, Role};
+use crate::view::tui::{Action, Binding, ListView, Outcome};
+use crate::view::{diff, escape};
 
-use super::CommitHistoryOptions;
+use crate::functions::git;
 
-#[derive(Debug)]
-struct Commit {
-    hash: String,
-    message: String,
-    date: String,
-    author: String,
-}
+use super::functions::{
+    commit_details, get_current_branch, has_label, parse_log, read_log, Commit, LOG_FORMAT,
+};
+use super::{graph, CommitHistoryOptions};
 
 pub fn commit_history(options: CommitHistoryOptions) {
-    use std::process::Command;
-
     let diff = options.diff;
 
-    if options.hash.is_some() {
-        print_commit(options.hash.unwrap().as_str(), diff);
+    if let Some(hash) = &options.hash {
+        print_commit(hash, diff);
         return;
     }
 
-    let limit = match options.limit {
-        Some(limit) => limit.to_string(),
-        None => "10".into(),
-    };
-
-    let author = match options.author {
-        Some(author) => author,
-        None => "".into(),
-    };
-
-    let mut branch = match options.branch {
-        Some(branch) => branch,
-        None => "".into(),
-    };
-
-    let file = match options.file {
-        Some(file) => file,
-        None => "".into(),
-    };
-
-    let all = options.all;
+    let (title, commits) = load_commits(options);
 
-    if branch.is_empty() && !all {
-        branch = super::functions::get_current_branch();
-    }
-
-    print!("\nShowing commits");
-    if !file.is_empty() {
-        print!(
-            " for {}",
-            format_color(file.as_str(), crate::utils::out::Color::Green)
-        );
-    }
-    if !author.is_empty() {
-        print!(
-            " made by {}",
-            format_color(author.as_str(), crate::utils::out::Color::Blue)
-        );
+    if commits.is_empty() {
+        crate::out::print_error("\nNo commits found\n");
+        return;
     }
-    println!(
-        " on {}",
-        format_color(branch.as_str(), crate::utils::out::Color::Yellow)
-    );
 
-    let mut binding = Command::new("git");
-    let command = binding
-        .arg("log")
-        .arg("--oneline")
-        .arg("--decorate")
-        .arg("--color")
-        .arg("--pretty=format:%h-_-%s-_-%cr-_-%an")
-        .arg("--full-history")
-        .arg(format!("-{}", limit))
-        .arg(format!("--author={}", author))
-        .arg(format!("{}", branch));
-
-    if !file.is_empty() {
-        command.arg(format!("{}", file));
+    if let Some(commit) = browse_commits(&title, &commits) {
+        print_commit(&commit.hash, diff);
     }
+}
 
-    let output = command.output().expect("Failed to execute git log");
-
-    let out = String::from_utf8(output.stdout).unwrap();
-
-    let commits: Vec<Commit> = out
-        .lines()
-        .map(|line| {
-            let mut parts = line.split("-_-");
-            Commit {
-                hash: parts.next().unwrap().into(),
-                message: parts.next().unwrap().into(),
-                date: parts.next().unwrap().into(),
-                author: parts.next().unwrap().into(),
-            }
-        })
-        .collect();
+/// Shows the history browser and returns the commit picked by the user.
+pub fn select_commit(options: CommitHistoryOptions) -> Option<Commit> {
+    let (title, commits) = load_commits(options);
 
-    if commits.len() == 0 {
+    if commits.is_empty() {
         crate::out::print_error("\nNo commits found\n");
-        return;
+        return None;
     }
 
-    let size = crossterm::terminal::size().unwrap();
+    browse_commits(&title, &commits).cloned()
+}
 
-    let mut window_size = size.1 as usize - 3;
+/// Returns the commits, and a title describing them.
+fn load_commits(options: CommitHistoryOptions) -> (String, Vec<Commit>) {
+    let limit = options.limit.unwrap_or(10);
 
-    if window_size > commits.len() {
-        window_size = commits.len();
+    let mut title = String::from("Showing commits");
+    if let Some(file) = &options.file {
+        title.push_str(&format!(" for {}", file));
     }
-
-    if window_size > 10 {
-        window_size = 10;
+    if let Some(author) = &options.author {
+        title.push_str(&format!(" made by {}", author));
+    }
+    if let Some(label) = &options.label {
+        title.push_str(&format!(" labeled {}", label));
+    }
+    match (options.all, &options.branch) {
+        (true, _) => title.push_str(" on all branches"),
+        (false, Some(branch)) => title.push_str(&format!(" on {}", branch)),
+        (false, None) => match get_current_branch().as_str() {
+            "" => title.push_str(" on HEAD"),
+            branch => title.push_str(&format!(" on {}", branch)),
+        },
     }
 
-    let mut index = 0;
-    let mut selected_index = 0;
-    let max_index = commits.len() - window_size;
-
-    for i in index..index + window_size {
-        if i < commits.len() {
-            let commit = &commits[i];
-            let hash = format_dim(format!("({})", commit.hash).as_str());
-            let message = commit.message.as_str();
-            let date = format_color(commit.date.as_str(), crate::utils::out::Color::Green);
-            let author = format_color(commit.author.as_str(), crate::utils::out::Color::Blue);
+    let mut args = vec![
+        "log".to_string(),
+        "-z".into(),
+        format!("--format={}", LOG_FORMAT),
+        "--full-history".into(),
+        "--parents".into(),
+        "--topo-order".into(),
+    ];
+
+    // The labels are matched while reading the commits, which stops after `limit` matches
+    if options.label.is_none() {
+        args.push(format!("-{}", limit));
+    }
+    if let Some(author) = options.author {
+        args.push(format!("--author={}", author));
+    }
+    if let Some(since) = options.since {
+        args.push(format!("--since={}", since));
+    }
+    if let Some(until) = options.until {
+        args.push(format!("--until={}", until));
+    }
+    if let Some(grep) = options.grep {
+        args.push(format!("--grep={}", grep));
+    }
 
-            if i == selected_index {
-                print!("> ");
-            } else {
-                print!("  ");
-            }
+    match (options.all, options.branch) {
+        (true, _) => args.push("--all".into()),
+        (false, Some(branch)) => args.push(branch),
+        (false, None) => {}
+    }
 
-            println!("{} - {} ({}) ~ {}", hash, message, date, author);
-        }
+    if let Some(file) = options.file {
+        args.extend(["--".into(), file]);
     }
 
-    enable_raw_mode().unwrap();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let label = options.label;
+    let matches_label = |commit: &Commit| match &label {
+        Some(label) => has_label(&commit.subject, label),
+        None => true,
+    };
 
-    loop {
-        match crossterm::event::read().unwrap() {
-            crossterm::event::Event::Key(event) => {
-                if event.code == crossterm::event::KeyCode::Down {
-                    if selected_index < commits.len() - 1 {
-                        selected_index += 1;
-                    }
-
-                    if index < max_index && selected_index >= index + window_size {
-                        index += 1;
-                    }
-
-                    render_commits(&commits, index, window_size, selected_index);
-                } else if event.code == crossterm::event::KeyCode::Up {
-                    if selected_index > 0 {
-                        selected_index -= 1;
-                    }
-
-                    if index > 0 && selected_index < index {
-                        index -= 1;
-                    }
-
-                    render_commits(&commits, index, window_size, selected_index);
-                } else if event.code == crossterm::event::KeyCode::Char('q') {
-                    break;
-                } else if event.code == crossterm::event::KeyCode::Enter {
-                    render_commit(&commits[selected_index], window_size, diff);
-                    return;
-                }
-            }
-            _ => {}
+    match read_log(&args, limit, matches_label) {
+        Ok(commits) => (title, commits),
+        Err(err) => {
+            crate::out::print_error("Failed to load the commits");
+            crate::out::print_error(&err);
+            std::process::exit(1);
         }
     }
-
-    disable_raw_mode().unwrap();
 }
 
-fn render_commits(commits: &Vec<Commit>, index: usize, window_size: usize, selected_index: usize) {
-    use crossterm::execute;
-    use std::io::{stdout, Write};
-
-    let mut stdout = stdout();
+/// Lets the user browse the commits, and returns the selected commit.
+/// The commits are drawn with the commit graph next to them, and `d` shows the diff of a commit.
+fn browse_commits<'a>(title: &str, commits: &'a [Commit]) -> Option<&'a Commit> {
+    let nodes: Vec<(String, Vec<String>)> = commits
+        .iter()
+        .map(|commit| (commit.hash.clone(), commit.parents.clone()))
+        .collect();
+    let graph = graph::layout(&nodes);
+    let lanes = graph.iter().map(|row| row.cells.len()).max().unwrap_or(0);
 
-    let _ = execute!(stdout, MoveUp(window_size as u16));
-    let _ = execute!(stdout, terminal::Clear(terminal::ClearType::FromCursorDown));
+    let entries: Vec<(&Commit, String)> = commits
+        .iter()
+        .zip(graph.iter().map(|row| row.markup(lanes)))
+        .collect();
 
-    for i in index..index + window_size {
-        if i < commits.len() {
-            let commit = &commits[i];
-            let hash = format_dim(format!("({})", commit.hash).as_str());
-            let message = commit.message.as_str();
-            let date = format_color(commit.date.as_str(), crate::utils::out::Color::Green);
-            let author = format_color(commit.author.as_str(), crate::utils::out::Color::Blue);
+    let matcher = SkimMatcherV2::default();
+    let row = move |(commit, graph): &(&Commit, String), query: &str| {
+        format!(
+            "{}$@hash `({})`{} - {} $@date `({})` ~ $@author `{}`",
+            graph,
+            highlight_matches(&commit.short_hash, &matcher, query),
+            format_refs(&commit.refs),
+            highlight_matches(&commit.subject, &matcher, query),
+            escape(&commit.relative_date),
+            highlight_matches(&commit.author, &matcher, query)
+        )
+    };
 
-            execute!(stdout, MoveLeft(1000)).unwrap();
+    let mut view = ListView::new(title, &entries, row)
+        .detail(|(commit, _)| commit_details(commit))
+        .searchable(|(commit, _)| {
+            format!("{} {} {}", commit.short_hash, commit.subject, commit.author)
+        })
+        .bind(Binding::new(KeyCode::Char('d'), Action::Custom("diff")).hint("d", "diff"));
 
-            if i == selected_index {
-                execute!(stdout, terminal::Clear(terminal::ClearType::CurrentLine)).unwrap();
-                print!("> ");
-            } else {
-                execute!(stdout, terminal::Clear(terminal::ClearType::CurrentLine)).unwrap();
-                print!("  ");
+    loop {
+        match view.run() {
+            Ok(Outcome::Selected(index)) => return Some(&commits[index]),
+            Ok(Outcome::Custom(_, index)) => {
+                if let Err(ReturnType::Exit) =
+                    show_diff(&commits[index].hash, &diff_title(&commits[index]))
+                {
+                    std::process::exit(1);
+                }
             }
-            writeln!(stdout, "{} - {} ({}) ~ {}", hash, message, date, author).unwrap();
+            Err(ReturnType::Cancel) => return None,
+            Err(ReturnType::Exit) => std::process::exit(1),
         }
     }
-    execute!(stdout, MoveLeft(1000)).unwrap();
-    stdout.flush().unwrap();
 }
-fn render_commit(commit: &Commit, window_size: usize, diff: bool) {
-    use crossterm::execute;
-    use std::io::stdout;
-
-    let mut stdout = stdout();
-
-    let _ = execute!(stdout, MoveUp(window_size as u16));
-    let _ = execute!(stdout, terminal::Clear(terminal::ClearType::FromCursorDown));
 
-    disable_raw_mode().unwrap();
-
-    let hash = commit.hash.as_str();
-
-    print_commit(hash, diff);
+/// Shows the changes of the commit in the diff viewer, with the title (ex. its subject).
+/// A merge is compared to its first parent, so it shows what the merge brought in.
+pub fn show_diff(hash: &str, title: &str) -> Result<(), ReturnType> {
+    let changes = git(&[
+        "show",
+        "--format=",
+        "--no-color",
+        "--no-ext-diff",
+        "--diff-merges=first-parent",
+        hash,
+    ])
+    .unwrap_or_default();
+
+    diff::show(title, &changes)
 }
 
-fn print_commit(hash: &str, diff: bool) {
-    use std::process::Command;
-
-    let mut binding = Command::new("git");
-    let command = binding
-        .arg("show")
-        .arg(hash)
-        .arg("--pretty=format:%H-_-%an-_-%ae-_-%ad-_-%s-_-%b")
-        .arg("--color");
-
-    if !diff {
-        command.arg("--compact-summary");
+/// Returns the markup of the refs, colored like `git log --decorate`.
+fn format_refs(refs: &[String]) -> String {
+    if refs.is_empty() {
+        return String::new();
     }
 
-    let output = command.output().expect("Failed to execute git show");
-
-    if !output.status.success() {
-        crate::out::print_error("\nCommit not found\n");
-        return;
-    }
-
-    let out = String::from_utf8(output.stdout).unwrap();
-
-    let binding = out.clone();
-    let header = binding.lines().next().unwrap();
-    let out = out.replace(header, "");
+    let names: Vec<String> = refs
+        .iter()
+        .map(|name| {
+            let color = if name.starts_with("HEAD") {
+                "$b$cc"
+            } else if name.starts_with("tag: ") {
+                "$b$cy"
+            } else if name.contains('/') {
+                "$b$cr"
+            } else {
+                "$b$cg"
+            };
 
-    let parts: Vec<&str> = header.split("-_-").collect();
+            format!("{} `{}`", color, escape(name))
+        })
+        .collect();
 
-    let hash = parts[0];
-    let author = parts[1];
-    let email = parts[2];
-    let date = parts[3];
-    let subject = parts[4];
-    let body = parts[5];
+    format!(" ({})", names.join(", "))
+}
 
+fn print_commit(hash: &str, diff: bool) {
     use crate::utils::out::Color;
 
-    println!("");
-    println!("Hash: ({})", format_dim(hash));
+    let commit = git(&[
+        "show",
+        "-s",
+        "-z",
+        &format!("--format={}", LOG_FORMAT),
+        hash,
+    ])
+    .ok()
+    .and_then(|output| parse_log(&output).into_iter().next());
+
+    let commit = match commit {
+        Some(commit) => commit,
+        None => {
+            crate::out::print_error("\nCommit not found\n");
+            return;
+        }
+    };
+
+    let color = match theme::colors_enabled() {
+        true => "--color=always",
+        false => "--color=never",
+    };
+    let summary = git(&[
+        "show",
+        "--format=",
+        color,
+        "--compact-summary",
+        &commit.hash,
+    ])
+    .unwrap_or_default();
+
+    println!();
+    println!("Hash: ({})", format_role(&commit.hash, Role::Hash));
     println!(
         "Author: {} <{}>",
-        format_color(author, Color::Blue),
-        format_underline(format_color(email, Color::Magenta).as_str())
+        format_role(&commit.author, Role::Author),
+        format_underline(format_color(&commit.email, Color::Magenta).as_str())
     );
-    println!("Date: {}", format_color(date, Color::Green));
+    println!("Date: {}", format_role(&commit.date(), Role::Date));
     println!(
         "Subject: {}",
-        format_bold(format_color(subject, Color::Yellow).as_str())
+        format_bold(format_color(&commit.subject, Color::Yellow).as_str())
     );
 
-    if !body.is_empty() {
-        println!("\nBody: {}", format_color(body, Color::Cyan));
+    if !commit.body.is_empty() {
+        println!("\nBody: {}", format_color(&commit.body, Color::Cyan));
     }
 
-    print!("\nChanges:");
-    println!("{}", out);
+    println!("\nChanges:\n{}", summary);
+
+    if diff {
+        if let Err(ReturnType::Exit) = show_diff(&commit.hash, &diff_title(&commit)) {
+            std::process::exit(1);
+        }
+    }
+}
+
+fn diff_title(commit: &Commit) -> String {
+    format!("{} {}", commit.short_hash, commit.subject)
 }
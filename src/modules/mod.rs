@@ -1,3 +1,11 @@
+pub mod amend;
+pub mod blame;
+pub mod changelog;
 pub mod clone;
 pub mod commit;
+pub mod conflicts;
 pub mod history;
+pub mod release;
+pub mod settings;
+pub mod stats;
+pub mod undo;
@@ -0,0 +1,36 @@
+use clap::Parser;
+
+mod functions;
+mod views;
+
+pub use views::create_release;
+
+#[derive(Parser)]
+pub struct ReleaseOptions {
+    /// The version to release (optional, defaults to the suggested version)
+    pub version: Option<String>,
+
+    /// Files to upload as release assets
+    #[clap(short, long, num_args = 1..)]
+    pub assets: Vec<String>,
+
+    /// Create the GitHub release as a draft
+    #[clap(long)]
+    pub draft: bool,
+
+    /// Mark the GitHub release as a pre-release
+    #[clap(long)]
+    pub prerelease: bool,
+
+    /// Only create the tag locally, without pushing it or creating a GitHub release
+    #[clap(long, conflicts_with_all = ["assets", "draft", "prerelease"])]
+    pub no_push: bool,
+
+    /// The remote to push the tag to
+    #[clap(long, default_value = "origin")]
+    pub remote: String,
+
+    /// Don't ask for confirmation, use the suggested version if none is given
+    #[clap(short, long)]
+    pub yes: bool,
+}
@@ -0,0 +1,257 @@
+use semver::Version;
+
+use crate::functions::git;
+use crate::modules::changelog::Entry;
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+#[derive(serde::Deserialize)]
+pub struct GithubRelease {
+    pub html_url: String,
+    pub upload_url: String,
+}
+
+/// Returns the tag of the newest version released on the current branch.
+/// Tags which aren't versions (ex. `nightly` or `docs-2024`) are skipped.
+pub fn get_last_tag() -> Option<String> {
+    last_tag_in(".")
+}
+
+fn last_tag_in(dir: &str) -> Option<String> {
+    let tags = git(&["-C", dir, "tag", "--merged", "HEAD"]).ok()?;
+
+    tags.lines()
+        .filter_map(|tag| Some((parse_tag(tag)?.1, tag)))
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, tag)| tag.to_string())
+}
+
+/// Decides how to bump the version, based on the labels of the commits.
+pub fn get_bump(entries: &[Entry]) -> Bump {
+    entries
+        .iter()
+        .map(|entry| {
+            if entry.breaking {
+                Bump::Major
+            } else if entry.label.as_deref() == Some("New feature") {
+                Bump::Minor
+            } else {
+                Bump::Patch
+            }
+        })
+        .fold(Bump::Patch, |max, bump| if bump > max { bump } else { max })
+}
+
+/// Splits a tag into its prefix and version (ex. `v1.2.3` -> (`v`, 1.2.3)).
+pub fn parse_tag(tag: &str) -> Option<(String, Version)> {
+    let start = tag.find(|c: char| c.is_ascii_digit())?;
+    let version = Version::parse(&tag[start..]).ok()?;
+
+    Some((tag[..start].to_string(), version))
+}
+
+pub fn next_version(current: Option<&Version>, bump: Bump) -> Version {
+    let current = match current {
+        Some(current) => current,
+        None => return Version::new(0, 1, 0),
+    };
+
+    // Before 1.0.0, breaking changes only bump the minor version
+    let bump = match (current.major, bump) {
+        (0, Bump::Major) => Bump::Minor,
+        (_, bump) => bump,
+    };
+
+    match bump {
+        Bump::Major => Version::new(current.major + 1, 0, 0),
+        Bump::Minor => Version::new(current.major, current.minor + 1, 0),
+        Bump::Patch if !current.pre.is_empty() => {
+            // Releasing a pre-release version (ex. 1.0.0-beta.1 -> 1.0.0)
+            Version::new(current.major, current.minor, current.patch)
+        }
+        Bump::Patch => Version::new(current.major, current.minor, current.patch + 1),
+    }
+}
+
+pub fn create_tag(tag: &str, message: &str) -> Result<(), String> {
+    git(&["tag", "--annotate", tag, "--message", message]).map(|_| ())
+}
+
+pub fn push_tag(remote: &str, tag: &str) -> Result<(), String> {
+    git(&["push", remote, &format!("refs/tags/{}", tag)]).map(|_| ())
+}
+
+pub async fn create_github_release(
+    repository: &(String, String),
+    tag: &str,
+    notes: &str,
+    draft: bool,
+    prerelease: bool,
+) -> Result<GithubRelease, String> {
+    use crate::config::github::{api_client, api_url};
+
+    let (owner, repo) = repository;
+    let url = format!("{}/repos/{}/{}/releases", api_url(), owner, repo);
+
+    let body = serde_json::json!({
+        "tag_name": tag,
+        "name": tag,
+        "body": notes,
+        "draft": draft,
+        "prerelease": prerelease,
+    });
+
+    let response = api_client()
+        .post(url)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        return Err(format!("GitHub responded with {}: {}", status, text));
+    }
+
+    response.json().await.map_err(|err| err.to_string())
+}
+
+pub async fn upload_asset(release: &GithubRelease, path: &str) -> Result<(), String> {
+    use crate::config::github::api_client;
+
+    let name = std::path::Path::new(path)
+        .file_name()
+        .ok_or(format!("Invalid asset path: {}", path))?
+        .to_string_lossy()
+        .to_string();
+    let content = std::fs::read(path).map_err(|err| format!("{}: {}", path, err))?;
+
+    // The upload URL is a URI template, ex. `.../assets{?name,label}`
+    let url = release.upload_url.split('{').next().unwrap_or_default();
+
+    let response = api_client()
+        .post(url)
+        .query(&[("name", name)])
+        .header("Content-Type", "application/octet-stream")
+        .body(content)
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        return Err(format!("GitHub responded with {}: {}", status, text));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(label: Option<&str>, breaking: bool) -> Entry {
+        Entry {
+            hash: "0123456".into(),
+            label: label.map(|label| label.to_string()),
+            emoji: None,
+            scope: None,
+            description: "".into(),
+            breaking,
+        }
+    }
+
+    #[test]
+    fn test_get_bump() {
+        let fix = entry(Some("Bug fix"), false);
+        let feature = entry(Some("New feature"), false);
+        let other = entry(None, false);
+        let breaking = entry(Some("Refactor code"), true);
+
+        assert_eq!(get_bump(&[fix.clone(), other]), Bump::Patch);
+        assert_eq!(get_bump(&[fix, feature.clone()]), Bump::Minor);
+        assert_eq!(get_bump(&[feature, breaking]), Bump::Major);
+        assert_eq!(get_bump(&[]), Bump::Patch);
+    }
+
+    #[test]
+    fn test_parse_tag() {
+        assert_eq!(
+            parse_tag("v1.2.3"),
+            Some(("v".into(), Version::new(1, 2, 3)))
+        );
+        assert_eq!(parse_tag("0.1.7"), Some(("".into(), Version::new(0, 1, 7))));
+        assert_eq!(parse_tag("latest"), None);
+    }
+
+    #[test]
+    fn test_next_version() {
+        let version = Version::new(1, 2, 3);
+
+        assert_eq!(
+            next_version(Some(&version), Bump::Patch),
+            Version::new(1, 2, 4)
+        );
+        assert_eq!(
+            next_version(Some(&version), Bump::Minor),
+            Version::new(1, 3, 0)
+        );
+        assert_eq!(
+            next_version(Some(&version), Bump::Major),
+            Version::new(2, 0, 0)
+        );
+
+        let version = Version::new(0, 1, 7);
+        assert_eq!(
+            next_version(Some(&version), Bump::Major),
+            Version::new(0, 2, 0)
+        );
+
+        let version = Version::parse("1.0.0-beta.1").unwrap();
+        assert_eq!(
+            next_version(Some(&version), Bump::Patch),
+            Version::new(1, 0, 0)
+        );
+
+        assert_eq!(next_version(None, Bump::Major), Version::new(0, 1, 0));
+    }
+
+    #[test]
+    fn test_last_tag_skips_other_tags() {
+        let repo = tempfile::tempdir().unwrap();
+        let dir = repo.path().to_str().unwrap();
+        let run = |args: &[&str]| {
+            let mut all = vec![
+                "-C",
+                dir,
+                "-c",
+                "user.name=Ada",
+                "-c",
+                "user.email=ada@example.com",
+            ];
+            all.extend(args);
+            git(&all).unwrap();
+        };
+
+        run(&["init", "-q"]);
+        assert_eq!(last_tag_in(dir), None);
+
+        run(&["commit", "-q", "--allow-empty", "-m", "First"]);
+        run(&["tag", "v0.9.0"]);
+        run(&["commit", "-q", "--allow-empty", "-m", "Second"]);
+        run(&["tag", "v0.10.0"]);
+        run(&["commit", "-q", "--allow-empty", "-m", "Third"]);
+        run(&["tag", "docs-2024"]);
+        run(&["tag", "nightly"]);
+
+        // The nearest tags aren't versions
+        assert_eq!(last_tag_in(dir).as_deref(), Some("v0.10.0"));
+    }
+}
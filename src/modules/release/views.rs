@@ -0,0 +1,175 @@
+use super::{functions, ReleaseOptions};
+use crate::modules::changelog;
+use crate::view::{self, input, input::ReturnType};
+
+pub async fn create_release(options: ReleaseOptions) {
+    if !crate::functions::is_git_repo() {
+        crate::out::print_error("Not a git repository");
+        std::process::exit(1);
+    }
+
+    for asset in &options.assets {
+        if !std::path::Path::new(asset).is_file() {
+            crate::out::print_error(&format!("Asset not found: {}", asset));
+            std::process::exit(1);
+        }
+    }
+
+    let repository = match options.no_push {
+        true => None,
+        false => match crate::functions::get_github_repository(&options.remote) {
+            Some(repository) => Some(repository),
+            None => {
+                crate::out::print_error(&format!(
+                    "Remote `{}` is not a GitHub repository",
+                    options.remote
+                ));
+                std::process::exit(1);
+            }
+        },
+    };
+
+    let last_tag = functions::get_last_tag();
+    let current = last_tag.as_deref().and_then(functions::parse_tag);
+
+    let range = match &last_tag {
+        Some(tag) => format!("{}..HEAD", tag),
+        None => "HEAD".into(),
+    };
+
+    let entries: Vec<changelog::Entry> = match changelog::get_commits(&range) {
+        Ok(commits) => commits.iter().map(changelog::parse_entry).collect(),
+        Err(err) => {
+            crate::out::print_error("Failed to read the commit history");
+            crate::out::print_error(&err);
+            std::process::exit(1);
+        }
+    };
+
+    if entries.is_empty() {
+        view::printer(format!(
+            "\n$@warning `Nothing to release, there are no commits since {}.`\n",
+            last_tag.unwrap_or_default()
+        ));
+        return;
+    }
+
+    let prefix = match &current {
+        Some((prefix, _)) => prefix.clone(),
+        None => "v".into(),
+    };
+    let bump = functions::get_bump(&entries);
+    let suggested = functions::next_version(current.as_ref().map(|(_, v)| v), bump);
+
+    view::printer(format!(
+        "\n$b `Last release:` $cy `{}` $s `({} commits since)`\n$b `Suggested version:` $@success `{}{}` $s `({:?} bump)`\n",
+        last_tag.as_deref().unwrap_or("none"),
+        entries.len(),
+        prefix,
+        suggested,
+        bump
+    ));
+
+    let version = match options.version {
+        Some(version) => version,
+        None if options.yes => suggested.to_string(),
+        None => match input::text(&format!("Version ({}{}): ", prefix, suggested)) {
+            Ok(version) if version.trim().is_empty() => suggested.to_string(),
+            Ok(version) => version,
+            Err(ReturnType::Cancel) => return,
+            Err(ReturnType::Exit) => std::process::exit(1),
+        },
+    };
+
+    let version = match functions::parse_tag(version.trim()) {
+        Some((_, version)) => version,
+        None => {
+            crate::out::print_error(&format!("`{}` is not a valid semver version", version));
+            std::process::exit(1);
+        }
+    };
+    let tag = format!("{}{}", prefix, version);
+
+    if crate::functions::git(&[
+        "rev-parse",
+        "--verify",
+        "--quiet",
+        &format!("refs/tags/{}", tag),
+    ])
+    .is_ok()
+    {
+        crate::out::print_error(&format!("Tag {} already exists", tag));
+        std::process::exit(1);
+    }
+
+    let notes = changelog::render_sections(&changelog::group_entries(entries));
+
+    println!();
+    println!("{}", notes);
+
+    if !options.yes && !confirm(&format!("Release {}? ", tag)) {
+        return;
+    }
+
+    if let Err(err) = functions::create_tag(&tag, &format!("Release {}\n\n{}", tag, notes)) {
+        crate::out::print_error("Failed to create the tag");
+        crate::out::print_error(&err);
+        std::process::exit(1);
+    }
+
+    view::printer(format!("$cg `✔ Created tag {}`\n", tag));
+
+    let repository = match repository {
+        Some(repository) => repository,
+        None => return,
+    };
+
+    if let Err(err) = functions::push_tag(&options.remote, &tag) {
+        crate::out::print_error("Failed to push the tag");
+        crate::out::print_error(&err);
+        std::process::exit(1);
+    }
+
+    view::printer(format!("$cg `✔ Pushed {} to {}`\n", tag, options.remote));
+
+    let release = match functions::create_github_release(
+        &repository,
+        &tag,
+        &notes,
+        options.draft,
+        options.prerelease,
+    )
+    .await
+    {
+        Ok(release) => release,
+        Err(err) => {
+            crate::out::print_error("Failed to create the GitHub release");
+            crate::out::print_error(&err);
+            std::process::exit(1);
+        }
+    };
+
+    for asset in &options.assets {
+        match functions::upload_asset(&release, asset).await {
+            Ok(_) => view::printer(format!("$cg `✔ Uploaded {}`\n", asset)),
+            Err(err) => {
+                crate::out::print_error(&format!("Failed to upload {}", asset));
+                crate::out::print_error(&err);
+                std::process::exit(1);
+            }
+        };
+    }
+
+    view::printer(format!(
+        "\n$@success `🔖 Released {}`\n&> $u `{}`\n",
+        tag, release.html_url
+    ));
+}
+
+fn confirm(prompt: &str) -> bool {
+    match input::list(prompt, vec!["Yes", "No"]) {
+        Ok(choice) => choice == "Yes",
+        Err(ReturnType::Cancel) => false,
+        Err(ReturnType::Exit) => std::process::exit(1),
+    }
+}
@@ -0,0 +1,46 @@
+use clap::{Parser, Subcommand};
+
+mod views;
+
+pub use views::handle_config_command;
+
+#[derive(Parser)]
+pub struct ConfigOptions {
+    #[clap(subcommand)]
+    pub action: ConfigAction,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Print the value of a setting (after applying the profile, repository and environment overrides)
+    Get {
+        /// The setting (username, token, sort, protocol, color, fancy, theme.<role> or profile.<name>.<field>)
+        key: String,
+    },
+
+    /// Change the value of a setting
+    Set {
+        /// The setting (username, token, sort, protocol, color, fancy, theme.<role> or profile.<name>.<field>)
+        key: String,
+        value: String,
+    },
+
+    /// List all settings
+    List {
+        /// Show where each value comes from (default, global, repository, env or command line)
+        #[clap(long)]
+        show_origin: bool,
+    },
+
+    /// List the account profiles (the active one is marked with `*`)
+    Profiles,
+
+    /// Open the config file in your editor
+    Edit,
+
+    /// Reset a setting (or all settings, except the username and token) to the default value
+    Reset {
+        /// The setting to reset (optional)
+        key: Option<String>,
+    },
+}
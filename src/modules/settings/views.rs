@@ -0,0 +1,228 @@
+use super::{ConfigAction, ConfigOptions};
+use crate::config::layers::{self, Layered};
+use crate::config::{utils, Config, KEYS};
+use crate::view;
+
+pub fn handle_config_command(options: ConfigOptions) {
+    match options.action {
+        ConfigAction::Get { key } => {
+            // Profiles are only stored in the global config
+            let config = match key.starts_with("profile.") {
+                true => read_config(),
+                false => resolve_config().config,
+            };
+
+            match config.get(&key) {
+                Ok(value) => println!("{}", value),
+                Err(err) => exit_with_error(&err),
+            }
+        }
+        ConfigAction::Set { key, value } => {
+            let mut config = read_saved_config();
+
+            if let Err(err) = config.set(&key, &value) {
+                exit_with_error(&err);
+            }
+
+            save_config(config);
+        }
+        ConfigAction::List { show_origin } => {
+            let layered = resolve_config();
+
+            for key in KEYS {
+                let mut value = layered.config.get(key).unwrap();
+
+                if key == "token" && !value.is_empty() {
+                    value = mask_token(&value);
+                }
+
+                match show_origin {
+                    true => println!("{}={}\t({})", key, value, layered.origin(key)),
+                    false => println!("{}={}", key, value),
+                }
+            }
+
+            for (role, style) in &layered.config.theme {
+                match show_origin {
+                    true => println!(
+                        "theme.{}={}\t({})",
+                        role,
+                        style,
+                        layers::Origin::Global(utils::get_config_path().unwrap_or_default())
+                    ),
+                    false => println!("theme.{}={}", role, style),
+                }
+            }
+        }
+        ConfigAction::Profiles => list_profiles(),
+        ConfigAction::Edit => edit_config(),
+        ConfigAction::Reset { key } => {
+            let mut config = read_saved_config();
+
+            match key {
+                Some(key) => {
+                    if let Err(err) = config.reset(&key) {
+                        exit_with_error(&err);
+                    }
+                }
+                None => {
+                    config = Config {
+                        username: config.username,
+                        token: config.token,
+                        profiles: config.profiles,
+                        ..Default::default()
+                    };
+                }
+            }
+
+            save_config(config);
+        }
+    }
+}
+
+fn resolve_config() -> Layered {
+    match layers::resolve() {
+        Ok(layered) => layered,
+        Err(err) => exit_with_error(&err),
+    }
+}
+
+/// Reads the global config file, or returns the default settings if it doesn't exist yet.
+fn read_config() -> Config {
+    if !utils::config_exists() {
+        return Config::default();
+    }
+
+    let contents = utils::read_path(utils::get_config_path()).unwrap_or_default();
+
+    match crate::config::migrations::deserialize(&contents) {
+        Ok((config, _)) => config,
+        Err(err) => exit_with_error(&format!(
+            "The config file is invalid ({}). Fix it with `tgh config edit`",
+            err
+        )),
+    }
+}
+
+/// Reads the global config file to change it. Before the first login there's no file,
+/// and saving the defaults would leave it without a username, so the change is refused.
+fn read_saved_config() -> Config {
+    if !utils::config_exists() {
+        exit_with_error("tgh isn't set up yet. Run `tgh` to log in, then change the settings");
+    }
+
+    read_config()
+}
+
+fn save_config(config: Config) {
+    if let Err(err) = utils::handle_config_folder() {
+        exit_with_error(&err.to_string());
+    }
+
+    utils::save_config_file(config);
+}
+
+fn list_profiles() {
+    let config = read_config();
+
+    if config.profiles.is_empty() {
+        println!(
+            "No profiles. Create one with `tgh config set profile.<name>.username <username>`"
+        );
+        return;
+    }
+
+    let active = layers::active_profile(&config).unwrap_or_else(|err| exit_with_error(&err));
+
+    for (name, profile) in &config.profiles {
+        let marker = match active.as_deref() == Some(name) {
+            true => "*",
+            false => " ",
+        };
+
+        println!(
+            "{} {}\t{}",
+            marker,
+            name,
+            profile.username.as_deref().unwrap_or(&config.username)
+        );
+
+        for remote in &profile.remotes {
+            println!("    remote: {}", remote);
+        }
+        for directory in &profile.directories {
+            println!("    directory: {}", directory);
+        }
+    }
+}
+
+fn mask_token(token: &str) -> String {
+    let visible: String = token.chars().take(4).collect();
+
+    format!(
+        "{}{}",
+        visible,
+        "*".repeat(token.chars().count().saturating_sub(4))
+    )
+}
+
+/// Opens a copy of the config file in the editor, and only saves it if it's valid.
+/// The copy holds the token, so it's only readable by the user.
+fn edit_config() {
+    use crate::view::input::{self, ReturnType};
+
+    let config = read_saved_config();
+    let file = match tempfile::Builder::new()
+        .prefix("tgh-config-")
+        .suffix(".json")
+        .tempfile()
+    {
+        Ok(file) => file,
+        Err(err) => exit_with_error(&format!("Failed to create a temporary file: {}", err)),
+    };
+    let path = file.path();
+    let mut contents = serde_json::to_string_pretty(&config).unwrap();
+
+    let config = loop {
+        if let Err(err) = std::fs::write(path, &contents) {
+            drop(file);
+            exit_with_error(&err.to_string());
+        }
+
+        if let Err(err) = crate::functions::open_editor(path) {
+            drop(file);
+            exit_with_error(&err);
+        }
+
+        contents = std::fs::read_to_string(path).unwrap_or_default();
+
+        match utils::parse_config(&contents) {
+            Ok(config) => break config,
+            Err(err) => {
+                crate::out::print_error(&format!("The config is invalid: {}", err));
+
+                match input::list("Edit it again? ", vec!["Yes", "No"]) {
+                    Ok("Yes") => continue,
+                    Ok(_) | Err(ReturnType::Cancel) => {
+                        view::printer("$cy `The config file was not changed.`\n");
+                        return;
+                    }
+                    Err(ReturnType::Exit) => {
+                        drop(file);
+                        std::process::exit(1);
+                    }
+                }
+            }
+        }
+    };
+
+    drop(file);
+
+    save_config(config);
+    view::printer("$@success `✔ Saved the config file.`\n");
+}
+
+fn exit_with_error(message: &str) -> ! {
+    crate::out::print_error(message);
+    std::process::exit(1);
+}
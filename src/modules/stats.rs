@@ -0,0 +1,29 @@
+use clap::Parser;
+
+mod functions;
+mod views;
+
+pub use views::show_stats;
+
+#[derive(Parser)]
+pub struct StatsOptions {
+    /// Count the commits more recent than a date (ex. `2024-01-31` or `1 year ago`)
+    #[clap(long)]
+    pub since: Option<String>,
+
+    /// Count the commits older than a date
+    #[clap(long)]
+    pub until: Option<String>,
+
+    /// Count the commits of all branches
+    #[clap(long)]
+    pub all: bool,
+
+    /// The number of authors and files shown
+    #[clap(short, long, default_value = "10")]
+    pub top: usize,
+
+    /// Print the statistics as JSON
+    #[clap(long)]
+    pub json: bool,
+}
@@ -0,0 +1,363 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Command, Stdio};
+
+use chrono::{DateTime, Datelike, FixedOffset, Timelike};
+use serde::Serialize;
+
+use crate::modules::changelog::{parse_entry, Commit};
+
+/// The format of the commit lines, the `--numstat` lines of the commit follow them.
+/// The commit lines start with `\x1e`, which a path can't start with.
+const LOG_FORMAT: &str = "%x1e%an%x00%aI%x00%s";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AuthorStats {
+    pub name: String,
+    pub commits: usize,
+    pub added: usize,
+    pub removed: usize,
+    /// The commits of every month, like `Stats::months`
+    pub activity: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FileStats {
+    pub path: String,
+    pub commits: usize,
+    pub added: usize,
+    pub removed: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LabelStats {
+    /// `None` for the commits without a label or conventional type
+    pub label: Option<String>,
+    pub emoji: Option<String>,
+    pub commits: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MonthStats {
+    /// The month, as `YYYY-MM`
+    pub month: String,
+    pub commits: usize,
+    pub added: usize,
+    pub removed: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Stats {
+    pub commits: usize,
+    pub added: usize,
+    pub removed: usize,
+    /// The dates of the first and the last commits, as `YYYY-MM-DD`
+    pub first_date: Option<String>,
+    pub last_date: Option<String>,
+    /// The authors with the most commits first
+    pub authors: Vec<AuthorStats>,
+    /// The files changed by the most commits first
+    pub files: Vec<FileStats>,
+    /// The labels of the most commits first
+    pub labels: Vec<LabelStats>,
+    /// The commits of every weekday, from Monday
+    pub weekdays: [usize; 7],
+    /// The commits of every hour, in the timezone of the author
+    pub hours: [usize; 24],
+    /// Every month from the first to the last commit, including the months without commits
+    pub months: Vec<MonthStats>,
+}
+
+/// The index of the month of a date, counted from year 0.
+fn month_index(date: &DateTime<FixedOffset>) -> i32 {
+    date.year() * 12 + date.month0() as i32
+}
+
+/// Collects the statistics from the lines of `git log`, one line at a time.
+#[derive(Default)]
+pub struct Collector {
+    stats: Stats,
+    /// The author and the month of the commit being read, for its `--numstat` lines
+    current: Option<(String, i32)>,
+    authors: HashMap<String, (AuthorStats, BTreeMap<i32, usize>)>,
+    files: HashMap<String, FileStats>,
+    labels: HashMap<(Option<String>, Option<String>), usize>,
+    months: BTreeMap<i32, MonthStats>,
+    first: Option<DateTime<FixedOffset>>,
+    last: Option<DateTime<FixedOffset>>,
+}
+
+impl Collector {
+    /// Reads a commit line (see `LOG_FORMAT`) or a `--numstat` line of the current commit.
+    /// The malformed lines are skipped.
+    pub fn add_line(&mut self, line: &str) {
+        match line.strip_prefix('\x1e') {
+            Some(commit) => self.add_commit(commit),
+            None => self.add_file(line),
+        }
+    }
+
+    fn add_commit(&mut self, line: &str) {
+        let fields: Vec<&str> = line.split('\0').collect();
+        let date = match fields.as_slice() {
+            [_, date, _] => DateTime::parse_from_rfc3339(date).ok(),
+            _ => None,
+        };
+        let date = match date {
+            Some(date) => date,
+            None => {
+                self.current = None;
+                return;
+            }
+        };
+        let (author, subject) = (fields[0], fields[2]);
+        let month = month_index(&date);
+
+        self.stats.commits += 1;
+        self.stats.weekdays[date.weekday().num_days_from_monday() as usize] += 1;
+        self.stats.hours[date.hour() as usize] += 1;
+        self.months.entry(month).or_default().commits += 1;
+
+        let (stats, activity) = self.authors.entry(author.to_string()).or_default();
+        stats.commits += 1;
+        *activity.entry(month).or_default() += 1;
+
+        let entry = parse_entry(&Commit {
+            hash: String::new(),
+            subject: subject.to_string(),
+            body: String::new(),
+        });
+        *self.labels.entry((entry.label, entry.emoji)).or_default() += 1;
+
+        // `git log` lists the newest commits first, but the order isn't assumed
+        if self.first.is_none_or(|first| date < first) {
+            self.first = Some(date);
+        }
+        if self.last.is_none_or(|last| date > last) {
+            self.last = Some(date);
+        }
+
+        self.current = Some((author.to_string(), month));
+    }
+
+    fn add_file(&mut self, line: &str) {
+        let mut parts = line.splitn(3, '\t');
+        let (added, removed, path) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(added), Some(removed), Some(path)) => (added, removed, path),
+            _ => return,
+        };
+        let (author, month) = match &self.current {
+            Some(current) => current,
+            None => return,
+        };
+
+        // The binary files are counted as `-`
+        let added: usize = added.parse().unwrap_or(0);
+        let removed: usize = removed.parse().unwrap_or(0);
+
+        self.stats.added += added;
+        self.stats.removed += removed;
+
+        let file = self.files.entry(path.to_string()).or_default();
+        file.commits += 1;
+        file.added += added;
+        file.removed += removed;
+
+        let month = self.months.entry(*month).or_default();
+        month.added += added;
+        month.removed += removed;
+
+        if let Some((author, _)) = self.authors.get_mut(author) {
+            author.added += added;
+            author.removed += removed;
+        }
+    }
+
+    /// Returns the statistics, with the `top` authors and files.
+    pub fn finish(self, top: usize) -> Stats {
+        let mut stats = self.stats;
+        // Every month from the first to the last, by index
+        let range: Vec<i32> = match (self.months.keys().next(), self.months.keys().last()) {
+            (Some(first), Some(last)) => (*first..=*last).collect(),
+            _ => Vec::new(),
+        };
+
+        stats.first_date = self.first.map(|date| date.format("%Y-%m-%d").to_string());
+        stats.last_date = self.last.map(|date| date.format("%Y-%m-%d").to_string());
+
+        stats.months = range
+            .iter()
+            .map(|index| MonthStats {
+                month: format!("{:04}-{:02}", index / 12, index % 12 + 1),
+                ..self.months.get(index).cloned().unwrap_or_default()
+            })
+            .collect();
+
+        stats.authors = self
+            .authors
+            .into_iter()
+            .map(|(name, (author, activity))| AuthorStats {
+                name,
+                activity: range
+                    .iter()
+                    .map(|index| activity.get(index).copied().unwrap_or(0))
+                    .collect(),
+                ..author
+            })
+            .collect();
+        stats
+            .authors
+            .sort_by(|a, b| b.commits.cmp(&a.commits).then(a.name.cmp(&b.name)));
+        stats.authors.truncate(top);
+
+        stats.files = self
+            .files
+            .into_iter()
+            .map(|(path, file)| FileStats { path, ..file })
+            .collect();
+        stats
+            .files
+            .sort_by(|a, b| b.commits.cmp(&a.commits).then(a.path.cmp(&b.path)));
+        stats.files.truncate(top);
+
+        stats.labels = self
+            .labels
+            .into_iter()
+            .map(|((label, emoji), commits)| LabelStats {
+                label,
+                emoji,
+                commits,
+            })
+            .collect();
+        stats
+            .labels
+            .sort_by(|a, b| b.commits.cmp(&a.commits).then(a.label.cmp(&b.label)));
+
+        stats
+    }
+}
+
+/// Collects the statistics from a single `git log --numstat`, read as it's written.
+pub fn collect(args: &[&str], top: usize) -> Result<Stats, String> {
+    let format = format!("--format={}", LOG_FORMAT);
+    let mut child = Command::new("git")
+        .args(["log", "--numstat", "--no-renames", &format])
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| err.to_string())?;
+
+    let mut collector = Collector::default();
+    if let Some(stdout) = child.stdout.take() {
+        // The lines are split by bytes, a path or a name might not be UTF-8
+        for line in BufReader::new(stdout).split(b'\n') {
+            let line = line.map_err(|err| err.to_string())?;
+            collector.add_line(&String::from_utf8_lossy(&line));
+        }
+    }
+
+    let mut error = String::new();
+    if let Some(mut stderr) = child.stderr.take() {
+        let _ = stderr.read_to_string(&mut error);
+    }
+
+    match child.wait() {
+        Ok(status) if status.success() => Ok(collector.finish(top)),
+        Ok(_) => Err(error.trim().to_string()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect_lines(lines: &[&str]) -> Stats {
+        let mut collector = Collector::default();
+        for line in lines {
+            collector.add_line(line);
+        }
+
+        collector.finish(10)
+    }
+
+    #[test]
+    fn test_collect() {
+        let stats = collect_lines(&[
+            "\x1eAda\x002024-03-04T10:30:00+01:00\x00🐛 Fix the parser",
+            "",
+            "3\t1\tsrc/parser.rs",
+            "-\t-\tlogo.png",
+            "\x1eBob\x002024-01-06T23:00:00-05:00\x00feat: add the parser",
+            "",
+            "10\t0\tsrc/parser.rs",
+            "2\t0\tREADME.md",
+            "\x1eAda\x002024-01-01T09:00:00+00:00\x00Initial files",
+            "malformed numstat line",
+        ]);
+
+        assert_eq!(stats.commits, 3);
+        assert_eq!((stats.added, stats.removed), (15, 1));
+        assert_eq!(stats.first_date.as_deref(), Some("2024-01-01"));
+        assert_eq!(stats.last_date.as_deref(), Some("2024-03-04"));
+
+        assert_eq!(stats.authors[0].name, "Ada");
+        assert_eq!(stats.authors[0].commits, 2);
+        assert_eq!(stats.authors[0].activity, vec![1, 0, 1]);
+        assert_eq!(stats.authors[1].added, 12);
+
+        assert_eq!(stats.files[0].path, "src/parser.rs");
+        assert_eq!(stats.files[0].commits, 2);
+        assert_eq!(stats.files[0].added, 13);
+
+        let months: Vec<(&str, usize, usize)> = stats
+            .months
+            .iter()
+            .map(|month| (month.month.as_str(), month.commits, month.added))
+            .collect();
+        assert_eq!(
+            months,
+            vec![("2024-01", 2, 12), ("2024-02", 0, 0), ("2024-03", 1, 3)]
+        );
+
+        // Monday, Saturday and Monday, in the timezone of the author
+        assert_eq!(stats.weekdays, [2, 0, 0, 0, 0, 1, 0]);
+        assert_eq!(
+            (stats.hours[9], stats.hours[10], stats.hours[23]),
+            (1, 1, 1)
+        );
+    }
+
+    #[test]
+    fn test_labels() {
+        let stats = collect_lines(&[
+            "\x1eAda\x002024-03-04T10:30:00+01:00\x00🐛 Fix the parser",
+            "\x1eAda\x002024-03-05T10:30:00+01:00\x00fix: the lexer",
+            "\x1eAda\x002024-03-06T10:30:00+01:00\x00Update",
+        ]);
+
+        assert_eq!(stats.labels[0].label.as_deref(), Some("Bug fix"));
+        assert_eq!(stats.labels[0].commits, 2);
+        assert_eq!(stats.labels[1].label, None);
+    }
+
+    #[test]
+    fn test_top() {
+        let mut collector = Collector::default();
+        for author in ["Ada", "Bob", "Cy"] {
+            collector.add_line(&format!(
+                "\x1e{}\x002024-03-04T10:30:00+01:00\x00Update",
+                author
+            ));
+        }
+
+        let stats = collector.finish(2);
+        let names: Vec<&str> = stats
+            .authors
+            .iter()
+            .map(|author| author.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Ada", "Bob"]);
+    }
+}
@@ -0,0 +1,163 @@
+use crate::view::{self, chart, escape};
+
+use super::functions::{collect, Stats};
+use super::StatsOptions;
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+/// The widest bars, the bars are narrower in small terminals.
+const MAX_BAR_WIDTH: usize = 40;
+/// The widest sparklines, longer timelines are summed into fewer columns.
+const MAX_SPARKLINE_WIDTH: usize = 48;
+
+pub fn show_stats(options: StatsOptions) {
+    let mut args = Vec::new();
+    if let Some(since) = &options.since {
+        args.push(format!("--since={}", since));
+    }
+    if let Some(until) = &options.until {
+        args.push(format!("--until={}", until));
+    }
+    if options.all {
+        args.push("--all".into());
+    }
+
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let stats = match collect(&args, options.top) {
+        Ok(stats) => stats,
+        Err(err) => {
+            crate::out::print_error("Failed to load the commits");
+            crate::out::print_error(&err);
+            std::process::exit(1);
+        }
+    };
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&stats).unwrap());
+        return;
+    }
+
+    if stats.commits == 0 {
+        crate::out::print_error("\nNo commits found\n");
+        return;
+    }
+
+    let width = crossterm::terminal::size().map_or(80, |(width, _)| width as usize);
+    view::printer(render(&stats, width));
+}
+
+/// A chart row: the label, its bar, the value and the extra markup (ex. the lines changed).
+fn chart_row(
+    label: &str,
+    label_width: usize,
+    value: usize,
+    max: usize,
+    bar_width: usize,
+) -> String {
+    format!(
+        "  {} $cc `{}` {}",
+        escape(&chart::pad(label, label_width)),
+        chart::pad(&chart::bar(value, max, bar_width), bar_width),
+        value
+    )
+}
+
+fn lines_changed(added: usize, removed: usize) -> String {
+    format!("$cg `+{}` $cr `-{}`", added, removed)
+}
+
+/// Renders the statistics as the markup of bar charts and sparklines, fitted in the width.
+fn render(stats: &Stats, width: usize) -> String {
+    let bar_width = width.saturating_sub(50).clamp(10, MAX_BAR_WIDTH);
+    let spark_width = width.saturating_sub(24).clamp(12, MAX_SPARKLINE_WIDTH);
+    let mut out = Vec::new();
+
+    let plural = |count: usize, noun: &str| match count {
+        1 => format!("1 {}", noun),
+        count => format!("{} {}s", count, noun),
+    };
+    out.push(format!(
+        "\n$b `{}` by {}, {} lines, from {} to {}",
+        plural(stats.commits, "commit"),
+        plural(stats.authors.len(), "author"),
+        lines_changed(stats.added, stats.removed),
+        stats.first_date.as_deref().unwrap_or_default(),
+        stats.last_date.as_deref().unwrap_or_default()
+    ));
+
+    out.push("\n$b `Commits by author` $s `(by month)`".into());
+    let max = stats.authors.first().map_or(0, |author| author.commits);
+    for author in &stats.authors {
+        out.push(format!(
+            "{} $s `{}`",
+            chart_row(&author.name, 20, author.commits, max, bar_width),
+            chart::sparkline(&chart::resample(&author.activity, spark_width))
+        ));
+    }
+
+    out.push("\n$b `Most changed files`".into());
+    let max = stats.files.first().map_or(0, |file| file.commits);
+    for file in &stats.files {
+        out.push(format!(
+            "{} {}",
+            chart_row(&file.path, 30, file.commits, max, bar_width),
+            lines_changed(file.added, file.removed)
+        ));
+    }
+
+    out.push("\n$b `Labels`".into());
+    let max = stats.labels.first().map_or(0, |label| label.commits);
+    for label in &stats.labels {
+        let name = match (&label.emoji, &label.label) {
+            (Some(emoji), Some(name)) => format!("{} {}", emoji, name),
+            _ => "No label".to_string(),
+        };
+        out.push(format!(
+            "{} $s `{}%`",
+            chart_row(&name, 24, label.commits, max, bar_width),
+            label.commits * 100 / stats.commits
+        ));
+    }
+
+    out.push("\n$b `Activity by weekday`".into());
+    let max = stats.weekdays.iter().copied().max().unwrap_or(0);
+    for (day, commits) in WEEKDAYS.iter().zip(stats.weekdays) {
+        out.push(chart_row(day, 3, commits, max, bar_width));
+    }
+
+    out.push("\n$b `Activity by hour`".into());
+    out.push(format!("  $cc `{}`", chart::sparkline(&stats.hours)));
+    out.push("  $s `0     6     12    18   23`".into());
+
+    let added: Vec<usize> = stats.months.iter().map(|month| month.added).collect();
+    let removed: Vec<usize> = stats.months.iter().map(|month| month.removed).collect();
+    let first = stats
+        .months
+        .first()
+        .map_or("", |month| month.month.as_str());
+    let last = stats.months.last().map_or("", |month| month.month.as_str());
+
+    let (added, removed) = (
+        chart::resample(&added, spark_width),
+        chart::resample(&removed, spark_width),
+    );
+    // The first and the last months under the ends of the sparklines
+    let axis = match first == last {
+        true => first.to_string(),
+        false => {
+            chart::pad(
+                first,
+                added.len().saturating_sub(last.len()).max(first.len() + 1),
+            ) + last
+        }
+    };
+
+    out.push("\n$b `Churn by month`".into());
+    out.push(format!("  $cg `{}` $s `added`", chart::sparkline(&added)));
+    out.push(format!(
+        "  $cr `{}` $s `removed`",
+        chart::sparkline(&removed)
+    ));
+    out.push(format!("  $s `{}`", axis));
+
+    out.join("\n") + "\n\n"
+}
@@ -0,0 +1,17 @@
+use clap::Parser;
+
+mod functions;
+mod views;
+
+pub use views::undo_last_operation;
+
+#[derive(Parser)]
+pub struct UndoOptions {
+    /// Don't ask for confirmation
+    #[clap(short, long)]
+    pub yes: bool,
+
+    /// Only undo the operation locally, even if it was pushed
+    #[clap(long)]
+    pub no_push: bool,
+}
@@ -0,0 +1,46 @@
+use crate::config::journal::Push;
+use crate::functions::git;
+
+pub fn get_head() -> Option<String> {
+    git(&["rev-parse", "--verify", "--quiet", "HEAD"]).ok()
+}
+
+pub fn get_branch() -> String {
+    git(&["symbolic-ref", "--quiet", "HEAD"]).unwrap_or_default()
+}
+
+/// Returns the commit the ref points to on the remote, or `None` if the ref doesn't exist.
+pub fn get_remote_commit(push: &Push) -> Result<Option<String>, String> {
+    let output = git(&["ls-remote", &push.remote, &push.remote_ref])?;
+
+    Ok(output
+        .lines()
+        .find(|line| line.ends_with(&push.remote_ref))
+        .and_then(|line| line.split_whitespace().next())
+        .map(|hash| hash.to_string()))
+}
+
+/// Moves HEAD back, keeping the working tree untouched.
+pub fn reset_head(head: Option<&str>) -> Result<(), String> {
+    match head {
+        Some(head) => git(&["reset", "--soft", head]),
+        // The operation created the first commit, so there's nothing to go back to
+        None => git(&["update-ref", "-d", "HEAD"]),
+    }
+    .map(|_| ())
+}
+
+pub fn restore_index(tree: &str) -> Result<(), String> {
+    git(&["read-tree", tree]).map(|_| ())
+}
+
+/// Moves the remote ref back to `head`, only if it still points to the pushed commit.
+pub fn force_push(push: &Push, head: &str) -> Result<(), String> {
+    git(&[
+        "push",
+        &format!("--force-with-lease={}:{}", push.remote_ref, push.commit),
+        &push.remote,
+        &format!("{}:{}", head, push.remote_ref),
+    ])
+    .map(|_| ())
+}
@@ -0,0 +1,160 @@
+use super::{functions, UndoOptions};
+use crate::config::journal::{self, Operation};
+use crate::view::{self, input, input::ReturnType};
+
+pub fn undo_last_operation(options: UndoOptions) {
+    if !crate::functions::is_git_repo() {
+        crate::out::print_error("Not a git repository");
+        std::process::exit(1);
+    }
+
+    let operation = match journal::last_operation() {
+        Some(operation) => operation,
+        None => {
+            view::printer("\n$@warning `Nothing to undo.`\n");
+            return;
+        }
+    };
+
+    let head = functions::get_head().unwrap_or_default();
+
+    if head != operation.head_after || functions::get_branch() != operation.branch {
+        crate::out::print_error(&format!(
+            "Refusing to undo `tgh {}`: HEAD has moved since it ran.",
+            operation.command
+        ));
+        std::process::exit(1);
+    }
+
+    let push = match &operation.pushed {
+        Some(push) if !options.no_push => Some(push),
+        _ => None,
+    };
+
+    if let Some(push) = push {
+        check_remote(&operation, push);
+    }
+
+    print_summary(&operation, push.is_some());
+
+    if !options.yes && !confirm("Undo this operation? ") {
+        return;
+    }
+
+    if let Err(err) = functions::reset_head(operation.head_before.as_deref()) {
+        crate::out::print_error("Failed to reset HEAD");
+        crate::out::print_error(&err);
+        std::process::exit(1);
+    }
+
+    if let Some(tree) = &operation.index_before {
+        if let Err(err) = functions::restore_index(tree) {
+            crate::out::print_error("Failed to restore the index");
+            crate::out::print_error(&err);
+            std::process::exit(1);
+        }
+    }
+
+    journal::remove_last_operation();
+
+    view::printer(format!(
+        "\n$@success `✔ Undid tgh {}.` $cw `Your changes have been restored.`\n",
+        operation.command
+    ));
+
+    let push = match push {
+        Some(push) => push,
+        None => return,
+    };
+
+    // `check_remote` guarantees there is a previous commit
+    let head_before = operation.head_before.as_deref().unwrap();
+
+    if !options.yes && !confirm("Force push (with lease) to revert the remote? ") {
+        view::printer(format!(
+            "$cw `The remote still has the commit. Run` $@highlight `git push --force-with-lease {} HEAD:{}` $cw `when you're ready.`\n",
+            push.remote, push.remote_ref
+        ));
+        return;
+    }
+
+    match functions::force_push(push, head_before) {
+        Ok(_) => {
+            view::printer(format!(
+                "$@success `✔ Reverted {} on {}.`\n",
+                push.remote_ref, push.remote
+            ));
+        }
+        Err(err) => {
+            crate::out::print_error("Failed to force push");
+            crate::out::print_error(&err);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Makes sure nobody else has pushed on top of the pushed commit.
+fn check_remote(operation: &Operation, push: &journal::Push) {
+    match functions::get_remote_commit(push) {
+        Ok(Some(commit)) if commit == push.commit => {}
+        Ok(_) => {
+            crate::out::print_error(&format!(
+                "Refusing to undo `tgh {}`: {} on {} has changed since it was pushed.",
+                operation.command, push.remote_ref, push.remote
+            ));
+            crate::out::print_error("Someone else may have pushed on top of it.");
+            std::process::exit(1);
+        }
+        Err(err) => {
+            crate::out::print_error("Failed to check the remote");
+            crate::out::print_error(&err);
+            std::process::exit(1);
+        }
+    }
+
+    if operation.head_before.is_none() {
+        crate::out::print_error(
+            "Refusing to undo: the first commit of the repository has already been pushed.",
+        );
+        std::process::exit(1);
+    }
+}
+
+fn print_summary(operation: &Operation, revert_push: bool) {
+    let date = chrono::DateTime::parse_from_rfc3339(&operation.timestamp)
+        .map(|date| {
+            date.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_default();
+
+    let head_before = match &operation.head_before {
+        Some(head) => head[..7].to_string(),
+        None => "(no commits)".into(),
+    };
+
+    view::printer(format!(
+        "\n$b `Last operation:` $cc `tgh {}` $s `({})`\n&> $cw `HEAD:` $@hash `{}` $cw `➜` $@hash `{}`\n",
+        operation.command,
+        date,
+        &operation.head_after[..7],
+        head_before
+    ));
+
+    if revert_push {
+        let push = operation.pushed.as_ref().unwrap();
+        view::printer(format!(
+            "&> $cw `Pushed to` $cm `{} {}`\n",
+            push.remote, push.remote_ref
+        ));
+    }
+}
+
+fn confirm(prompt: &str) -> bool {
+    match input::list(prompt, vec!["Yes", "No"]) {
+        Ok(choice) => choice == "Yes",
+        Err(ReturnType::Cancel) => false,
+        Err(ReturnType::Exit) => std::process::exit(1),
+    }
+}
@@ -1,3 +1,5 @@
+use crate::view::theme::{self, Role};
+
 pub enum Color {
     Red,
     Green,
@@ -31,36 +33,49 @@ pub fn print_bold(message: &str) {
 }
 
 pub fn format_error(message: &str) -> String {
-    format!("\x1B[1;31m{}\x1B[m", message)
+    theme::paint(Role::Error, message)
 }
 
 pub fn format_success(message: &str) -> String {
-    format!("\x1B[1;32m{}\x1B[m", message)
+    theme::paint(Role::Success, message)
+}
+
+/// Formats the message with the style of a theme role.
+pub fn format_role(message: &str, role: Role) -> String {
+    theme::paint(role, message)
+}
+
+/// Wraps the message in the ANSI code, unless the output isn't styled.
+fn format_ansi(code: &str, message: &str) -> String {
+    match theme::colors_enabled() {
+        true => format!("\x1B[{}m{}\x1B[m", code, message),
+        false => message.to_string(),
+    }
 }
 
 pub fn format_dim(message: &str) -> String {
-    format!("\x1B[2m{}\x1B[m", message)
+    format_ansi("2", message)
 }
 
 pub fn format_bold(message: &str) -> String {
-    format!("\x1B[1m{}\x1B[m", message)
+    format_ansi("1", message)
 }
 
 pub fn format_underline(message: &str) -> String {
-    format!("\x1B[4m{}\x1B[m", message)
+    format_ansi("4", message)
 }
 
 pub fn format_color(message: &str, color: Color) -> String {
     let color = match color {
-        Color::Red => 31,
-        Color::Green => 32,
-        Color::Yellow => 33,
-        Color::Blue => 34,
-        Color::Magenta => 35,
-        Color::Cyan => 36,
-        Color::White => 37,
-        Color::Black => 30,
+        Color::Red => "31",
+        Color::Green => "32",
+        Color::Yellow => "33",
+        Color::Blue => "34",
+        Color::Magenta => "35",
+        Color::Cyan => "36",
+        Color::White => "37",
+        Color::Black => "30",
     };
 
-    format!("\x1B[{}m{}\x1B[m", color, message)
+    format_ansi(color, message)
 }
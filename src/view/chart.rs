@@ -0,0 +1,111 @@
+use super::markup::display_width;
+
+/// The eighths of a block, for the end of a bar.
+const BLOCKS: [char; 8] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉', '█'];
+/// The heights of a sparkline, from the lowest.
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// A horizontal bar of the value, `width` columns long for the maximum.
+/// The bars are drawn in eighths of a column, and any value above zero gets at least one.
+pub fn bar(value: usize, max: usize, width: usize) -> String {
+    if value == 0 || max == 0 {
+        return String::new();
+    }
+
+    let eighths = ((value * width * 8 + max / 2) / max).max(1);
+    let mut bar = "█".repeat(eighths / 8);
+    if !eighths.is_multiple_of(8) {
+        bar.push(BLOCKS[eighths % 8 - 1]);
+    }
+
+    bar
+}
+
+/// A sparkline with a character for every value, scaled to the maximum.
+/// The zeros are blank, so they stand out from small values.
+pub fn sparkline(values: &[usize]) -> String {
+    let max = values.iter().copied().max().unwrap_or(0);
+
+    values
+        .iter()
+        .map(|value| match value {
+            0 => ' ',
+            value => SPARKS[(value * SPARKS.len()).div_ceil(max) - 1],
+        })
+        .collect()
+}
+
+/// Sums the values into at most `width` buckets, to fit a sparkline in the width.
+pub fn resample(values: &[usize], width: usize) -> Vec<usize> {
+    if values.len() <= width {
+        return values.to_vec();
+    }
+
+    (0..width)
+        .map(|i| {
+            let (start, end) = (i * values.len() / width, (i + 1) * values.len() / width);
+            values[start..end].iter().sum()
+        })
+        .collect()
+}
+
+/// Cuts the text to the width (ending it with `…`) or pads it with spaces, by display width.
+pub fn pad(text: &str, width: usize) -> String {
+    let mut padded = String::new();
+    let mut used = 0;
+
+    if display_width(text) > width {
+        for c in text.chars() {
+            let c_width = display_width(c.encode_utf8(&mut [0; 4]));
+            if used + c_width + 1 > width {
+                break;
+            }
+            padded.push(c);
+            used += c_width;
+        }
+        if width > 0 {
+            padded.push('…');
+            used += 1;
+        }
+    } else {
+        padded.push_str(text);
+        used = display_width(text);
+    }
+
+    padded + &" ".repeat(width.saturating_sub(used))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bar() {
+        assert_eq!(bar(10, 10, 4), "████");
+        assert_eq!(bar(5, 10, 3), "█▌");
+        assert_eq!(bar(1, 1000, 10), "▏");
+        assert_eq!(bar(0, 10, 10), "");
+        assert_eq!(bar(3, 0, 10), "");
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[0, 1, 4, 8]), " ▁▄█");
+        assert_eq!(sparkline(&[3, 3]), "██");
+        assert_eq!(sparkline(&[]), "");
+    }
+
+    #[test]
+    fn test_resample() {
+        assert_eq!(resample(&[1, 2, 3], 5), vec![1, 2, 3]);
+        assert_eq!(resample(&[1, 2, 3, 4, 5, 6], 3), vec![3, 7, 11]);
+        assert_eq!(resample(&[1, 1, 1, 1, 1], 2), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_pad() {
+        assert_eq!(pad("main.rs", 9), "main.rs  ");
+        assert_eq!(pad("src/main.rs", 8), "src/mai…");
+        assert_eq!(pad("日本語", 5), "日本…");
+    }
+}
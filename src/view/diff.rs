@@ -0,0 +1,1051 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+use std::io::IsTerminal;
+use std::ops::Range;
+use std::rc::Rc;
+
+use super::input::ReturnType;
+use super::markup::{self, Node};
+use super::syntax::Highlighter;
+use super::tui::{self, fit, Binding, Screen};
+
+/// From this width the diff is shown side by side, unless the layout was picked.
+const SIDE_BY_SIDE_WIDTH: u16 = 120;
+/// The longest lines (in words) compared word by word, the comparison grows with the square.
+const MAX_TOKENS: usize = 400;
+/// Shown instead of the diff when there are no changes (ex. an empty commit).
+const NO_CHANGES: &str = "No changes";
+/// Below this share of unchanged text the changed words aren't highlighted, as nearly
+/// everything would be.
+const MIN_SIMILARITY: f32 = 0.5;
+
+/// The changed byte ranges of a line.
+type Changes = Vec<Range<usize>>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffLine {
+    pub kind: LineKind,
+    /// The text without the `+`, `-` or ` ` prefix, with the tabs expanded
+    pub text: String,
+    pub old_number: Option<usize>,
+    pub new_number: Option<usize>,
+    /// The byte ranges which changed from the paired line (see `word_diff`)
+    pub changes: Changes,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hunk {
+    /// The `@@ -1,2 +1,3 @@` line
+    pub header: String,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileDiff {
+    /// `None` for an added file
+    pub old_path: Option<String>,
+    /// `None` for a deleted file
+    pub new_path: Option<String>,
+    pub binary: bool,
+    pub hunks: Vec<Hunk>,
+}
+
+impl FileDiff {
+    pub fn path(&self) -> &str {
+        self.new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// The path, with the old path of a renamed file and the state of an added or deleted one.
+    pub fn title(&self) -> String {
+        match (&self.old_path, &self.new_path) {
+            (Some(old), Some(new)) if old != new => format!("{} → {}", old, new),
+            (None, Some(new)) => format!("{} (added)", new),
+            (Some(old), None) => format!("{} (deleted)", old),
+            _ => self.path().to_string(),
+        }
+    }
+
+    /// The number of added and removed lines.
+    pub fn stats(&self) -> (usize, usize) {
+        let lines = self.hunks.iter().flat_map(|hunk| &hunk.lines);
+        let count = |kind| lines.clone().filter(|line| line.kind == kind).count();
+
+        (count(LineKind::Added), count(LineKind::Removed))
+    }
+}
+
+/// Parses `-12,5` (or `-12` for a single line) into the start and the count of lines.
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    match range[1..].split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((range[1..].parse().ok()?, 1)),
+    }
+}
+
+/// Parses a hunk header into the old and the new ranges.
+fn parse_hunk_header(line: &str) -> Option<((usize, usize), (usize, usize))> {
+    let mut parts = line.strip_prefix("@@ ")?.split(' ');
+    let old = parse_range(parts.next().filter(|part| part.starts_with('-'))?)?;
+    let new = parse_range(parts.next().filter(|part| part.starts_with('+'))?)?;
+
+    Some((old, new))
+}
+
+/// The path of a `--- a/file` or `+++ b/file` line, `None` for `/dev/null`.
+fn parse_path(path: &str) -> Option<String> {
+    let path = path.trim_end_matches('\t').trim_matches('"');
+
+    match path {
+        "/dev/null" => None,
+        _ => Some(
+            path.strip_prefix("a/")
+                .or(path.strip_prefix("b/"))
+                .unwrap_or(path)
+                .to_string(),
+        ),
+    }
+}
+
+/// Parses the output of `git diff` (or `git show`) without colors.
+/// The lines of the hunks are counted, so removed lines starting with `--` aren't headers.
+pub fn parse(diff: &str) -> Vec<FileDiff> {
+    let mut files: Vec<FileDiff> = Vec::new();
+    // The lines left in the current hunk, in the old and the new file
+    let (mut old_left, mut new_left) = (0, 0);
+    let (mut old_number, mut new_number) = (0, 0);
+
+    for line in diff.lines() {
+        let file = files.last_mut();
+
+        // `\ No newline at end of file`
+        if line.starts_with('\\') {
+            continue;
+        }
+
+        if old_left + new_left > 0 {
+            if let Some(hunk) = file.and_then(|file| file.hunks.last_mut()) {
+                let text = line.get(1..).unwrap_or_default().replace('\t', "    ");
+                let (kind, old, new) = match line.chars().next() {
+                    Some('+') => (LineKind::Added, None, Some(new_number)),
+                    Some('-') => (LineKind::Removed, Some(old_number), None),
+                    _ => (LineKind::Context, Some(old_number), Some(new_number)),
+                };
+
+                if old.is_some() {
+                    old_number += 1;
+                    old_left -= 1.min(old_left);
+                }
+                if new.is_some() {
+                    new_number += 1;
+                    new_left -= 1.min(new_left);
+                }
+
+                hunk.lines.push(DiffLine {
+                    kind,
+                    text,
+                    old_number: old,
+                    new_number: new,
+                    changes: Vec::new(),
+                });
+            }
+            continue;
+        }
+
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            // The paths are read again from the `---` and `+++` lines when there are changes
+            let (old, new) = paths.rsplit_once(" b/").unwrap_or((paths, paths));
+            files.push(FileDiff {
+                old_path: parse_path(old),
+                new_path: parse_path(new),
+                ..Default::default()
+            });
+            continue;
+        }
+
+        let file = match file {
+            Some(file) => file,
+            None => continue,
+        };
+
+        if let Some(path) = line.strip_prefix("--- ") {
+            file.old_path = parse_path(path);
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            file.new_path = parse_path(path);
+        } else if let Some(path) = line.strip_prefix("rename from ") {
+            file.old_path = Some(path.to_string());
+        } else if let Some(path) = line.strip_prefix("rename to ") {
+            file.new_path = Some(path.to_string());
+        } else if line.starts_with("new file mode") {
+            file.old_path = None;
+        } else if line.starts_with("deleted file mode") {
+            file.new_path = None;
+        } else if line.starts_with("Binary files") || line == "GIT binary patch" {
+            file.binary = true;
+        } else if let Some((old, new)) = parse_hunk_header(line) {
+            (old_number, old_left) = old;
+            (new_number, new_left) = new;
+            file.hunks.push(Hunk {
+                header: line.to_string(),
+                lines: Vec::new(),
+            });
+        }
+    }
+
+    for hunk in files.iter_mut().flat_map(|file| file.hunks.iter_mut()) {
+        pair_changes(&mut hunk.lines);
+    }
+
+    files
+}
+
+/// Compares the removed lines with the added lines following them, line by line.
+fn pair_changes(lines: &mut [DiffLine]) {
+    let mut i = 0;
+
+    while i < lines.len() {
+        let removed = lines[i..]
+            .iter()
+            .take_while(|line| line.kind == LineKind::Removed)
+            .count();
+        let added = lines[i + removed..]
+            .iter()
+            .take_while(|line| line.kind == LineKind::Added)
+            .count();
+
+        for pair in 0..removed.min(added) {
+            let (old, new) = (i + pair, i + removed + pair);
+            if let Some((old_changes, new_changes)) = word_diff(&lines[old].text, &lines[new].text)
+            {
+                lines[old].changes = old_changes;
+                lines[new].changes = new_changes;
+            }
+        }
+
+        i += (removed + added).max(1);
+    }
+}
+
+/// Splits the text into words, runs of spaces and single other characters.
+fn tokens(text: &str) -> Vec<Range<usize>> {
+    let class = |c: char| match c {
+        c if c.is_alphanumeric() || c == '_' => 0,
+        c if c.is_whitespace() => 1,
+        _ => 2,
+    };
+
+    let mut tokens: Vec<Range<usize>> = Vec::new();
+    let mut previous = None;
+
+    for (index, c) in text.char_indices() {
+        let current = class(c);
+        let end = index + c.len_utf8();
+
+        match tokens.last_mut() {
+            Some(last) if previous == Some(current) && current != 2 => last.end = end,
+            _ => tokens.push(index..end),
+        }
+        previous = Some(current);
+    }
+
+    tokens
+}
+
+/// Returns the byte ranges of the old and the new text which aren't in their longest
+/// common sequence of words. `None` when the texts are too long or too different.
+pub fn word_diff(old: &str, new: &str) -> Option<(Changes, Changes)> {
+    let (old_tokens, new_tokens) = (tokens(old), tokens(new));
+    if old_tokens.len() > MAX_TOKENS || new_tokens.len() > MAX_TOKENS {
+        return None;
+    }
+
+    let (n, m) = (old_tokens.len(), new_tokens.len());
+    let same = |i: usize, j: usize| old[old_tokens[i].clone()] == new[new_tokens[j].clone()];
+
+    // lengths[i][j] is the length of the common sequence of the tokens from i and j
+    let mut lengths = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i][j] = match same(i, j) {
+                true => lengths[i + 1][j + 1] + 1,
+                false => lengths[i + 1][j].max(lengths[i][j + 1]),
+            };
+        }
+    }
+
+    let (mut old_common, mut new_common) = (vec![false; n], vec![false; m]);
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if same(i, j) {
+            old_common[i] = true;
+            new_common[j] = true;
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    let common: usize = old_tokens
+        .iter()
+        .zip(&old_common)
+        .filter(|(_, common)| **common)
+        .map(|(token, _)| token.len())
+        .sum();
+    let total = old.len() + new.len();
+    if total > 0 && (2 * common) as f32 / (total as f32) < MIN_SIMILARITY {
+        return None;
+    }
+
+    let changed = |tokens: &[Range<usize>], common: &[bool]| {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+
+        for (token, _) in tokens.iter().zip(common).filter(|(_, common)| !**common) {
+            match ranges.last_mut() {
+                Some(last) if last.end == token.start => last.end = token.end,
+                _ => ranges.push(token.clone()),
+            }
+        }
+
+        ranges
+    };
+
+    Some((
+        changed(&old_tokens, &old_common),
+        changed(&new_tokens, &new_common),
+    ))
+}
+
+/// The markup of a line with the syntax highlighting, and the changed words on the background.
+fn style_line(
+    text: &str,
+    syntax: &[(Range<usize>, &'static str)],
+    changes: &[Range<usize>],
+    changed: &str,
+) -> String {
+    let mut bounds: Vec<usize> = syntax
+        .iter()
+        .map(|(range, _)| range)
+        .chain(changes)
+        .flat_map(|range| [range.start, range.end])
+        .chain([0, text.len()])
+        .collect();
+    bounds.sort_unstable();
+    bounds.dedup();
+
+    let mut markup = String::new();
+    for bound in bounds.windows(2) {
+        let (start, end) = (bound[0], bound[1]);
+        let segment = markup::escape(&text[start..end]);
+
+        let effect = if changes.iter().any(|range| range.contains(&start)) {
+            Some(changed)
+        } else {
+            syntax
+                .iter()
+                .find(|(range, _)| range.contains(&start))
+                .map(|(_, effect)| *effect)
+        };
+
+        match effect {
+            Some(effect) => markup.push_str(&format!("${} `{}`", effect, segment)),
+            None => markup.push_str(&segment),
+        }
+    }
+
+    markup
+}
+
+/// The markup of the text of every line, by file and hunk.
+/// The old and the new side of a file are highlighted separately, like the files they come from.
+fn style_files(files: &[FileDiff]) -> Vec<Vec<Vec<String>>> {
+    files
+        .iter()
+        .map(|file| {
+            let mut old = Highlighter::for_path(file.old_path.as_deref().unwrap_or_default());
+            let mut new = Highlighter::for_path(file.path());
+
+            file.hunks
+                .iter()
+                .map(|hunk| {
+                    hunk.lines
+                        .iter()
+                        .map(|line| {
+                            let (syntax, changed) = match line.kind {
+                                LineKind::Removed => (old.line(&line.text), "b$cw$br"),
+                                LineKind::Added => (new.line(&line.text), "b$cw$bg"),
+                                LineKind::Context => {
+                                    old.line(&line.text);
+                                    (new.line(&line.text), "")
+                                }
+                            };
+
+                            style_line(&line.text, &syntax, &line.changes, changed)
+                        })
+                        .collect()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Layout {
+    Unified,
+    SideBySide,
+}
+
+/// What a key does in the diff view.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiffAction {
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    First,
+    Last,
+    NextHunk,
+    PreviousHunk,
+    NextFile,
+    PreviousFile,
+    /// Collapses or expands the current file
+    Collapse,
+    CollapseAll,
+    ToggleLayout,
+    Quit,
+}
+
+pub fn bindings() -> Vec<Binding<DiffAction>> {
+    vec![
+        Binding::new(KeyCode::Up, DiffAction::Up).hint("↑↓", "scroll"),
+        Binding::new(KeyCode::Down, DiffAction::Down),
+        Binding::new(KeyCode::Char('k'), DiffAction::Up),
+        Binding::new(KeyCode::Char('j'), DiffAction::Down),
+        Binding::new(KeyCode::PageUp, DiffAction::PageUp),
+        Binding::new(KeyCode::PageDown, DiffAction::PageDown),
+        Binding::new(KeyCode::Char('b'), DiffAction::PageUp),
+        Binding::new(KeyCode::Char(' '), DiffAction::PageDown),
+        Binding::new(KeyCode::Home, DiffAction::First),
+        Binding::new(KeyCode::End, DiffAction::Last),
+        Binding::new(KeyCode::Char('g'), DiffAction::First),
+        Binding::new(KeyCode::Char('G'), DiffAction::Last),
+        Binding::new(KeyCode::Char('n'), DiffAction::NextHunk).hint("n/N", "hunks"),
+        Binding::new(KeyCode::Char('N'), DiffAction::PreviousHunk),
+        Binding::new(KeyCode::Char(']'), DiffAction::NextFile).hint("]/[", "files"),
+        Binding::new(KeyCode::Char('['), DiffAction::PreviousFile),
+        Binding::new(KeyCode::Tab, DiffAction::NextFile),
+        Binding::new(KeyCode::BackTab, DiffAction::PreviousFile).modifiers(KeyModifiers::SHIFT),
+        Binding::new(KeyCode::Char('z'), DiffAction::Collapse).hint("z", "fold"),
+        Binding::new(KeyCode::Char('Z'), DiffAction::CollapseAll),
+        Binding::new(KeyCode::Char('s'), DiffAction::ToggleLayout).hint("s", "layout"),
+        Binding::new(KeyCode::Char('q'), DiffAction::Quit).hint("q", "quit"),
+        Binding::new(KeyCode::Esc, DiffAction::Quit),
+    ]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RowKind {
+    File,
+    Hunk,
+    Line,
+}
+
+/// A row of the diff, side by side rows have a cell for each side.
+#[derive(Debug, Clone, PartialEq)]
+struct Row {
+    kind: RowKind,
+    file: usize,
+    cells: Vec<String>,
+}
+
+/// The markup of a line number, padded to the width.
+fn number(number: Option<usize>, width: usize, kind: LineKind) -> String {
+    let color = match kind {
+        LineKind::Context => "s",
+        LineKind::Added => "cg",
+        LineKind::Removed => "cr",
+    };
+
+    match number {
+        Some(number) => format!("${} `{:>width$}`", color, number, width = width),
+        None => " ".repeat(width),
+    }
+}
+
+fn sign(kind: LineKind) -> &'static str {
+    match kind {
+        LineKind::Context => " ",
+        LineKind::Added => "$cg `+`",
+        LineKind::Removed => "$cr `-`",
+    }
+}
+
+/// A full-screen viewer of a diff, unified or side by side, with syntax highlighting.
+pub struct DiffView {
+    title: String,
+    files: Vec<FileDiff>,
+    /// The markup of the text of the lines, see `style_files`
+    styled: Vec<Vec<Vec<String>>>,
+    /// `None` until the user picks one, the layout then depends on the width
+    layout: Option<Layout>,
+    collapsed: Vec<bool>,
+    /// The rows built for a layout and a width, until the files are folded or unfolded
+    rows: Option<(Layout, u16, Rc<Vec<Row>>)>,
+    /// The index of the first shown row
+    top: usize,
+    /// The size of the body when it was last drawn
+    page: usize,
+    width: u16,
+    /// The terminal size when the view was last drawn
+    size: (u16, u16),
+    bindings: Vec<Binding<DiffAction>>,
+}
+
+impl DiffView {
+    /// Creates a view of the diff (the output of `git diff` without colors).
+    pub fn new(title: impl Into<String>, diff: &str) -> Self {
+        let files = parse(diff);
+
+        Self {
+            title: title.into(),
+            styled: style_files(&files),
+            layout: None,
+            collapsed: vec![false; files.len()],
+            rows: None,
+            files,
+            top: 0,
+            page: 1,
+            width: 80,
+            size: (0, 0),
+            bindings: bindings(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    fn layout(&self, width: u16) -> Layout {
+        match self.layout {
+            Some(layout) => layout,
+            None if width >= SIDE_BY_SIDE_WIDTH => Layout::SideBySide,
+            None => Layout::Unified,
+        }
+    }
+
+    /// The rows of the diff, they're only built again when the layout, the width or the folds change.
+    fn rows(&mut self, width: u16) -> Rc<Vec<Row>> {
+        let layout = self.layout(width);
+
+        match &self.rows {
+            Some((cached, cached_width, rows)) if *cached == layout && *cached_width == width => {
+                Rc::clone(rows)
+            }
+            _ => {
+                let rows = Rc::new(self.build_rows(layout));
+                self.rows = Some((layout, width, Rc::clone(&rows)));
+                rows
+            }
+        }
+    }
+
+    fn build_rows(&self, layout: Layout) -> Vec<Row> {
+        let mut rows = Vec::new();
+        let row = |kind, file, cells: Vec<String>| Row { kind, file, cells };
+
+        for (index, file) in self.files.iter().enumerate() {
+            if index > 0 {
+                rows.push(row(RowKind::Line, index - 1, vec![String::new()]));
+            }
+
+            let (added, removed) = file.stats();
+            let fold = match self.collapsed[index] {
+                true => "▸",
+                false => "▾",
+            };
+            let mut header = format!(
+                "$b$cy `{} {}` $cg `+{}` $cr `-{}`",
+                fold,
+                markup::escape(&file.title()),
+                added,
+                removed
+            );
+            if file.binary {
+                header.push_str(" $s `binary`");
+            }
+            rows.push(row(RowKind::File, index, vec![header]));
+
+            if self.collapsed[index] {
+                continue;
+            }
+
+            let width = file
+                .hunks
+                .iter()
+                .flat_map(|hunk| &hunk.lines)
+                .flat_map(|line| [line.old_number, line.new_number])
+                .flatten()
+                .max()
+                .unwrap_or(0)
+                .to_string()
+                .len();
+
+            for (hunk, styled) in file.hunks.iter().zip(&self.styled[index]) {
+                let (range, context) = match hunk.header[2..].split_once("@@") {
+                    Some((range, context)) => (format!("@@{}@@", range), context),
+                    None => (hunk.header.clone(), ""),
+                };
+                rows.push(row(
+                    RowKind::Hunk,
+                    index,
+                    vec![format!(
+                        "$cc `{}`$s `{}`",
+                        markup::escape(&range),
+                        markup::escape(context)
+                    )],
+                ));
+
+                match layout {
+                    Layout::Unified => {
+                        for (line, text) in hunk.lines.iter().zip(styled) {
+                            let cell = format!(
+                                "{} {} {} {}",
+                                number(line.old_number, width, line.kind),
+                                number(line.new_number, width, line.kind),
+                                sign(line.kind),
+                                text
+                            );
+                            rows.push(row(RowKind::Line, index, vec![cell]));
+                        }
+                    }
+                    Layout::SideBySide => {
+                        for (old, new) in side_by_side(&hunk.lines) {
+                            let cell = |line: Option<usize>, old: bool| match line {
+                                Some(i) => {
+                                    let line = &hunk.lines[i];
+                                    let line_number = match old {
+                                        true => line.old_number,
+                                        false => line.new_number,
+                                    };
+                                    format!(
+                                        "{} {} {}",
+                                        number(line_number, width, line.kind),
+                                        sign(line.kind),
+                                        styled[i]
+                                    )
+                                }
+                                None => String::new(),
+                            };
+                            rows.push(row(
+                                RowKind::Line,
+                                index,
+                                vec![cell(old, true), cell(new, false)],
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        rows
+    }
+
+    /// The index of the next (or previous) row of the kind from the top row.
+    fn find(&self, rows: &[Row], kind: RowKind, forward: bool) -> Option<usize> {
+        let matching = |i: &usize| rows[*i].kind == kind;
+
+        match forward {
+            true => (self.top + 1..rows.len()).find(matching),
+            false => (0..self.top.min(rows.len())).rev().find(matching),
+        }
+    }
+
+    /// The file of the top row.
+    fn current_file(&self, rows: &[Row]) -> usize {
+        rows.get(self.top.min(rows.len().saturating_sub(1)))
+            .map_or(0, |row| row.file)
+    }
+
+    /// Scrolls to the header of the file.
+    fn scroll_to_file(&mut self, file: usize) {
+        let rows = self.rows(self.width);
+        self.top = rows
+            .iter()
+            .position(|row| row.kind == RowKind::File && row.file == file)
+            .unwrap_or(0);
+    }
+
+    fn apply(&mut self, action: DiffAction) {
+        let rows = self.rows(self.width);
+        let page = self.page;
+        let last = rows.len().saturating_sub(page);
+
+        match action {
+            DiffAction::Up => self.top = self.top.saturating_sub(1),
+            DiffAction::Down => self.top += 1,
+            DiffAction::PageUp => self.top = self.top.saturating_sub(page),
+            DiffAction::PageDown => self.top += page,
+            DiffAction::First => self.top = 0,
+            DiffAction::Last => self.top = last,
+            DiffAction::NextHunk => {
+                self.top = self.find(&rows, RowKind::Hunk, true).unwrap_or(self.top)
+            }
+            DiffAction::PreviousHunk => {
+                self.top = self.find(&rows, RowKind::Hunk, false).unwrap_or(self.top)
+            }
+            DiffAction::NextFile => {
+                self.top = self.find(&rows, RowKind::File, true).unwrap_or(self.top)
+            }
+            DiffAction::PreviousFile => {
+                self.top = self.find(&rows, RowKind::File, false).unwrap_or(self.top)
+            }
+            DiffAction::Collapse => {
+                let file = self.current_file(&rows);
+                self.collapsed[file] = !self.collapsed[file];
+                self.rows = None;
+                self.scroll_to_file(file);
+            }
+            DiffAction::CollapseAll => {
+                let file = self.current_file(&rows);
+                let collapse = self.collapsed.contains(&false);
+                self.collapsed
+                    .iter_mut()
+                    .for_each(|collapsed| *collapsed = collapse);
+                self.rows = None;
+                self.scroll_to_file(file);
+            }
+            DiffAction::ToggleLayout => {
+                let file = self.current_file(&rows);
+                self.layout = match self.layout(self.width) {
+                    Layout::Unified => Some(Layout::SideBySide),
+                    Layout::SideBySide => Some(Layout::Unified),
+                };
+                self.scroll_to_file(file);
+            }
+            DiffAction::Quit => {}
+        }
+    }
+
+    fn status_line(&self, rows: &[Row]) -> String {
+        let hints: Vec<String> = self
+            .bindings
+            .iter()
+            .filter_map(Binding::hint_markup)
+            .collect();
+
+        let shown = (self.top + self.page).min(rows.len());
+        let percent = match rows.len() {
+            0 => 100,
+            len => shown * 100 / len,
+        };
+
+        format!(
+            "$s `{}/{} {}%` {}",
+            (self.current_file(rows) + 1).min(self.files.len()),
+            self.files.len(),
+            percent,
+            hints.join(" $s `·` ")
+        )
+    }
+
+    /// Builds every row of the screen: the title, the diff and the status bar.
+    pub fn frame(&mut self, width: u16, height: u16) -> Vec<Vec<Node>> {
+        let rows = self.rows(width);
+        let body = height.saturating_sub(2) as usize;
+        self.width = width;
+        self.page = body.max(1);
+        self.top = self.top.min(rows.len().saturating_sub(body));
+
+        let width = width as usize;
+        let mut frame = vec![fit(&format!("$b `{}`", markup::escape(&self.title)), width)];
+
+        for i in 0..body {
+            let cells = rows
+                .get(self.top + i)
+                .map_or(&[][..], |row| row.cells.as_slice());
+
+            frame.push(match cells {
+                [left, right] => {
+                    let half = width.saturating_sub(1) / 2;
+                    let mut nodes = fit(left, half);
+                    nodes.push(Node::Text("│".into()));
+                    nodes.extend(fit(right, width.saturating_sub(half + 1)));
+                    nodes
+                }
+                [cell, ..] => fit(cell, width),
+                [] if i == 0 && self.is_empty() => fit(&format!("$s `{}`", NO_CHANGES), width),
+                [] => fit("", width),
+            });
+        }
+
+        if height > 1 {
+            frame.push(fit(&self.status_line(&rows), width));
+        }
+
+        frame.truncate(height as usize);
+        frame
+    }
+
+    /// Shows the diff until the user quits, Ctrl+C returns `ReturnType::Exit`.
+    pub fn run(&mut self) -> Result<(), ReturnType> {
+        let _screen = Screen::enter().map_err(|_| ReturnType::Exit)?;
+        self.size = (0, 0);
+
+        loop {
+            let mut size = self.size;
+            tui::draw(&mut size, |width, height| self.frame(width, height))
+                .map_err(|_| ReturnType::Exit)?;
+            self.size = size;
+
+            let key = match tui::read_key()? {
+                Some(key) => key,
+                None => continue,
+            };
+
+            match tui::action(&self.bindings, &key) {
+                Some(DiffAction::Quit) => return Ok(()),
+                Some(action) => self.apply(action),
+                None => {}
+            }
+        }
+    }
+}
+
+/// Pairs the lines of a hunk for the side by side layout, by their index.
+/// The removed lines are next to the added lines following them, the context lines on both sides.
+fn side_by_side(lines: &[DiffLine]) -> Vec<(Option<usize>, Option<usize>)> {
+    let mut pairs = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        if lines[i].kind == LineKind::Context {
+            pairs.push((Some(i), Some(i)));
+            i += 1;
+            continue;
+        }
+
+        let removed = lines[i..]
+            .iter()
+            .take_while(|line| line.kind == LineKind::Removed)
+            .count();
+        let added = lines[i + removed..]
+            .iter()
+            .take_while(|line| line.kind == LineKind::Added)
+            .count();
+
+        for pair in 0..removed.max(added) {
+            pairs.push((
+                (pair < removed).then_some(i + pair),
+                (pair < added).then_some(i + removed + pair),
+            ));
+        }
+
+        i += removed + added;
+    }
+
+    pairs
+}
+
+/// Shows the diff in the viewer, it's printed as it is when the output isn't a terminal.
+pub fn show(title: &str, diff: &str) -> Result<(), ReturnType> {
+    if !std::io::stdout().is_terminal() {
+        match diff.trim().is_empty() {
+            true => println!("{}", NO_CHANGES),
+            false => print!("{}", diff),
+        }
+        return Ok(());
+    }
+
+    DiffView::new(title, diff).run()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,4 +1,3 @@ fn main() {
+ let a = 1;
+-let b = 2;
+--- a comment
++let b = 3;
+ let c = 4;
+\\ No newline at end of file
+diff --git a/notes.txt b/notes.txt
+new file mode 100644
+index 0000000..3333333
+--- /dev/null
++++ b/notes.txt
+@@ -0,0 +1 @@
++hello
+diff --git a/old.md b/new.md
+similarity index 100%
+rename from old.md
+rename to new.md
+diff --git a/logo.png b/logo.png
+deleted file mode 100644
+Binary files a/logo.png and /dev/null differ
+";
+
+    fn plain(nodes: &[Node]) -> String {
+        markup::render_plain(nodes).trim_end().to_string()
+    }
+
+    #[test]
+    fn test_parse() {
+        let files = parse(DIFF);
+        assert_eq!(files.len(), 4);
+
+        let lib = &files[0];
+        assert_eq!(lib.title(), "src/lib.rs");
+        assert_eq!(lib.stats(), (1, 2));
+        assert_eq!(lib.hunks[0].header, "@@ -1,4 +1,3 @@ fn main() {");
+
+        let lines = &lib.hunks[0].lines;
+        assert_eq!(lines.len(), 5);
+        // A removed line starting with `--` isn't a header
+        assert_eq!(lines[2].kind, LineKind::Removed);
+        assert_eq!(lines[2].text, "-- a comment");
+        assert_eq!((lines[3].old_number, lines[3].new_number), (None, Some(2)));
+        assert_eq!(
+            (lines[4].old_number, lines[4].new_number),
+            (Some(4), Some(3))
+        );
+
+        assert_eq!(files[1].title(), "notes.txt (added)");
+        assert_eq!(files[1].hunks[0].lines[0].new_number, Some(1));
+        assert_eq!(files[2].title(), "old.md → new.md");
+        assert!(files[2].hunks.is_empty());
+        assert_eq!(files[3].title(), "logo.png (deleted)");
+        assert!(files[3].binary);
+    }
+
+    /// The changed parts of the text.
+    fn changed<'a>(text: &'a str, changes: &[Range<usize>]) -> Vec<&'a str> {
+        changes.iter().map(|range| &text[range.clone()]).collect()
+    }
+
+    #[test]
+    fn test_word_diff() {
+        let (old, new) = word_diff("let b = 2;", "let b = 3;").unwrap();
+        assert_eq!(changed("let b = 2;", &old), ["2"]);
+        assert_eq!(changed("let b = 3;", &new), ["3"]);
+
+        let new_text = "call(first, third, second)";
+        let (old, new) = word_diff("call(first, second)", new_text).unwrap();
+        assert!(old.is_empty());
+        assert_eq!(changed(new_text, &new), ["third, "]);
+
+        // Nothing in common
+        assert_eq!(word_diff("alpha beta", "gamma delta"), None);
+
+        // The paired lines of the parsed diff
+        let files = parse(DIFF);
+        let lines = &files[0].hunks[0].lines;
+        assert_eq!(changed(&lines[1].text, &lines[1].changes), ["2"]);
+        assert_eq!(changed(&lines[3].text, &lines[3].changes), ["3"]);
+        assert!(lines[2].changes.is_empty());
+    }
+
+    #[test]
+    fn test_side_by_side() {
+        let files = parse(DIFF);
+        assert_eq!(
+            side_by_side(&files[0].hunks[0].lines),
+            vec![
+                (Some(0), Some(0)),
+                (Some(1), Some(3)),
+                (Some(2), None),
+                (Some(4), Some(4))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_frame() {
+        let mut view = DiffView::new("Diff", DIFF);
+        let frame = view.frame(60, 12);
+        let lines: Vec<String> = frame.iter().map(|row| plain(row)).collect();
+
+        assert_eq!(lines[0], "Diff");
+        assert_eq!(lines[1], "▾ src/lib.rs +1 -2");
+        assert_eq!(lines[2], "@@ -1,4 +1,3 @@ fn main() {");
+        assert_eq!(lines[3], "1 1   let a = 1;");
+        assert_eq!(lines[4], "2   - let b = 2;");
+        assert_eq!(lines[6], "  2 + let b = 3;");
+        assert!(lines[11].starts_with("1/4 "));
+
+        view.layout = Some(Layout::SideBySide);
+        let frame = view.frame(61, 12);
+        assert_eq!(
+            plain(&frame[4]),
+            "2 - let b = 2;                │2 + let b = 3;"
+        );
+        assert_eq!(plain(&frame[5]), "3 - -- a comment              │");
+    }
+
+    #[test]
+    fn test_navigation() {
+        let mut view = DiffView::new("Diff", DIFF);
+        view.frame(60, 6);
+
+        view.apply(DiffAction::NextFile);
+        assert_eq!(view.top, 8);
+        view.apply(DiffAction::PreviousHunk);
+        assert_eq!(view.top, 1);
+        view.apply(DiffAction::NextHunk);
+        assert_eq!(view.top, 9);
+
+        // Collapsing a file keeps its header on top, and hides its lines
+        view.apply(DiffAction::Collapse);
+        assert_eq!(view.top, 8);
+        let lines: Vec<String> = view.frame(60, 6).iter().map(|row| plain(row)).collect();
+        assert_eq!(lines[1], "▸ notes.txt (added) +1 -0");
+        assert_eq!(lines[3], "▾ old.md → new.md +0 -0");
+
+        view.apply(DiffAction::CollapseAll);
+        assert!(view.collapsed.iter().all(|collapsed| *collapsed));
+        view.apply(DiffAction::CollapseAll);
+        assert!(view.collapsed.iter().all(|collapsed| !*collapsed));
+    }
+
+    #[test]
+    fn test_rows_cache() {
+        let mut view = DiffView::new("Diff", DIFF);
+        let rows = view.rows(60);
+
+        // The rows are reused until the layout, the width or the folds change
+        assert!(Rc::ptr_eq(&rows, &view.rows(60)));
+        assert!(!Rc::ptr_eq(&rows, &view.rows(140)));
+
+        let rows = view.rows(60);
+        view.apply(DiffAction::Collapse);
+        assert_eq!(
+            view.rows(60)[0].cells[0],
+            rows[0].cells[0].replace('▾', "▸")
+        );
+    }
+
+    #[test]
+    fn test_empty_diff() {
+        let mut view = DiffView::new("Merge", "");
+        let lines: Vec<String> = view.frame(60, 4).iter().map(|row| plain(row)).collect();
+
+        assert_eq!(lines[1], NO_CHANGES);
+        assert!(lines[3].starts_with("0/0 "));
+    }
+}
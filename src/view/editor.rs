@@ -0,0 +1,377 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::markup::{display_width, escape};
+
+/// The recommended width of a commit body.
+pub const BODY_GUIDE: usize = 72;
+
+/// A multi-line text buffer.
+/// The cursor is a line and a grapheme index, so emoji and accented characters move as one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Buffer {
+    lines: Vec<String>,
+    row: usize,
+    col: usize,
+}
+
+fn grapheme_count(line: &str) -> usize {
+    line.graphemes(true).count()
+}
+
+/// Returns the byte offset of the grapheme at the index (or the end of the line).
+fn byte_offset(line: &str, index: usize) -> usize {
+    line.grapheme_indices(true)
+        .nth(index)
+        .map(|(offset, _)| offset)
+        .unwrap_or(line.len())
+}
+
+impl Buffer {
+    /// Creates a buffer with the text, and the cursor at the end.
+    pub fn new(text: &str) -> Self {
+        let mut buffer = Buffer {
+            lines: vec![String::new()],
+            row: 0,
+            col: 0,
+        };
+        buffer.insert(text);
+
+        buffer
+    }
+
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.row, self.col)
+    }
+
+    /// Inserts the text at the cursor, `\r\n` and `\r` are treated as new lines and tabs as 4 spaces.
+    pub fn insert(&mut self, text: &str) {
+        let text = text
+            .replace("\r\n", "\n")
+            .replace('\r', "\n")
+            .replace('\t', "    ");
+
+        for (i, part) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.newline();
+            }
+
+            let line = &mut self.lines[self.row];
+            line.insert_str(byte_offset(line, self.col), part);
+            self.col += grapheme_count(part);
+        }
+    }
+
+    /// Splits the line at the cursor.
+    pub fn newline(&mut self) {
+        let line = &mut self.lines[self.row];
+        let rest = line.split_off(byte_offset(line, self.col));
+
+        self.row += 1;
+        self.col = 0;
+        self.lines.insert(self.row, rest);
+    }
+
+    /// Removes the grapheme before the cursor, or joins the line with the previous one.
+    pub fn backspace(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+            self.delete();
+        } else if self.row > 0 {
+            let line = self.lines.remove(self.row);
+
+            self.row -= 1;
+            self.col = grapheme_count(&self.lines[self.row]);
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    /// Removes the grapheme after the cursor, or joins the next line.
+    pub fn delete(&mut self) {
+        let line = &mut self.lines[self.row];
+
+        if self.col < grapheme_count(line) {
+            let start = byte_offset(line, self.col);
+            let end = byte_offset(line, self.col + 1);
+            line.replace_range(start..end, "");
+        } else if self.row + 1 < self.lines.len() {
+            let next = self.lines.remove(self.row + 1);
+            self.lines[self.row].push_str(&next);
+        }
+    }
+
+    pub fn move_left(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+        } else if self.row > 0 {
+            self.row -= 1;
+            self.col = grapheme_count(&self.lines[self.row]);
+        }
+    }
+
+    pub fn move_right(&mut self) {
+        if self.col < grapheme_count(&self.lines[self.row]) {
+            self.col += 1;
+        } else if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.col = 0;
+        }
+    }
+
+    pub fn move_up(&mut self) {
+        match self.row {
+            0 => self.col = 0,
+            _ => {
+                self.row -= 1;
+                self.col = self.col.min(grapheme_count(&self.lines[self.row]));
+            }
+        }
+    }
+
+    pub fn move_down(&mut self) {
+        match self.row + 1 < self.lines.len() {
+            true => {
+                self.row += 1;
+                self.col = self.col.min(grapheme_count(&self.lines[self.row]));
+            }
+            false => self.col = grapheme_count(&self.lines[self.row]),
+        }
+    }
+
+    pub fn home(&mut self) {
+        self.col = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.col = grapheme_count(&self.lines[self.row]);
+    }
+}
+
+/// Splits a line into rows of at most `width` columns.
+/// Returns the grapheme ranges of the rows, an empty line has a single empty row.
+pub fn wrap(line: &str, width: usize) -> Vec<(usize, usize)> {
+    let width = width.max(2);
+    let mut rows = Vec::new();
+    let (mut start, mut row_width) = (0, 0);
+
+    for (i, grapheme) in line.graphemes(true).enumerate() {
+        let grapheme_width = display_width(grapheme);
+
+        if row_width + grapheme_width > width {
+            rows.push((start, i));
+            start = i;
+            row_width = 0;
+        }
+
+        row_width += grapheme_width;
+    }
+
+    rows.push((start, grapheme_count(line)));
+
+    rows
+}
+
+/// The rows to draw, and the position of the cursor in them.
+pub struct Layout {
+    /// The markup of every row
+    pub rows: Vec<String>,
+    /// The row and the column of the cursor
+    pub cursor: (usize, usize),
+}
+
+/// Wraps the buffer to the width.
+/// With a guide, the text past the guide column is highlighted as a warning.
+pub fn layout(buffer: &Buffer, width: usize, guide: Option<usize>) -> Layout {
+    let mut rows = Vec::new();
+    let mut cursor = (0, 0);
+
+    for (line_index, line) in buffer.lines().iter().enumerate() {
+        let graphemes: Vec<&str> = line.graphemes(true).collect();
+        let wrapped = wrap(line, width);
+
+        for (row_index, (start, end)) in wrapped.iter().enumerate() {
+            let (row, col) = buffer.cursor();
+            let is_last = row_index + 1 == wrapped.len();
+
+            if row == line_index && col >= *start && (col < *end || is_last) {
+                cursor = (rows.len(), display_width(&graphemes[*start..col].concat()));
+            }
+
+            // The column of the row start in the line, for the guide
+            let offset = display_width(&graphemes[..*start].concat());
+            let mut markup = String::new();
+            let mut column = offset;
+
+            for grapheme in &graphemes[*start..*end] {
+                match guide {
+                    Some(guide) if column >= guide => {
+                        markup.push_str(&format!("$@warning `{}`", escape(grapheme)))
+                    }
+                    _ => markup.push_str(&escape(grapheme)),
+                }
+
+                column += display_width(grapheme);
+            }
+
+            rows.push(markup);
+        }
+    }
+
+    Layout { rows, cursor }
+}
+
+/// The line above the template, like the one of `git commit --cleanup=scissors`.
+const SCISSORS: &str = "# ------------------------ >8 ------------------------";
+
+/// Opens the text in the user's editor, with the template as comments below a scissors line.
+/// Everything from the scissors line on is removed from the result, the text is kept as it is
+/// (ex. a `#123` line).
+pub fn open_in_editor(text: &str, template: &str) -> Result<String, String> {
+    let file = tempfile::Builder::new()
+        .prefix("tgh-message-")
+        .suffix(".txt")
+        .tempfile()
+        .map_err(|err| err.to_string())?;
+
+    let comments: Vec<String> = template
+        .lines()
+        .map(|line| format!("# {}", line).trim_end().to_string())
+        .collect();
+    let contents = format!(
+        "{}\n\n{}\n# Don't change or remove the line above, everything below it is ignored.\n{}\n",
+        text,
+        SCISSORS,
+        comments.join("\n")
+    );
+
+    std::fs::write(file.path(), contents).map_err(|err| err.to_string())?;
+
+    crate::functions::open_editor(file.path())?;
+    let result = std::fs::read_to_string(file.path()).map_err(|err| err.to_string())?;
+
+    Ok(strip_template(&result))
+}
+
+/// Removes the template below the scissors line, and the blank lines at the start and the end.
+pub fn strip_template(text: &str) -> String {
+    let lines: Vec<&str> = text
+        .lines()
+        .take_while(|line| line.trim_end() != SCISSORS)
+        .map(str::trim_end)
+        .collect();
+
+    lines.join("\n").trim_matches('\n').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_newline() {
+        let mut buffer = Buffer::new("first\r\nsecond");
+        assert_eq!(buffer.lines(), ["first", "second"]);
+        assert_eq!(buffer.cursor(), (1, 6));
+
+        buffer.move_up();
+        buffer.home();
+        buffer.move_right();
+        buffer.newline();
+        buffer.insert("✨\tx");
+
+        assert_eq!(buffer.text(), "f\n✨    xirst\nsecond");
+        assert_eq!(buffer.cursor(), (1, 6));
+    }
+
+    #[test]
+    fn test_backspace_and_delete() {
+        let mut buffer = Buffer::new("ab\ncd");
+
+        buffer.home();
+        buffer.backspace();
+        assert_eq!(buffer.text(), "abcd");
+        assert_eq!(buffer.cursor(), (0, 2));
+
+        buffer.backspace();
+        assert_eq!(buffer.text(), "acd");
+
+        buffer.end();
+        buffer.insert("\n");
+        buffer.move_up();
+        buffer.end();
+        buffer.delete();
+        assert_eq!(buffer.text(), "acd");
+
+        // Emoji are removed as a single grapheme
+        let mut buffer = Buffer::new("👨‍👩‍👧é");
+        buffer.backspace();
+        buffer.backspace();
+        assert_eq!(buffer.text(), "");
+    }
+
+    #[test]
+    fn test_cursor_movement() {
+        let mut buffer = Buffer::new("long line\nab");
+
+        buffer.move_up();
+        assert_eq!(buffer.cursor(), (0, 2));
+
+        buffer.end();
+        buffer.move_down();
+        assert_eq!(buffer.cursor(), (1, 2));
+
+        buffer.move_right();
+        assert_eq!(buffer.cursor(), (1, 2));
+
+        buffer.home();
+        buffer.move_left();
+        assert_eq!(buffer.cursor(), (0, 9));
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("", 10), vec![(0, 0)]);
+        assert_eq!(wrap("abcdefgh", 3), vec![(0, 3), (3, 6), (6, 8)]);
+        // A wide character isn't split across rows
+        assert_eq!(wrap("ab日本", 3), vec![(0, 2), (2, 3), (3, 4)]);
+    }
+
+    #[test]
+    fn test_layout() {
+        let buffer = Buffer::new("abcdef\ngh");
+        let layout = layout(&buffer, 4, Some(5));
+
+        assert_eq!(layout.rows, vec!["abcd", "e$@warning `f`", "gh"]);
+        assert_eq!(layout.cursor, (2, 2));
+
+        // At the end of a full row, the cursor stays on the next row
+        let mut buffer = Buffer::new("abcdef");
+        buffer.home();
+        for _ in 0..4 {
+            buffer.move_right();
+        }
+        assert_eq!(layout_cursor(&buffer, 4), (1, 0));
+    }
+
+    fn layout_cursor(buffer: &Buffer, width: usize) -> (usize, usize) {
+        layout(buffer, width, None).cursor
+    }
+
+    #[test]
+    fn test_strip_template() {
+        let text = format!(
+            "\nBody line  \n#123 fixes the parser\n\n{}\n# Subject: ✨ Add x\n",
+            SCISSORS
+        );
+
+        assert_eq!(strip_template(&text), "Body line\n#123 fixes the parser");
+        assert_eq!(strip_template("# Not a comment\n"), "# Not a comment");
+    }
+}
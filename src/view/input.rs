@@ -10,13 +10,13 @@ use std::{
     io::{self, Write},
 };
 
-use super::{print, PrintSize};
+use super::{escape, print, PrintSize};
 
 const MAX_ROWS: usize = 12;
 
-const QUESTION_PREFIX: &str = "$b$cg `?`";
-const SUCCESS_PREFIX: &str = "$b$cg `→`";
-const ERROR_PREFIX: &str = "$b$cr `✖`";
+const QUESTION_PREFIX: &str = "$@prompt `?`";
+const SUCCESS_PREFIX: &str = "$@success `→`";
+const ERROR_PREFIX: &str = "$@error `✖`";
 
 pub enum ReturnType {
     Cancel,
@@ -142,7 +142,7 @@ impl TextInput {
                     self.input.drain(..self.cursor_position);
                     self.cursor_position = 0;
 
-                    print(format!("{}", self.input));
+                    print(escape(&self.input));
                     io::stdout().flush().unwrap();
                     execute!(
                         io::stdout(),
@@ -176,7 +176,7 @@ impl TextInput {
                         terminal::Clear(ClearType::UntilNewLine)
                     )
                     .unwrap();
-                    print(format!("{}", self.input));
+                    print(escape(&self.input));
                     io::stdout().flush().unwrap();
                     execute!(
                         io::stdout(),
@@ -201,7 +201,7 @@ impl TextInput {
 
                         match self.input_type {
                             TextInputType::Text => {
-                                print(format!("{}", self.input));
+                                print(escape(&self.input));
                             }
                             TextInputType::Password => {
                                 print(format!("{}", "*".repeat(self.input.len())));
@@ -238,7 +238,7 @@ impl TextInput {
                     .unwrap();
                     match self.input_type {
                         TextInputType::Text => {
-                            print(format!("{}", self.input));
+                            print(escape(&self.input));
                         }
                         TextInputType::Password => {
                             print(format!("{}", "*".repeat(self.input.len())));
@@ -293,6 +293,166 @@ pub fn password(prompt: &str) -> Result<String, ReturnType> {
     get_user_text_input(prompt, prompt_length, TextInputType::Password)
 }
 
+/// Asks for a multi-line text, ex. a commit description.
+/// Enter starts a new line, Ctrl+D finishes, and Ctrl+E opens the text in `$EDITOR`,
+/// with the template (ex. the commit subject) as comments below it.
+pub fn multiline(prompt: &str, template: &str) -> Result<String, ReturnType> {
+    use super::editor::{self, Buffer};
+    use crossterm::event::{DisableBracketedPaste, EnableBracketedPaste, Event};
+
+    super::init();
+    let _ = execute!(io::stdout(), EnableBracketedPaste);
+
+    let mut buffer = Buffer::new("");
+    let mut scroll = 0;
+    let mut cursor_row = 0;
+    let mut notice: Option<String> = None;
+
+    let clear = |cursor_row: usize| {
+        if cursor_row > 0 {
+            execute!(io::stdout(), MoveUp(cursor_row as u16)).unwrap();
+        }
+        execute!(
+            io::stdout(),
+            MoveToColumn(0),
+            terminal::Clear(ClearType::FromCursorDown)
+        )
+        .unwrap();
+    };
+
+    loop {
+        let (width, height) = terminal::size().unwrap_or((80, 24));
+        let layout = editor::layout(
+            &buffer,
+            (width as usize).saturating_sub(3),
+            Some(editor::BODY_GUIDE),
+        );
+
+        // The prompt, the guide and the notice take up 3 rows
+        let visible = (height as usize).saturating_sub(3).max(1);
+        if layout.cursor.0 < scroll {
+            scroll = layout.cursor.0;
+        } else if layout.cursor.0 >= scroll + visible {
+            scroll = layout.cursor.0 + 1 - visible;
+        }
+
+        let rows: Vec<String> = layout
+            .rows
+            .iter()
+            .skip(scroll)
+            .take(visible)
+            .map(|row| format!("  {}", row))
+            .collect();
+        let guide = "─".repeat(editor::BODY_GUIDE.min((width as usize).saturating_sub(6)));
+
+        clear(cursor_row);
+        print(format!(
+            "{} {}$s `(Ctrl+D to finish, Ctrl+E to open \\$EDITOR, Esc to cancel)`\n  $s `{}┤`\n{}",
+            QUESTION_PREFIX,
+            prompt,
+            guide,
+            rows.join("\n")
+        ));
+
+        let mut last_row = 1 + rows.len();
+        if let Some(message) = &notice {
+            print(format!("\n{} {}", ERROR_PREFIX, escape(message)));
+            last_row += 1;
+        }
+
+        cursor_row = 2 + layout.cursor.0 - scroll;
+        if last_row > cursor_row {
+            execute!(io::stdout(), MoveUp((last_row - cursor_row) as u16)).unwrap();
+        }
+        execute!(io::stdout(), MoveToColumn((2 + layout.cursor.1) as u16)).unwrap();
+        io::stdout().flush().unwrap();
+
+        let event = match event::read() {
+            Ok(event) => event,
+            Err(_) => continue,
+        };
+
+        notice = None;
+
+        let key = match event {
+            Event::Paste(text) => {
+                buffer.insert(&text);
+                continue;
+            }
+            Event::Key(key) if key.kind != event::KeyEventKind::Release => key,
+            _ => continue,
+        };
+
+        match (key.modifiers, key.code) {
+            (KeyModifiers::CONTROL, KeyCode::Char('c')) => {
+                clear(cursor_row);
+                let _ = execute!(io::stdout(), DisableBracketedPaste);
+                disable_raw_mode().unwrap();
+                return Err(ReturnType::Exit);
+            }
+            (KeyModifiers::CONTROL, KeyCode::Char('d')) | (_, KeyCode::Esc) => {
+                clear(cursor_row);
+
+                let text = buffer.text().trim_end().to_string();
+
+                if key.code == KeyCode::Esc {
+                    print(format!("{} {}$@error `canceled`\n", ERROR_PREFIX, prompt));
+                } else {
+                    let more = match text.lines().count() {
+                        0 | 1 => String::new(),
+                        count => format!(" $s `(+{} lines)`", count - 1),
+                    };
+
+                    print(format!(
+                        "{} {}$cw$b `{}`{}\n",
+                        SUCCESS_PREFIX,
+                        prompt,
+                        escape(text.lines().next().unwrap_or_default()),
+                        more
+                    ));
+                }
+
+                let _ = execute!(io::stdout(), DisableBracketedPaste);
+                disable_raw_mode().unwrap();
+
+                return match key.code {
+                    KeyCode::Esc => Err(ReturnType::Cancel),
+                    _ => Ok(text),
+                };
+            }
+            (KeyModifiers::CONTROL, KeyCode::Char('e')) => {
+                clear(cursor_row);
+                cursor_row = 0;
+
+                let _ = execute!(io::stdout(), DisableBracketedPaste);
+                disable_raw_mode().unwrap();
+
+                match editor::open_in_editor(&buffer.text(), template) {
+                    Ok(text) => buffer = Buffer::new(&text),
+                    Err(err) => notice = Some(err),
+                }
+
+                super::init();
+                let _ = execute!(io::stdout(), EnableBracketedPaste);
+            }
+            (_, KeyCode::Enter) => buffer.newline(),
+            (_, KeyCode::Backspace) => buffer.backspace(),
+            (_, KeyCode::Delete) => buffer.delete(),
+            (_, KeyCode::Left) => buffer.move_left(),
+            (_, KeyCode::Right) => buffer.move_right(),
+            (_, KeyCode::Up) => buffer.move_up(),
+            (_, KeyCode::Down) => buffer.move_down(),
+            (_, KeyCode::Home) => buffer.home(),
+            (_, KeyCode::End) => buffer.end(),
+            (KeyModifiers::NONE | KeyModifiers::SHIFT, KeyCode::Char(c)) => {
+                buffer.insert(&c.to_string())
+            }
+            (_, KeyCode::Tab) => buffer.insert("\t"),
+            _ => {}
+        }
+    }
+}
+
 pub fn list<T>(prompt: &str, items: Vec<T>) -> Result<T, ReturnType>
 where
     T: Display + Clone,
@@ -373,10 +533,12 @@ where
                         if event.code == KeyCode::Enter {
                             print(format!(
                                 "{} {}$cw$b `{}`\n",
-                                SUCCESS_PREFIX, prompt, items[selected]
+                                SUCCESS_PREFIX,
+                                prompt,
+                                escape(&items[selected].to_string())
                             ));
                         } else {
-                            print(format!("{} {}$cr$b `canceled`\n", ERROR_PREFIX, prompt));
+                            print(format!("{} {}$@error `canceled`\n", ERROR_PREFIX, prompt));
                         }
 
                         disable_raw_mode().unwrap();
@@ -387,9 +549,7 @@ where
                         };
                     }
                     KeyCode::Up => {
-                        if selected > 0 {
-                            selected -= 1;
-                        }
+                        selected = selected.saturating_sub(1);
 
                         while selected > 0 && !kv_items[selected].matched {
                             selected -= 1;
@@ -427,7 +587,9 @@ where
             .unwrap();
             print(format!(
                 "{} {}{}",
-                QUESTION_PREFIX, prompt, text_input.input
+                QUESTION_PREFIX,
+                prompt,
+                escape(&text_input.input)
             ));
             execute!(io::stdout(), MoveDown(1), MoveToColumn(0)).unwrap();
 
@@ -482,6 +644,174 @@ where
     }
 }
 
+/// Asks for any number of the items, at least one has to be picked.
+/// Typing filters the items, Space toggles the current one, Ctrl+A toggles all the shown items,
+/// and Ctrl+R inverts the selection of the shown items.
+pub fn multi_select<T>(prompt: &str, items: Vec<T>) -> Result<Vec<T>, ReturnType>
+where
+    T: Display + Clone,
+{
+    use crossterm::event::Event;
+
+    if items.is_empty() {
+        return Err(ReturnType::Cancel);
+    }
+
+    super::init();
+
+    let names: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+    let matcher = SkimMatcherV2::default();
+
+    let mut checked = vec![false; items.len()];
+    let mut filter = String::new();
+    let mut selected = 0;
+    let mut offset = 0;
+    let mut notice: Option<&str> = None;
+
+    let clear = || {
+        execute!(
+            io::stdout(),
+            MoveToColumn(0),
+            terminal::Clear(ClearType::FromCursorDown)
+        )
+        .unwrap();
+    };
+
+    loop {
+        let shown: Vec<usize> = (0..items.len())
+            .filter(|i| matcher.fuzzy_match(&names[*i], &filter).is_some())
+            .collect();
+
+        // Keep the cursor on a shown item, and in the window
+        let position = shown.iter().position(|i| *i == selected).unwrap_or(0);
+        selected = shown.get(position).copied().unwrap_or(0);
+
+        let (_, height) = terminal::size().unwrap_or((80, 24));
+        let rows = MAX_ROWS.min((height as usize).saturating_sub(3)).max(1);
+
+        if position < offset {
+            offset = position;
+        } else if position >= offset + rows {
+            offset = position + 1 - rows;
+        }
+        offset = offset.min(shown.len().saturating_sub(rows));
+
+        clear();
+
+        let PrintSize {
+            cols: prompt_length,
+            rows: _,
+        } = print(format!("{} {}", QUESTION_PREFIX, prompt));
+        print(escape(&filter));
+
+        for (index, item) in shown.iter().enumerate().skip(offset).take(rows) {
+            let pointer = match *item == selected {
+                true => "$cc `>`",
+                false if index == offset && offset > 0 => "⌃",
+                false if index + 1 == offset + rows && index + 1 < shown.len() => "⌄",
+                false => " ",
+            };
+            let checkbox = match checked[*item] {
+                true => "$@success `[x]`",
+                false => "$s `[ ]`",
+            };
+
+            print(format!(
+                "\n{} {} {}",
+                pointer,
+                checkbox,
+                highlight_matches(&fit_to_terminal(&names[*item], 6), &matcher, &filter)
+            ));
+        }
+
+        let count = checked.iter().filter(|checked| **checked).count();
+        let footer = match notice {
+            Some(notice) => format!("$@error `{}`", notice),
+            None => format!(
+                "$s `{} selected · Space to select, Ctrl+A to toggle all, Ctrl+R to invert`",
+                count
+            ),
+        };
+        print(format!("\n{}", footer));
+
+        // Go back to the filter
+        let printed = shown.len().saturating_sub(offset).min(rows) + 1;
+        execute!(
+            io::stdout(),
+            MoveUp(printed as u16),
+            MoveToColumn((prompt_length + super::markup::display_width(&filter)) as u16)
+        )
+        .unwrap();
+        io::stdout().flush().unwrap();
+
+        let key = match event::read() {
+            Ok(Event::Key(key)) if key.kind != event::KeyEventKind::Release => key,
+            Ok(_) => continue,
+            Err(_) => continue,
+        };
+
+        notice = None;
+
+        match (key.modifiers, key.code) {
+            (KeyModifiers::CONTROL, KeyCode::Char('c')) => {
+                clear();
+                disable_raw_mode().unwrap();
+                return Err(ReturnType::Exit);
+            }
+            (KeyModifiers::CONTROL, KeyCode::Char('a')) => {
+                let all = shown.iter().all(|i| checked[*i]);
+                shown.iter().for_each(|i| checked[*i] = !all);
+            }
+            (KeyModifiers::CONTROL, KeyCode::Char('r')) => {
+                shown.iter().for_each(|i| checked[*i] = !checked[*i]);
+            }
+            (_, KeyCode::Char(' ')) if !shown.is_empty() => {
+                checked[selected] = !checked[selected];
+            }
+            // Nothing is shown to check, and a space isn't a part of the filter
+            (_, KeyCode::Char(' ')) => {}
+            (_, KeyCode::Up) if !shown.is_empty() => selected = shown[position.saturating_sub(1)],
+            (_, KeyCode::Down) if !shown.is_empty() => {
+                selected = shown[(position + 1).min(shown.len() - 1)]
+            }
+            (_, KeyCode::Backspace) => {
+                filter.pop();
+            }
+            (KeyModifiers::NONE | KeyModifiers::SHIFT, KeyCode::Char(c)) => filter.push(c),
+            (_, KeyCode::Esc) => {
+                clear();
+                print(format!("{} {}$@error `canceled`\n", ERROR_PREFIX, prompt));
+                disable_raw_mode().unwrap();
+                return Err(ReturnType::Cancel);
+            }
+            (_, KeyCode::Enter) => {
+                if count == 0 {
+                    notice = Some("Select at least one item (Space)");
+                    continue;
+                }
+
+                let picked: Vec<T> = (0..items.len())
+                    .filter(|i| checked[*i])
+                    .map(|i| items[i].clone())
+                    .collect();
+                let summary: Vec<String> = picked.iter().map(|item| item.to_string()).collect();
+
+                clear();
+                print(format!(
+                    "{} {}$cw$b `{}`\n",
+                    SUCCESS_PREFIX,
+                    prompt,
+                    escape(&summary.join(", "))
+                ));
+                disable_raw_mode().unwrap();
+
+                return Ok(picked);
+            }
+            _ => {}
+        }
+    }
+}
+
 /// Render the list of items
 /// This function assumes that the items are already filtered, and correctly offset, and uses the matcher to color the items
 fn render_list<T>(
@@ -495,8 +825,6 @@ fn render_list<T>(
 where
     T: Display + Clone,
 {
-    use unicode_segmentation::UnicodeSegmentation;
-
     let mut rendered = 0;
     let mut i = offset;
     while rendered < usable_rows {
@@ -519,38 +847,56 @@ where
             print("  ");
         }
 
-        let word = items[i].value.to_string();
+        print(highlight_matches(
+            &fit_to_terminal(&items[i].value.to_string(), 2),
+            matcher,
+            &input,
+        ));
 
-        let mut matched_bytes = matcher
-            .fuzzy_indices(&word, &input)
-            .map(|(_, v)| v)
-            .unwrap_or_default();
+        rendered += 1;
+        i += 1;
 
-        matched_bytes.sort_unstable();
+        execute!(io::stdout(), MoveDown(1), MoveToColumn(0)).unwrap();
+    }
 
-        let mut match_idx = 0;
-        let mut word_idx = 0;
-        for (_byte_start, grapheme) in word.grapheme_indices(true) {
-            let is_matched =
-                match_idx < matched_bytes.len() && word_idx == matched_bytes[match_idx];
+    rendered
+}
 
-            if is_matched {
-                match_idx += 1;
-                print(format!("$cc `{}`", grapheme));
-            } else {
-                print(format!("{}", grapheme));
-            }
+/// Cuts the text to the terminal width, minus the columns taken up before it.
+/// A wrapped line would break the cursor movement of the lists.
+fn fit_to_terminal(text: &str, taken: usize) -> String {
+    use super::markup::{render_plain, truncate, Node};
 
-            word_idx += 1;
-        }
+    let (width, _) = terminal::size().unwrap_or((80, 24));
+    let width = (width as usize).saturating_sub(taken);
 
-        rendered += 1;
-        i += 1;
+    render_plain(&truncate(&[Node::Text(text.to_string())], width))
+}
 
-        execute!(io::stdout(), MoveDown(1), MoveToColumn(0)).unwrap();
+/// Returns the markup of the word, with the characters matching the filter highlighted.
+pub fn highlight_matches(word: &str, matcher: &SkimMatcherV2, input: &str) -> String {
+    use unicode_segmentation::UnicodeSegmentation;
+
+    let mut matched = matcher
+        .fuzzy_indices(word, input)
+        .map(|(_, v)| v)
+        .unwrap_or_default();
+
+    matched.sort_unstable();
+
+    let mut markup = String::new();
+    let mut match_idx = 0;
+
+    for (word_idx, grapheme) in word.graphemes(true).enumerate() {
+        if match_idx < matched.len() && word_idx == matched[match_idx] {
+            match_idx += 1;
+            markup.push_str(&format!("$cc `{}`", escape(grapheme)));
+        } else {
+            markup.push_str(&escape(grapheme));
+        }
     }
 
-    rendered
+    markup
 }
 
 fn get_user_text_input(
@@ -589,11 +935,11 @@ fn get_user_text_input(
                                     if input_type == TextInputType::Password {
                                         "*".repeat(text_input.input.len())
                                     } else {
-                                        text_input.input.clone()
+                                        escape(&text_input.input)
                                     }
                                 ));
                             } else {
-                                print(format!("{} {}$cr$b `canceled`\n", ERROR_PREFIX, prompt));
+                                print(format!("{} {}$@error `canceled`\n", ERROR_PREFIX, prompt));
                             }
 
                             disable_raw_mode().unwrap();
@@ -616,7 +962,12 @@ fn get_user_text_input(
                 terminal::Clear(ClearType::FromCursorDown)
             )
             .unwrap();
-            print(format!("$b$cg `?` {}{}", prompt, text_input.input));
+            print(format!(
+                "{} {}{}",
+                QUESTION_PREFIX,
+                prompt,
+                escape(&text_input.input)
+            ));
             io::stdout().flush().unwrap();
         }
     }
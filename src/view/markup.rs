@@ -0,0 +1,493 @@
+use crossterm::style::{Attribute, Color, SetAttribute, SetBackgroundColor, SetForegroundColor};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use super::theme::{self, Role};
+
+/// The characters which have to be escaped with `\` to be printed as they are.
+const SPECIAL: [char; 4] = ['$', '`', '&', '\\'];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    /// `$b$cr ` followed by a backtick, opens a styled span
+    Open(Vec<String>),
+    /// A backtick, closes the innermost span
+    Close,
+    Text(String),
+    Newline,
+    /// `&>`, 4 spaces
+    Tab,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Effect {
+    Attribute(Attribute),
+    Foreground(Color),
+    Background(Color),
+    Role(Role),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Text(String),
+    Newline,
+    Tab,
+    Span(Vec<Effect>, Vec<Node>),
+}
+
+/// The size of the printed content, in terminal columns and lines.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PrintSize {
+    /// The width of the widest line
+    pub cols: usize,
+    pub rows: usize,
+}
+
+/// Parses the effects of a span opening (ex. `$b$cr ` followed by a backtick).
+/// Returns the effect names and the length of the opening in bytes.
+fn parse_opening(input: &str) -> Option<(Vec<String>, usize)> {
+    let end = input
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '$' || c == '@' || c == '_'))
+        .unwrap_or(input.len());
+
+    if !input[end..].starts_with(" `") {
+        return None;
+    }
+
+    let names: Vec<String> = input[1..end].split('$').map(str::to_string).collect();
+
+    match names.iter().all(|name| !name.is_empty()) {
+        true => Some((names, end + 2)),
+        false => None,
+    }
+}
+
+pub fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut text = String::new();
+
+    let flush = |text: &mut String, tokens: &mut Vec<Token>| {
+        if !text.is_empty() {
+            tokens.push(Token::Text(std::mem::take(text)));
+        }
+    };
+
+    let mut i = 0;
+    while i < input.len() {
+        let rest = &input[i..];
+        let c = rest.chars().next().unwrap();
+
+        match c {
+            '\\' => match rest[1..].chars().next() {
+                Some(next) if SPECIAL.contains(&next) => {
+                    text.push(next);
+                    i += 2;
+                    continue;
+                }
+                _ => text.push(c),
+            },
+            '$' => match parse_opening(rest) {
+                Some((names, length)) => {
+                    flush(&mut text, &mut tokens);
+                    tokens.push(Token::Open(names));
+                    i += length;
+                    continue;
+                }
+                None => text.push(c),
+            },
+            '`' => {
+                flush(&mut text, &mut tokens);
+                tokens.push(Token::Close);
+            }
+            '\n' => {
+                flush(&mut text, &mut tokens);
+                tokens.push(Token::Newline);
+            }
+            '&' if rest[1..].starts_with('>') => {
+                flush(&mut text, &mut tokens);
+                tokens.push(Token::Tab);
+                i += 2;
+                continue;
+            }
+            _ => text.push(c),
+        }
+
+        i += c.len_utf8();
+    }
+
+    flush(&mut text, &mut tokens);
+
+    tokens
+}
+
+/// Converts an effect name to the effect, unknown names are ignored.
+fn match_effect(name: &str) -> Option<Effect> {
+    let effect = match name {
+        "b" => Effect::Attribute(Attribute::Bold),
+        "i" => Effect::Attribute(Attribute::Italic),
+        "u" => Effect::Attribute(Attribute::Underlined),
+        "s" => Effect::Attribute(Attribute::Dim),
+
+        "cr" => Effect::Foreground(Color::Red),
+        "cg" => Effect::Foreground(Color::Green),
+        "cb" => Effect::Foreground(Color::Blue),
+        "cy" => Effect::Foreground(Color::Yellow),
+        "cm" => Effect::Foreground(Color::Magenta),
+        "cc" => Effect::Foreground(Color::Cyan),
+        "cw" => Effect::Foreground(Color::White),
+
+        "br" => Effect::Background(Color::Red),
+        "bg" => Effect::Background(Color::Green),
+        "bb" => Effect::Background(Color::Blue),
+        "by" => Effect::Background(Color::Yellow),
+        "bm" => Effect::Background(Color::Magenta),
+        "bc" => Effect::Background(Color::Cyan),
+        "bw" => Effect::Background(Color::White),
+
+        _ => Effect::Role(Role::from_name(name.strip_prefix('@')?)?),
+    };
+
+    Some(effect)
+}
+
+/// Parses the markup into styled spans.
+/// A backtick without an open span is printed as it is, and unclosed spans end with the content.
+pub fn parse(input: &str) -> Vec<Node> {
+    let mut stack: Vec<(Vec<Effect>, Vec<Node>)> = vec![(Vec::new(), Vec::new())];
+
+    for token in tokenize(input) {
+        let node = match token {
+            Token::Open(names) => {
+                let effects = names.iter().filter_map(|name| match_effect(name)).collect();
+                stack.push((effects, Vec::new()));
+                continue;
+            }
+            Token::Close if stack.len() > 1 => {
+                let (effects, children) = stack.pop().unwrap();
+                Node::Span(effects, children)
+            }
+            Token::Close => Node::Text("`".into()),
+            Token::Text(text) => Node::Text(text),
+            Token::Newline => Node::Newline,
+            Token::Tab => Node::Tab,
+        };
+
+        stack.last_mut().unwrap().1.push(node);
+    }
+
+    while stack.len() > 1 {
+        let (effects, children) = stack.pop().unwrap();
+        stack
+            .last_mut()
+            .unwrap()
+            .1
+            .push(Node::Span(effects, children));
+    }
+
+    stack.pop().unwrap().1
+}
+
+fn write_effects(stack: &[Effect], output: &mut String) {
+    output.push_str(&SetAttribute(Attribute::Reset).to_string());
+
+    for effect in stack {
+        match effect {
+            Effect::Attribute(attribute) => output.push_str(&SetAttribute(*attribute).to_string()),
+            Effect::Foreground(color) => output.push_str(&SetForegroundColor(*color).to_string()),
+            Effect::Background(color) => output.push_str(&SetBackgroundColor(*color).to_string()),
+            Effect::Role(role) => {
+                let style = theme::get().style(*role);
+
+                if let Some(color) = style.foreground_color {
+                    output.push_str(&SetForegroundColor(color).to_string());
+                }
+
+                for attribute in Attribute::iterator().filter(|a| style.attributes.has(*a)) {
+                    output.push_str(&SetAttribute(attribute).to_string());
+                }
+            }
+        }
+    }
+}
+
+fn render_nodes(nodes: &[Node], stack: &mut Vec<Effect>, ansi: bool, output: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Newline => output.push('\n'),
+            Node::Tab => output.push_str("    "),
+            Node::Span(effects, children) => {
+                if !ansi {
+                    render_nodes(children, stack, ansi, output);
+                    continue;
+                }
+
+                let depth = stack.len();
+                stack.extend(effects);
+                write_effects(stack, output);
+
+                render_nodes(children, stack, ansi, output);
+
+                // Restore the effects of the enclosing spans
+                stack.truncate(depth);
+                write_effects(stack, output);
+            }
+        }
+    }
+}
+
+/// Renders the spans with ANSI escape codes.
+pub fn render_ansi(nodes: &[Node]) -> String {
+    let mut output = String::new();
+    render_nodes(nodes, &mut Vec::new(), true, &mut output);
+
+    output
+}
+
+/// Renders the spans without any styling.
+pub fn render_plain(nodes: &[Node]) -> String {
+    let mut output = String::new();
+    render_nodes(nodes, &mut Vec::new(), false, &mut output);
+
+    output
+}
+
+/// Returns the number of terminal columns the text takes up.
+/// Wide characters (CJK, most emoji) take up 2 columns, and a grapheme never takes up more than 2.
+pub fn display_width(text: &str) -> usize {
+    text.graphemes(true)
+        .map(|grapheme| match grapheme.chars().all(char::is_control) {
+            true => 0,
+            false => grapheme.width().min(2),
+        })
+        .sum()
+}
+
+/// Measures the rendered spans.
+pub fn measure(nodes: &[Node]) -> PrintSize {
+    let plain = render_plain(nodes);
+    let lines: Vec<&str> = plain.split('\n').collect();
+
+    PrintSize {
+        cols: lines
+            .iter()
+            .map(|line| display_width(line))
+            .max()
+            .unwrap_or(0),
+        rows: lines.len(),
+    }
+}
+
+fn take_width(nodes: &[Node], remaining: &mut usize) -> Vec<Node> {
+    let mut taken = Vec::new();
+
+    for node in nodes {
+        match node {
+            Node::Text(text) => {
+                let mut fitted = String::new();
+
+                for grapheme in text.graphemes(true) {
+                    let width = display_width(grapheme);
+                    if width > *remaining {
+                        *remaining = 0;
+                        break;
+                    }
+
+                    *remaining -= width;
+                    fitted.push_str(grapheme);
+                }
+
+                taken.push(Node::Text(fitted));
+            }
+            Node::Tab => {
+                let width = (*remaining).min(4);
+                *remaining -= width;
+                taken.push(Node::Text(" ".repeat(width)));
+            }
+            Node::Newline => taken.push(Node::Newline),
+            Node::Span(effects, children) => {
+                taken.push(Node::Span(effects.clone(), take_width(children, remaining)))
+            }
+        }
+
+        if *remaining == 0 {
+            break;
+        }
+    }
+
+    taken
+}
+
+/// Cuts a line to the width, keeping the styling. The cut lines end with `…`.
+pub fn truncate(nodes: &[Node], width: usize) -> Vec<Node> {
+    if measure(nodes).cols <= width {
+        return nodes.to_vec();
+    }
+
+    let mut remaining = width.saturating_sub(1);
+    let mut truncated = take_width(nodes, &mut remaining);
+
+    if width > 0 {
+        truncated.push(Node::Text("…".into()));
+    }
+
+    truncated
+}
+
+/// Escapes the special characters, so the text is printed as it is (ex. a commit message or user input).
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        if SPECIAL.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+/// Removes the indentation of every line, for markup written as an indented string literal.
+pub fn unindent(text: &str) -> String {
+    text.lines()
+        .map(str::trim_start)
+        .collect::<Vec<_>>()
+        .join("\n")
+        + if text.ends_with('\n') { "\n" } else { "" }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("$b$cr `error`: &>x\n"),
+            vec![
+                Token::Open(vec!["b".into(), "cr".into()]),
+                Token::Text("error".into()),
+                Token::Close,
+                Token::Text(": ".into()),
+                Token::Tab,
+                Token::Text("x".into()),
+                Token::Newline,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_literal_characters() {
+        // Not a span opening, or not a tab
+        assert_eq!(
+            tokenize("costs $5 & more"),
+            vec![Token::Text("costs $5 & more".into())]
+        );
+        assert_eq!(
+            tokenize(r"\$b \`x\` \&> C:\Users \\"),
+            vec![Token::Text(r"$b `x` &> C:\Users \".into())]
+        );
+    }
+
+    #[test]
+    fn test_escape() {
+        let text = "fix `$HOME` & C:\\path";
+
+        assert_eq!(render_plain(&parse(&escape(text))), text);
+        assert_eq!(
+            render_plain(&parse(&format!("$b `{}`", escape(text)))),
+            text
+        );
+    }
+
+    #[test]
+    fn test_parse_nested_spans() {
+        assert_eq!(
+            parse("$b `Bold $u `both`` plain"),
+            vec![
+                Node::Span(
+                    vec![Effect::Attribute(Attribute::Bold)],
+                    vec![
+                        Node::Text("Bold ".into()),
+                        Node::Span(
+                            vec![Effect::Attribute(Attribute::Underlined)],
+                            vec![Node::Text("both".into())]
+                        ),
+                    ]
+                ),
+                Node::Text(" plain".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_unbalanced() {
+        assert_eq!(
+            parse("a`b"),
+            vec![
+                Node::Text("a".into()),
+                Node::Text("`".into()),
+                Node::Text("b".into())
+            ]
+        );
+        assert_eq!(
+            parse("$x$@error `open"),
+            vec![Node::Span(
+                vec![Effect::Role(Role::Error)],
+                vec![Node::Text("open".into())]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_render_plain() {
+        let nodes = parse("$@error `error`: bad\n  &> $cc$i `tgh config`");
+
+        // Leading spaces are kept
+        assert_eq!(render_plain(&nodes), "error: bad\n       tgh config");
+    }
+
+    #[test]
+    fn test_render_ansi() {
+        assert_eq!(
+            render_ansi(&parse("$b `a $cr `b` c`")),
+            "\x1b[0m\x1b[1ma \x1b[0m\x1b[1m\x1b[38;5;9mb\x1b[0m\x1b[1m c\x1b[0m"
+        );
+        assert_eq!(render_ansi(&parse("no effects")), "no effects");
+    }
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width("tgh"), 3);
+        assert_eq!(display_width("日本語"), 6);
+        assert_eq!(display_width("✨ done"), 7);
+        assert_eq!(display_width("👨‍👩‍👧"), 2);
+        assert_eq!(display_width("e\u{301}"), 1);
+        assert_eq!(display_width("\r"), 0);
+    }
+
+    #[test]
+    fn test_measure() {
+        let size = measure(&parse("$b `?` 名前\n&> x"));
+
+        assert_eq!(size, PrintSize { cols: 6, rows: 2 });
+    }
+
+    #[test]
+    fn test_truncate() {
+        let nodes = parse("$b `日本`語 text");
+
+        assert_eq!(truncate(&nodes, 20), nodes);
+        assert_eq!(render_plain(&truncate(&nodes, 7)), "日本語…");
+        // A wide character which doesn't fit isn't split
+        assert_eq!(render_plain(&truncate(&nodes, 6)), "日本…");
+        assert_eq!(render_plain(&truncate(&nodes, 0)), "");
+        assert!(matches!(&truncate(&nodes, 3)[0], Node::Span(_, _)));
+    }
+
+    #[test]
+    fn test_unindent() {
+        assert_eq!(unindent("\n    a\n      b\n"), "\na\nb\n");
+    }
+}
@@ -1,13 +1,21 @@
 use crossterm::{
-    cursor::{MoveToColumn, MoveToNextLine},
+    cursor::MoveToColumn,
     execute,
-    style::{Attribute, Color, Print, SetAttribute, SetBackgroundColor, SetForegroundColor},
-    terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType},
+    terminal::{disable_raw_mode, enable_raw_mode, is_raw_mode_enabled, Clear, ClearType},
 };
 use std::io::stdout;
 
+pub mod chart;
+pub mod diff;
+pub mod editor;
 pub mod input;
+pub mod markup;
 pub mod spinner;
+pub mod syntax;
+pub mod theme;
+pub mod tui;
+
+pub use markup::{escape, unindent, PrintSize};
 
 pub fn init() {
     enable_raw_mode().unwrap();
@@ -17,83 +25,6 @@ pub fn clean_up() {
     disable_raw_mode().unwrap();
 }
 
-enum VisualEffect {
-    SetAttribute(Attribute),
-    SetForegroundColor(Color),
-    SetBackgroundColor(Color),
-}
-
-fn match_effect(effect: &str) -> Vec<VisualEffect> {
-    let mut effects: Vec<VisualEffect> = Vec::new();
-
-    let mut i = 1;
-    while i < effect.len() {
-        let c = effect.chars().nth(i).unwrap();
-
-        let mut special = String::new();
-        special.push(c);
-
-        i += 1;
-
-        while i < effect.len() && effect.chars().nth(i).unwrap() != '$' {
-            special.push(effect.chars().nth(i).unwrap());
-            i += 1;
-        }
-
-        match special.as_str() {
-            "b" => effects.push(VisualEffect::SetAttribute(Attribute::Bold)),
-            "i" => effects.push(VisualEffect::SetAttribute(Attribute::Italic)),
-            "u" => effects.push(VisualEffect::SetAttribute(Attribute::Underlined)),
-            "s" => effects.push(VisualEffect::SetAttribute(Attribute::Dim)),
-
-            "cr" => effects.push(VisualEffect::SetForegroundColor(Color::Red)),
-            "cg" => effects.push(VisualEffect::SetForegroundColor(Color::Green)),
-            "cb" => effects.push(VisualEffect::SetForegroundColor(Color::Blue)),
-            "cy" => effects.push(VisualEffect::SetForegroundColor(Color::Yellow)),
-            "cm" => effects.push(VisualEffect::SetForegroundColor(Color::Magenta)),
-            "cc" => effects.push(VisualEffect::SetForegroundColor(Color::Cyan)),
-            "cw" => effects.push(VisualEffect::SetForegroundColor(Color::White)),
-
-            "br" => effects.push(VisualEffect::SetBackgroundColor(Color::Red)),
-            "bg" => effects.push(VisualEffect::SetBackgroundColor(Color::Green)),
-            "bb" => effects.push(VisualEffect::SetBackgroundColor(Color::Blue)),
-            "by" => effects.push(VisualEffect::SetBackgroundColor(Color::Yellow)),
-            "bm" => effects.push(VisualEffect::SetBackgroundColor(Color::Magenta)),
-            "bc" => effects.push(VisualEffect::SetBackgroundColor(Color::Cyan)),
-            "bw" => effects.push(VisualEffect::SetBackgroundColor(Color::White)),
-            _ => {}
-        }
-
-        i += 1;
-    }
-
-    effects
-}
-
-fn set_new_effects(stdout: &mut std::io::Stdout, effects: &Vec<Vec<VisualEffect>>) {
-    execute!(stdout, SetAttribute(Attribute::Reset)).unwrap();
-    for effect in effects {
-        for e in effect {
-            match e {
-                VisualEffect::SetAttribute(a) => {
-                    execute!(stdout, SetAttribute(*a)).unwrap();
-                }
-                VisualEffect::SetForegroundColor(c) => {
-                    execute!(stdout, SetForegroundColor(*c)).unwrap();
-                }
-                VisualEffect::SetBackgroundColor(c) => {
-                    execute!(stdout, SetBackgroundColor(*c)).unwrap();
-                }
-            }
-        }
-    }
-}
-
-pub struct PrintSize {
-    pub cols: usize,
-    pub rows: usize,
-}
-
 /**
 This function is used to print a string with special effects.
 
@@ -118,138 +49,49 @@ The special effects are defined by the following syntax: $effect `content`
 - $bc: background cyan color
 - $bw: background white color
 
+- $@role: the style of a theme role (error, success, warning, hash, author, date, prompt, highlight)
+
 - &>: tab (4 spaces)
 
+- \$, \`, \& and \\: the character itself (see `markup::escape`)
+
 Multiple effects can be combined, ex. $b$u - bold underline, or $b `Bold and $u `underline``
+
+The effects are skipped when the output isn't styled (see `theme::colors_enabled`).
+Leading spaces are printed as they are, use `unindent` for indented string literals.
  */
 pub fn printer(content: impl AsRef<str>) -> PrintSize {
-    enable_raw_mode().unwrap();
-    let size = print(content);
-    disable_raw_mode().unwrap();
-
-    size
+    print(content)
 }
 
-/** Print the content with correct formatting, but without going into raw mode
-This function is used to print a string with special effects.
-
-The special effects are defined by the following syntax: $effect `content`
-- $b: bold
-- $i: italic
-- $u: underline
-
-- $cr: red color
-- $cg: green color
-- $cb: blue color
-- $cy: yellow color
-- $cm: magenta color
-- $cc: cyan color
-- $cw: white color
-
-- $br: background red color
-- $bg: background green color
-- $bb: background blue color
-- $by: background yellow color
-- $bm: background magenta color
-- $bc: background cyan color
-- $bw: background white color
-
-- &>: tab (4 spaces)
-
-Multiple effects can be combined, ex. $b$u - bold underline, or $b `Bold and $u `underline``
-*/
+/// Prints the markup (see `printer`), in raw mode the new lines also return the cursor to the first column.
 pub fn print(content: impl AsRef<str>) -> PrintSize {
     use std::io::Write;
 
-    let mut size = PrintSize { cols: 0, rows: 0 };
-    let mut current_width: usize = 0;
-
-    let content = content.as_ref();
-    let chars = content.chars();
-    let n = chars.count();
-    let mut stdout = stdout();
-
-    let mut effects: Vec<Vec<VisualEffect>> = Vec::new();
-
-    let mut i = 0;
-    while i < n {
-        let c = content.chars().nth(i).unwrap();
-
-        // Add a special effect
-        if c == '$' {
-            let mut special = String::new();
-
-            while i < n && content.chars().nth(i).unwrap() != ' ' {
-                special.push(content.chars().nth(i).unwrap());
-                i += 1;
-            }
+    let nodes = markup::parse(content.as_ref());
+    let mut output = render_nodes(&nodes);
 
-            let effect = match_effect(&special);
-            effects.push(effect);
-            set_new_effects(&mut stdout, &effects);
-
-            i += 2;
-
-            continue;
-        }
-
-        // Clear the last effect
-        if c == '`' {
-            effects.pop();
-
-            set_new_effects(&mut stdout, &effects);
-
-            i += 1;
-            continue;
-        }
-
-        // Print a new line and clear the spaces
-        if c == '\n' {
-            if current_width > size.cols {
-                size.cols = current_width;
-                size.rows += 1;
-            }
-
-            write!(std::io::stdout(), "\n\r");
-            i += 1;
-
-            while i < n && content.chars().nth(i).unwrap() == ' ' {
-                i += 1;
-            }
-
-            continue;
-        }
-
-        if c == '&' {
-            i += 1;
-
-            if i >= n {
-                break;
-            }
-
-            let c = content.chars().nth(i).unwrap();
-
-            // Print a 4 wide space (tab)
-            if c == '>' {
-                execute!(stdout, Print("    ")).unwrap();
-            }
+    if is_raw_mode_enabled().unwrap_or(false) {
+        output = output.replace('\n', "\r\n");
+    }
 
-            i += 1;
-            continue;
-        }
+    let mut stdout = stdout();
+    let _ = stdout.write_all(output.as_bytes());
+    let _ = stdout.flush();
 
-        current_width += 1;
-        execute!(stdout, Print(c)).unwrap();
+    markup::measure(&nodes)
+}
 
-        i += 1;
-    }
+/// Renders the markup (see `printer`) without printing it, ex. for stderr.
+pub fn render(content: impl AsRef<str>) -> String {
+    render_nodes(&markup::parse(content.as_ref()))
+}
 
-    if current_width > size.cols {
-        size.cols = current_width;
-        size.rows += 1;
+fn render_nodes(nodes: &[markup::Node]) -> String {
+    match theme::colors_enabled() {
+        true => markup::render_ansi(nodes),
+        false => markup::render_plain(nodes),
     }
-
-    size
 }
 
 pub fn clear_line() {
@@ -264,12 +106,12 @@ pub fn clear_line() {
 
 pub fn no_subcommand_error() {
     let error_message = r#"
-        $b$cr `error`: no subcommand provided
+        $@error `error`: no subcommand provided
 
         $b$u `Usage`: $b `tgh` [COMMAND]
 
         For more information try $b `'tgh --help'`
     "#;
 
-    printer(error_message);
+    printer(unindent(error_message));
 }
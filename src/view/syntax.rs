@@ -0,0 +1,203 @@
+use std::ops::Range;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use syntect::parsing::{ParseState, Scope, ScopeStack, SyntaxSet};
+
+use super::markup::escape;
+
+static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+static COLORS: OnceLock<Vec<(Scope, &'static str)>> = OnceLock::new();
+
+/// The markup effect of the scopes, the most specific scopes first.
+/// The basic colors are used, so the highlighting follows the terminal theme (and `NO_COLOR`).
+const SCOPE_COLORS: [(&str, &str); 12] = [
+    ("comment", "s"),
+    ("string", "cg"),
+    ("constant.character.escape", "cc"),
+    ("constant", "cy"),
+    ("keyword.operator", "cw"),
+    ("keyword", "cm"),
+    ("storage", "cm"),
+    ("entity.name.function", "cb"),
+    ("support.function", "cb"),
+    ("entity.name", "cc"),
+    ("support.type", "cc"),
+    ("variable.language", "cr"),
+];
+
+fn syntaxes() -> &'static SyntaxSet {
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn colors() -> &'static [(Scope, &'static str)] {
+    COLORS.get_or_init(|| {
+        SCOPE_COLORS
+            .iter()
+            .filter_map(|(scope, effect)| Some((Scope::new(scope).ok()?, *effect)))
+            .collect()
+    })
+}
+
+/// The effect of the innermost scope which has one.
+fn effect(stack: &ScopeStack) -> Option<&'static str> {
+    stack.as_slice().iter().rev().find_map(|scope| {
+        colors()
+            .iter()
+            .find(|(prefix, _)| prefix.is_prefix_of(*scope))
+            .map(|(_, effect)| *effect)
+    })
+}
+
+/// Highlights the lines of a file one after the other, the state is kept between the lines
+/// (ex. in a block comment).
+pub struct Highlighter {
+    /// `None` when the language of the file isn't known, or when it failed to parse
+    state: Option<ParseState>,
+    stack: ScopeStack,
+}
+
+impl Highlighter {
+    /// A highlighter for the language of the file, guessed from its name.
+    pub fn for_path(path: &str) -> Self {
+        let path = Path::new(path);
+        let syntaxes = syntaxes();
+
+        let syntax = path
+            .extension()
+            .and_then(|extension| syntaxes.find_syntax_by_extension(&extension.to_string_lossy()))
+            .or_else(|| {
+                let name = path.file_name()?.to_string_lossy();
+                syntaxes.find_syntax_by_extension(&name)
+            });
+
+        Self {
+            state: syntax.map(ParseState::new),
+            stack: ScopeStack::new(),
+        }
+    }
+
+    /// Returns the byte ranges of the line with the effect of their scope.
+    /// The ranges without an effect are left out.
+    pub fn line(&mut self, text: &str) -> Vec<(Range<usize>, &'static str)> {
+        let state = match &mut self.state {
+            Some(state) => state,
+            None => return Vec::new(),
+        };
+
+        let line = format!("{}\n", text);
+        let operations = match state.parse_line(&line, syntaxes()) {
+            Ok(operations) => operations,
+            Err(_) => {
+                self.state = None;
+                return Vec::new();
+            }
+        };
+
+        let mut ranges: Vec<(Range<usize>, &'static str)> = Vec::new();
+        let mut start = 0;
+        let mut push = |range: Range<usize>, stack: &ScopeStack| {
+            let effect = match effect(stack) {
+                Some(effect) => effect,
+                None => return,
+            };
+            // The ranges of the same effect are merged (ex. `//` and the text of a comment)
+            match ranges.last_mut() {
+                Some((last, last_effect)) if last.end == range.start && *last_effect == effect => {
+                    last.end = range.end
+                }
+                _ => ranges.push((range, effect)),
+            }
+        };
+
+        for (index, operation) in operations {
+            let index = index.min(text.len());
+            if index > start {
+                push(start..index, &self.stack);
+                start = index;
+            }
+
+            if self.stack.apply(&operation).is_err() {
+                self.state = None;
+                return Vec::new();
+            }
+        }
+
+        if start < text.len() {
+            push(start..text.len(), &self.stack);
+        }
+
+        ranges
+    }
+
+    /// Returns the markup of the highlighted line.
+    pub fn markup(&mut self, text: &str) -> String {
+        let mut markup = String::new();
+        let mut end = 0;
+
+        for (range, effect) in self.line(text) {
+            markup.push_str(&escape(&text[end..range.start]));
+            markup.push_str(&format!("${} `{}`", effect, escape(&text[range.clone()])));
+            end = range.end;
+        }
+
+        markup + &escape(&text[end..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans<'a>(
+        text: &'a str,
+        ranges: &[(Range<usize>, &'static str)],
+    ) -> Vec<(&'a str, &'static str)> {
+        ranges
+            .iter()
+            .map(|(range, effect)| (&text[range.clone()], *effect))
+            .collect()
+    }
+
+    #[test]
+    fn test_highlight() {
+        let mut highlighter = Highlighter::for_path("src/main.rs");
+
+        let line = "let name = \"crate\"; // 1";
+        let ranges = highlighter.line(line);
+        let spans = spans(line, &ranges);
+
+        assert!(spans.contains(&("let", "cm")));
+        assert!(spans
+            .iter()
+            .any(|(text, effect)| text.contains("crate") && *effect == "cg"));
+        assert!(spans
+            .iter()
+            .any(|(text, effect)| text.contains("// 1") && *effect == "s"));
+    }
+
+    #[test]
+    fn test_highlight_state() {
+        // The second line is inside the block comment opened by the first one
+        let mut highlighter = Highlighter::for_path("lib.c");
+        highlighter.line("/* start");
+
+        let line = "int end */";
+        assert_eq!(
+            spans(line, &highlighter.line(line)),
+            vec![("int end */", "s")]
+        );
+    }
+
+    #[test]
+    fn test_markup() {
+        let mut highlighter = Highlighter::for_path("main.py");
+        assert_eq!(highlighter.markup("x = 1 # $"), "x $cw `=` $cy `1` $s `# \\$`");
+    }
+
+    #[test]
+    fn test_unknown_language() {
+        let mut highlighter = Highlighter::for_path("notes.unknown-extension");
+        assert!(highlighter.line("let x = 1;").is_empty());
+    }
+}
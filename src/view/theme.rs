@@ -0,0 +1,351 @@
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use crossterm::style::{Attribute, Color, ContentStyle};
+
+use crate::config::defines::COLOR;
+
+/// The semantic roles of the styled output.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Role {
+    Error,
+    Success,
+    Warning,
+    Hash,
+    Author,
+    Date,
+    Prompt,
+    Highlight,
+}
+
+impl Role {
+    pub const ALL: [Role; 8] = [
+        Role::Error,
+        Role::Success,
+        Role::Warning,
+        Role::Hash,
+        Role::Author,
+        Role::Date,
+        Role::Prompt,
+        Role::Highlight,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Role::Error => "error",
+            Role::Success => "success",
+            Role::Warning => "warning",
+            Role::Hash => "hash",
+            Role::Author => "author",
+            Role::Date => "date",
+            Role::Prompt => "prompt",
+            Role::Highlight => "highlight",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Role> {
+        Role::ALL.into_iter().find(|role| role.name() == name)
+    }
+}
+
+pub struct Theme {
+    styles: Vec<(Role, ContentStyle)>,
+}
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+static COLORS_ENABLED: OnceLock<bool> = OnceLock::new();
+
+/// Converts the configured accent color.
+fn accent_color(color: COLOR) -> Option<Color> {
+    match color {
+        COLOR::NORMAL => None,
+        COLOR::RED => Some(Color::Red),
+        COLOR::GREEN => Some(Color::Green),
+        COLOR::YELLOW => Some(Color::Yellow),
+        COLOR::BLUE => Some(Color::Blue),
+        COLOR::MAGENTA => Some(Color::Magenta),
+        COLOR::CYAN => Some(Color::Cyan),
+        COLOR::WHITE => Some(Color::White),
+        COLOR::GRAY => Some(Color::Grey),
+    }
+}
+
+fn style(color: Option<Color>, attributes: &[Attribute]) -> ContentStyle {
+    let mut style = ContentStyle::new();
+    style.foreground_color = color;
+
+    for attribute in attributes {
+        style.attributes.set(*attribute);
+    }
+
+    style
+}
+
+impl Theme {
+    /// Builds the theme from the accent color and the `theme.<role>` overrides.
+    pub fn new(color: COLOR, overrides: &BTreeMap<String, String>) -> Theme {
+        let accent = accent_color(color);
+
+        let styles = Role::ALL
+            .into_iter()
+            .map(|role| {
+                let default = match role {
+                    Role::Error => style(Some(Color::Red), &[Attribute::Bold]),
+                    Role::Success => style(Some(Color::Green), &[Attribute::Bold]),
+                    Role::Warning => style(Some(Color::Yellow), &[Attribute::Bold]),
+                    Role::Hash => style(Some(Color::Yellow), &[]),
+                    Role::Author => style(Some(Color::Blue), &[]),
+                    Role::Date => style(Some(Color::Green), &[]),
+                    Role::Prompt => style(accent.or(Some(Color::Green)), &[Attribute::Bold]),
+                    Role::Highlight => style(accent.or(Some(Color::Cyan)), &[Attribute::Italic]),
+                };
+
+                let style = overrides
+                    .get(role.name())
+                    .and_then(|spec| parse_style(spec).ok())
+                    .unwrap_or(default);
+
+                (role, style)
+            })
+            .collect();
+
+        Theme { styles }
+    }
+
+    pub fn style(&self, role: Role) -> ContentStyle {
+        let style = self
+            .styles
+            .iter()
+            .find(|(name, _)| *name == role)
+            .map(|(_, style)| *style)
+            .unwrap_or_default();
+
+        ContentStyle {
+            foreground_color: style.foreground_color.map(downgrade),
+            ..style
+        }
+    }
+}
+
+/// Sets the theme from the resolved config.
+/// An invalid or missing config falls back to the default theme (the error is reported later).
+pub fn init() {
+    let theme = match crate::config::layers::resolve() {
+        Ok(layered) => Theme::new(layered.config.color, &layered.config.theme),
+        Err(_) => Theme::new(COLOR::NORMAL, &BTreeMap::new()),
+    };
+
+    let _ = THEME.set(theme);
+}
+
+pub fn get() -> &'static Theme {
+    THEME.get_or_init(|| Theme::new(COLOR::NORMAL, &BTreeMap::new()))
+}
+
+/// Checks if the output should be styled.
+/// `NO_COLOR` disables it, `CLICOLOR_FORCE` enables it even when the output isn't a terminal.
+pub fn colors_enabled() -> bool {
+    *COLORS_ENABLED.get_or_init(|| {
+        use std::io::IsTerminal;
+
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+
+        resolve_colors_enabled(
+            var("NO_COLOR").is_some(),
+            var("CLICOLOR_FORCE").filter(|value| value != "0").is_some(),
+            std::io::stdout().is_terminal(),
+        )
+    })
+}
+
+fn resolve_colors_enabled(no_color: bool, force: bool, is_terminal: bool) -> bool {
+    if no_color {
+        return false;
+    }
+
+    force || is_terminal
+}
+
+fn truecolor_supported() -> bool {
+    matches!(
+        std::env::var("COLORTERM").as_deref(),
+        Ok("truecolor") | Ok("24bit")
+    )
+}
+
+/// Replaces truecolor values with the closest 256 color value, if the terminal doesn't support them.
+fn downgrade(color: Color) -> Color {
+    match color {
+        Color::Rgb { r, g, b } if !truecolor_supported() => {
+            Color::AnsiValue(rgb_to_ansi256(r, g, b))
+        }
+        color => color,
+    }
+}
+
+/// Maps a color to the 6x6x6 cube or the grayscale ramp of the 256 color palette.
+pub fn rgb_to_ansi256(r: u8, g: u8, b: u8) -> u8 {
+    if r == g && g == b {
+        return match r {
+            0..=7 => 16,
+            248..=255 => 231,
+            _ => 232 + ((r as u16 - 8) * 24 / 247) as u8,
+        };
+    }
+
+    // The cube levels are 0, 95, 135, 175, 215 and 255
+    let level = |value: u8| match value {
+        0..=47 => 0,
+        48..=114 => 1,
+        _ => (value - 35) / 40,
+    };
+
+    16 + 36 * level(r) + 6 * level(g) + level(b)
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+    if let Some(hex) = value.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+
+        let channel = |index: usize| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok();
+
+        return Some(Color::Rgb {
+            r: channel(0)?,
+            g: channel(2)?,
+            b: channel(4)?,
+        });
+    }
+
+    if let Ok(value) = value.parse::<u8>() {
+        return Some(Color::AnsiValue(value));
+    }
+
+    let color = match value {
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "white" => Color::White,
+        "gray" | "grey" => Color::Grey,
+        _ => return None,
+    };
+
+    Some(color)
+}
+
+/// Parses a style, ex. `bold red`, `italic 208` (256 colors) or `underline #ff8800` (truecolor).
+pub fn parse_style(spec: &str) -> Result<ContentStyle, String> {
+    let mut style = ContentStyle::new();
+
+    for word in spec.split_whitespace() {
+        let word = word.to_lowercase();
+
+        match word.as_str() {
+            "bold" => style.attributes.set(Attribute::Bold),
+            "dim" => style.attributes.set(Attribute::Dim),
+            "italic" => style.attributes.set(Attribute::Italic),
+            "underline" => style.attributes.set(Attribute::Underlined),
+            "normal" | "none" => {}
+            _ => {
+                style.foreground_color = Some(parse_color(&word).ok_or(format!(
+                    "Invalid style `{}`, expected attributes (bold, dim, italic, underline) and a color (ex. red, 208 or #ff8800)",
+                    spec
+                ))?)
+            }
+        }
+    }
+
+    Ok(style)
+}
+
+/// Styles the text with the role, or returns it as it is if the output isn't styled.
+pub fn paint(role: Role, text: &str) -> String {
+    match colors_enabled() {
+        true => get().style(role).apply(text).to_string(),
+        false => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_style() {
+        let style = parse_style("bold red").unwrap();
+        assert_eq!(style.foreground_color, Some(Color::Red));
+        assert!(style.attributes.has(Attribute::Bold));
+
+        let style = parse_style("208").unwrap();
+        assert_eq!(style.foreground_color, Some(Color::AnsiValue(208)));
+
+        let style = parse_style("Italic #FF8800").unwrap();
+        assert_eq!(
+            style.foreground_color,
+            Some(Color::Rgb {
+                r: 255,
+                g: 136,
+                b: 0
+            })
+        );
+        assert!(style.attributes.has(Attribute::Italic));
+
+        assert!(parse_style("blink").is_err());
+        assert!(parse_style("#ff88").is_err());
+        assert!(parse_style("256").is_err());
+    }
+
+    #[test]
+    fn test_rgb_to_ansi256() {
+        assert_eq!(rgb_to_ansi256(0, 0, 0), 16);
+        assert_eq!(rgb_to_ansi256(255, 255, 255), 231);
+        assert_eq!(rgb_to_ansi256(255, 0, 0), 196);
+        assert_eq!(rgb_to_ansi256(0, 255, 0), 46);
+        assert_eq!(rgb_to_ansi256(255, 136, 0), 208);
+        assert_eq!(rgb_to_ansi256(128, 128, 128), 243);
+    }
+
+    #[test]
+    fn test_theme() {
+        let mut overrides = BTreeMap::new();
+        overrides.insert("hash".to_string(), "bold 214".to_string());
+        overrides.insert("date".to_string(), "not a style".to_string());
+
+        let theme = Theme::new(COLOR::MAGENTA, &overrides);
+
+        assert_eq!(
+            theme.style(Role::Hash).foreground_color,
+            Some(Color::AnsiValue(214))
+        );
+        // Invalid overrides fall back to the default style
+        assert_eq!(theme.style(Role::Date).foreground_color, Some(Color::Green));
+        // The accent color is used for prompts and highlights
+        assert_eq!(
+            theme.style(Role::Prompt).foreground_color,
+            Some(Color::Magenta)
+        );
+        assert_eq!(
+            theme.style(Role::Highlight).foreground_color,
+            Some(Color::Magenta)
+        );
+
+        let theme = Theme::new(COLOR::NORMAL, &BTreeMap::new());
+        assert_eq!(
+            theme.style(Role::Highlight).foreground_color,
+            Some(Color::Cyan)
+        );
+    }
+
+    #[test]
+    fn test_colors_enabled() {
+        assert!(resolve_colors_enabled(false, false, true));
+        assert!(!resolve_colors_enabled(false, false, false));
+        assert!(resolve_colors_enabled(false, true, false));
+        assert!(!resolve_colors_enabled(true, true, true));
+    }
+}
@@ -0,0 +1,816 @@
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    terminal::{
+        self, disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
+};
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use std::io::{self, Write};
+
+use super::input::ReturnType;
+use super::markup::{self, Node};
+use super::theme;
+
+/// From this width the detail pane is shown next to the list, below it otherwise.
+const SIDE_BY_SIDE_WIDTH: u16 = 100;
+/// Below this height there's no room for a detail pane under the list.
+const STACKED_HEIGHT: u16 = 16;
+
+/// A rectangle of the terminal, in columns and rows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+/// Where the parts of a list view are drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Areas {
+    pub title: Rect,
+    pub list: Rect,
+    pub detail: Option<Rect>,
+    pub status: Rect,
+}
+
+/// Splits the terminal into a title row, the list, the detail pane and a status bar.
+/// Wide terminals get the detail pane on the right (after a 1 column border),
+/// tall ones below the list (after a 1 row border), and small ones don't get it at all.
+pub fn split(width: u16, height: u16, with_detail: bool) -> Areas {
+    let body = height.saturating_sub(2);
+    let title = Rect {
+        x: 0,
+        y: 0,
+        width,
+        height: height.min(1),
+    };
+    let status = Rect {
+        x: 0,
+        y: height.saturating_sub(1),
+        width,
+        height: height.saturating_sub(1).min(1),
+    };
+    let mut list = Rect {
+        x: 0,
+        y: 1,
+        width,
+        height: body,
+    };
+
+    if !with_detail {
+        return Areas {
+            title,
+            list,
+            detail: None,
+            status,
+        };
+    }
+
+    let detail = if width >= SIDE_BY_SIDE_WIDTH {
+        list.width = width / 2;
+        Some(Rect {
+            x: list.width + 1,
+            y: 1,
+            width: width - list.width - 1,
+            height: body,
+        })
+    } else if height >= STACKED_HEIGHT {
+        list.height = body / 2;
+        Some(Rect {
+            x: 0,
+            y: list.height + 2,
+            width,
+            height: body - list.height - 1,
+        })
+    } else {
+        None
+    };
+
+    Areas {
+        title,
+        list,
+        detail,
+        status,
+    }
+}
+
+/// The selected item and the scroll position of a list.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ListState {
+    pub selected: usize,
+    /// The index of the first shown item
+    pub offset: usize,
+}
+
+impl ListState {
+    /// Moves the selection by the number of items, staying in the list.
+    pub fn move_by(&mut self, delta: isize, len: usize) {
+        let selected = self.selected as isize + delta;
+        self.selected = selected.clamp(0, len.saturating_sub(1) as isize) as usize;
+    }
+
+    /// Scrolls the list so the selected item is shown in the height.
+    pub fn scroll_into_view(&mut self, height: usize, len: usize) {
+        let height = height.max(1);
+
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + height {
+            self.offset = self.selected + 1 - height;
+        }
+
+        self.offset = self.offset.min(len.saturating_sub(height));
+    }
+}
+
+/// What a key does in a list view.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    First,
+    Last,
+    ToggleDetail,
+    DetailUp,
+    DetailDown,
+    /// Starts typing a search query, which filters the items
+    Search,
+    Select,
+    Quit,
+    /// An action handled by the caller, returned with the selected item
+    Custom(&'static str),
+}
+
+/// A key and what it does, the views have their own actions (a list view has `Action`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Binding<A = Action> {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+    /// The key name and the description in the status bar, hidden when empty
+    pub hint: (&'static str, &'static str),
+    pub action: A,
+}
+
+impl<A> Binding<A> {
+    pub fn new(code: KeyCode, action: A) -> Self {
+        Self {
+            code,
+            modifiers: KeyModifiers::NONE,
+            hint: ("", ""),
+            action,
+        }
+    }
+
+    pub fn modifiers(mut self, modifiers: KeyModifiers) -> Self {
+        self.modifiers = modifiers;
+        self
+    }
+
+    pub fn hint(mut self, key: &'static str, description: &'static str) -> Self {
+        self.hint = (key, description);
+        self
+    }
+
+    pub fn matches(&self, event: &KeyEvent) -> bool {
+        // Shift is part of the character for letters (ex. `G`)
+        let modifiers = match event.code {
+            KeyCode::Char(_) => event.modifiers.difference(KeyModifiers::SHIFT),
+            _ => event.modifiers,
+        };
+
+        self.code == event.code && self.modifiers == modifiers
+    }
+
+    /// The markup of the hint in the status bar, `None` when it's hidden.
+    pub fn hint_markup(&self) -> Option<String> {
+        let (key, description) = self.hint;
+
+        match key.is_empty() {
+            true => None,
+            false => Some(format!("$b `{}` {}", markup::escape(key), description)),
+        }
+    }
+}
+
+/// Returns the action of the first binding matching the key.
+pub fn action<A: Copy>(bindings: &[Binding<A>], event: &KeyEvent) -> Option<A> {
+    bindings
+        .iter()
+        .find(|binding| binding.matches(event))
+        .map(|binding| binding.action)
+}
+
+/// The bindings every list view has, the caller's bindings take precedence.
+pub fn default_bindings() -> Vec<Binding> {
+    vec![
+        Binding::new(KeyCode::Up, Action::Up).hint("↑↓", "move"),
+        Binding::new(KeyCode::Down, Action::Down),
+        Binding::new(KeyCode::Char('k'), Action::Up),
+        Binding::new(KeyCode::Char('j'), Action::Down),
+        Binding::new(KeyCode::PageUp, Action::PageUp),
+        Binding::new(KeyCode::PageDown, Action::PageDown),
+        Binding::new(KeyCode::Home, Action::First),
+        Binding::new(KeyCode::End, Action::Last),
+        Binding::new(KeyCode::Char('g'), Action::First),
+        Binding::new(KeyCode::Char('G'), Action::Last),
+        Binding::new(KeyCode::Enter, Action::Select).hint("Enter", "select"),
+        Binding::new(KeyCode::Tab, Action::ToggleDetail).hint("Tab", "details"),
+        Binding::new(KeyCode::Char('u'), Action::DetailUp).modifiers(KeyModifiers::CONTROL),
+        Binding::new(KeyCode::Char('d'), Action::DetailDown).modifiers(KeyModifiers::CONTROL),
+        Binding::new(KeyCode::Char('q'), Action::Quit).hint("q", "quit"),
+        Binding::new(KeyCode::Esc, Action::Quit),
+    ]
+}
+
+/// How a list view was left.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Selected(usize),
+    Custom(&'static str, usize),
+}
+
+/// The alternate screen in raw mode, the terminal is restored when it's dropped (also on a panic).
+pub struct Screen;
+
+impl Screen {
+    pub fn enter() -> io::Result<Self> {
+        enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, Hide)?;
+
+        Ok(Screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), Show, LeaveAlternateScreen);
+        let _ = disable_raw_mode();
+    }
+}
+
+/// Waits for a key, `None` is returned for the other events (ex. a resize, which is redrawn).
+/// Ctrl+C returns `ReturnType::Exit`.
+pub fn read_key() -> Result<Option<KeyEvent>, ReturnType> {
+    match event::read() {
+        Ok(Event::Key(key)) if key.kind == KeyEventKind::Release => Ok(None),
+        Ok(Event::Key(key))
+            if key.modifiers == KeyModifiers::CONTROL && key.code == KeyCode::Char('c') =>
+        {
+            Err(ReturnType::Exit)
+        }
+        Ok(Event::Key(key)) => Ok(Some(key)),
+        Ok(_) => Ok(None),
+        Err(_) => Err(ReturnType::Exit),
+    }
+}
+
+/// Draws the rows returned by `frame` for the terminal size.
+/// Every row is padded to the width, so the screen only has to be cleared when the size
+/// changes, `size` is the size of the last drawing.
+pub fn draw(
+    size: &mut (u16, u16),
+    frame: impl FnOnce(u16, u16) -> Vec<Vec<Node>>,
+) -> io::Result<()> {
+    let (width, height) = terminal::size().unwrap_or((80, 24));
+    let frame = frame(width, height);
+    let mut stdout = io::stdout();
+
+    if *size != (width, height) {
+        *size = (width, height);
+        queue!(stdout, Clear(ClearType::All))?;
+    }
+
+    for (y, row) in frame.iter().enumerate() {
+        let line = match theme::colors_enabled() {
+            true => markup::render_ansi(row),
+            false => markup::render_plain(row),
+        };
+
+        queue!(stdout, MoveTo(0, y as u16))?;
+        stdout.write_all(line.as_bytes())?;
+    }
+
+    stdout.flush()
+}
+
+/// Parses a line of markup and fits it in the width, cutting it or padding it with spaces.
+pub fn fit(line: &str, width: usize) -> Vec<Node> {
+    let mut nodes = markup::truncate(&markup::parse(line), width);
+    let padding = width.saturating_sub(markup::measure(&nodes).cols);
+    nodes.push(Node::Text(" ".repeat(padding)));
+
+    nodes
+}
+
+type Row<'a, T> = Box<dyn Fn(&T, &str) -> String + 'a>;
+type Detail<'a, T> = Box<dyn Fn(&T) -> Vec<String> + 'a>;
+type SearchText<'a, T> = Box<dyn Fn(&T) -> String + 'a>;
+
+/// A full-screen list with a detail pane for the selected item.
+pub struct ListView<'a, T> {
+    title: String,
+    items: &'a [T],
+    row: Row<'a, T>,
+    detail: Option<Detail<'a, T>>,
+    search: Option<SearchText<'a, T>>,
+    bindings: Vec<Binding>,
+    /// The selection and the scroll position, in the shown items
+    state: ListState,
+    /// The indexes of the items matching the search query
+    shown: Vec<usize>,
+    query: String,
+    /// Whether the keys are typed into the query
+    searching: bool,
+    show_detail: bool,
+    detail_offset: usize,
+    /// The height of the list when it was last drawn, to move by a page
+    page: usize,
+    /// The terminal size when the view was last drawn, the screen is cleared when it changes
+    size: (u16, u16),
+    /// The detail lines of the selected item, as loading them can be slow (ex. `git show`)
+    detail_cache: Option<(usize, Vec<String>)>,
+}
+
+impl<'a, T> ListView<'a, T> {
+    /// Creates a view of the items, `row` returns the markup of an item.
+    /// It also gets the search query, to highlight the matches (see `input::highlight_matches`).
+    pub fn new(
+        title: impl Into<String>,
+        items: &'a [T],
+        row: impl Fn(&T, &str) -> String + 'a,
+    ) -> Self {
+        Self {
+            title: title.into(),
+            items,
+            row: Box::new(row),
+            detail: None,
+            search: None,
+            bindings: default_bindings(),
+            state: ListState::default(),
+            shown: (0..items.len()).collect(),
+            query: String::new(),
+            searching: false,
+            show_detail: true,
+            detail_offset: 0,
+            page: 1,
+            size: (0, 0),
+            detail_cache: None,
+        }
+    }
+
+    /// Shows the markup lines returned by `detail` for the selected item.
+    pub fn detail(mut self, detail: impl Fn(&T) -> Vec<String> + 'a) -> Self {
+        self.detail = Some(Box::new(detail));
+        self
+    }
+
+    /// Lets the user fuzzy search the items with `/`, matching the text returned by `search`.
+    pub fn searchable(mut self, search: impl Fn(&T) -> String + 'a) -> Self {
+        self.search = Some(Box::new(search));
+        self.bind(Binding::new(KeyCode::Char('/'), Action::Search).hint("/", "search"))
+    }
+
+    /// Selects the item at the index (ex. to keep the position when the items are reloaded).
+    pub fn select(mut self, index: usize) -> Self {
+        self.state.selected = index.min(self.items.len().saturating_sub(1));
+        self
+    }
+
+    /// Adds a key binding, it replaces a default binding of the same key.
+    pub fn bind(mut self, binding: Binding) -> Self {
+        self.bindings
+            .retain(|other| (other.code, other.modifiers) != (binding.code, binding.modifiers));
+        self.bindings.insert(0, binding);
+        self
+    }
+
+    fn action(&self, event: &KeyEvent) -> Option<Action> {
+        action(&self.bindings, event)
+    }
+
+    /// The index of the selected item, `None` when no item matches the search.
+    fn current(&self) -> Option<usize> {
+        self.shown.get(self.state.selected).copied()
+    }
+
+    /// Shows the items matching the query, keeping the selected item when it still matches.
+    fn filter(&mut self) {
+        let current = self.current();
+
+        self.shown = match &self.search {
+            Some(search) if !self.query.is_empty() => {
+                let matcher = SkimMatcherV2::default();
+
+                (0..self.items.len())
+                    .filter(|i| {
+                        matcher
+                            .fuzzy_match(&search(&self.items[*i]), &self.query)
+                            .is_some()
+                    })
+                    .collect()
+            }
+            _ => (0..self.items.len()).collect(),
+        };
+
+        self.state.selected = current
+            .and_then(|current| self.shown.iter().position(|i| *i == current))
+            .unwrap_or(0);
+    }
+
+    /// Edits the query with the key, returns `false` for the keys which aren't typed.
+    fn edit_query(&mut self, key: &KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Enter => self.searching = false,
+            KeyCode::Esc => {
+                self.searching = false;
+                self.query.clear();
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.query.push(c)
+            }
+            _ => return false,
+        }
+
+        self.filter();
+        true
+    }
+
+    fn detail_lines(&mut self) -> &[String] {
+        let current = self.current();
+
+        let stale = !matches!(&self.detail_cache, Some((index, _)) if Some(*index) == current);
+        if stale {
+            let lines = match (&self.detail, current) {
+                (Some(detail), Some(index)) => detail(&self.items[index]),
+                _ => Vec::new(),
+            };
+            self.detail_cache = current.map(|index| (index, lines));
+        }
+
+        match &self.detail_cache {
+            Some((_, lines)) => lines,
+            None => &[],
+        }
+    }
+
+    fn status_line(&self) -> String {
+        if self.searching {
+            return format!(
+                "$@prompt `/`{} $s `· Enter to keep the filter, Esc to clear it`",
+                markup::escape(&self.query)
+            );
+        }
+
+        let mut hints = Vec::new();
+        if !self.query.is_empty() {
+            hints.push(format!("$@highlight `/{}`", markup::escape(&self.query)));
+        }
+
+        for binding in &self.bindings {
+            if self.detail.is_none() && binding.action == Action::ToggleDetail {
+                continue;
+            }
+            hints.extend(binding.hint_markup());
+        }
+
+        format!(
+            "$s `{}/{}` {}",
+            (self.state.selected + 1).min(self.shown.len()),
+            self.shown.len(),
+            hints.join(" $s `·` ")
+        )
+    }
+
+    /// Builds every row of the screen, the list and the detail pane are joined side by side.
+    pub fn frame(&mut self, width: u16, height: u16) -> Vec<Vec<Node>> {
+        let areas = split(width, height, self.detail.is_some() && self.show_detail);
+        let list_height = areas.list.height as usize;
+        self.page = list_height.max(1);
+
+        self.state.scroll_into_view(list_height, self.shown.len());
+
+        let mut rows: Vec<Vec<Node>> = Vec::with_capacity(height as usize);
+        rows.push(fit(
+            &format!("$b `{}`", markup::escape(&self.title)),
+            width as usize,
+        ));
+
+        let list: Vec<Vec<Node>> = (0..list_height)
+            .map(|i| {
+                let index = self.state.offset + i;
+                let line = match self.shown.get(index) {
+                    Some(item) if index == self.state.selected => {
+                        format!("$cc `>` {}", (self.row)(&self.items[*item], &self.query))
+                    }
+                    Some(item) => format!("  {}", (self.row)(&self.items[*item], &self.query)),
+                    None if i == 0 => "  $s `No matching items`".into(),
+                    None => String::new(),
+                };
+
+                fit(&line, areas.list.width as usize)
+            })
+            .collect();
+
+        let detail: Vec<Vec<Node>> = match areas.detail {
+            Some(area) => {
+                let offset = self.detail_offset;
+                let lines = self.detail_lines();
+
+                (0..area.height as usize)
+                    .map(|i| {
+                        fit(
+                            lines.get(offset + i).map_or("", |line| line.as_str()),
+                            area.width as usize,
+                        )
+                    })
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+
+        match areas.detail {
+            Some(area) if area.x > 0 => {
+                for (i, mut row) in list.into_iter().enumerate() {
+                    row.push(Node::Text("│".into()));
+                    row.extend(detail[i].clone());
+                    rows.push(row);
+                }
+            }
+            Some(_) => {
+                rows.extend(list);
+                rows.push(vec![Node::Text("─".repeat(width as usize))]);
+                rows.extend(detail);
+            }
+            None => rows.extend(list),
+        }
+
+        if areas.status.height > 0 {
+            rows.push(fit(&self.status_line(), width as usize));
+        }
+
+        rows.truncate(height as usize);
+        rows
+    }
+
+    fn draw(&mut self) -> io::Result<()> {
+        let mut size = self.size;
+        draw(&mut size, |width, height| self.frame(width, height))?;
+        self.size = size;
+
+        Ok(())
+    }
+
+    /// Shows the view until an item is selected or a custom action is used.
+    /// The view can be run again, with the same selection (ex. after handling a custom action).
+    /// Quitting returns `ReturnType::Cancel`, and Ctrl+C `ReturnType::Exit`.
+    pub fn run(&mut self) -> Result<Outcome, ReturnType> {
+        if self.items.is_empty() {
+            return Err(ReturnType::Cancel);
+        }
+
+        let _screen = Screen::enter().map_err(|_| ReturnType::Exit)?;
+        // The screen is cleared when it's entered again (ex. after a custom action)
+        self.size = (0, 0);
+
+        loop {
+            self.draw().map_err(|_| ReturnType::Exit)?;
+
+            let key = match read_key()? {
+                Some(key) => key,
+                None => continue,
+            };
+
+            if self.searching && self.edit_query(&key) {
+                self.detail_offset = 0;
+                continue;
+            }
+
+            let page = self.page as isize;
+            let len = self.shown.len();
+            let previous = self.state.selected;
+
+            match self.action(&key) {
+                Some(Action::Up) => self.state.move_by(-1, len),
+                Some(Action::Down) => self.state.move_by(1, len),
+                Some(Action::PageUp) => self.state.move_by(-page, len),
+                Some(Action::PageDown) => self.state.move_by(page, len),
+                Some(Action::First) => self.state.move_by(-(len as isize), len),
+                Some(Action::Last) => self.state.move_by(len as isize, len),
+                Some(Action::ToggleDetail) => self.show_detail = !self.show_detail,
+                Some(Action::DetailUp) => {
+                    self.detail_offset = self.detail_offset.saturating_sub(page as usize / 2)
+                }
+                Some(Action::DetailDown) => {
+                    let last = self.detail_lines().len().saturating_sub(1);
+                    self.detail_offset = (self.detail_offset + page as usize / 2).min(last);
+                }
+                Some(Action::Search) => self.searching = true,
+                Some(Action::Select) => {
+                    if let Some(index) = self.current() {
+                        return Ok(Outcome::Selected(index));
+                    }
+                }
+                Some(Action::Custom(name)) => {
+                    if let Some(index) = self.current() {
+                        return Ok(Outcome::Custom(name, index));
+                    }
+                }
+                Some(Action::Quit) => return Err(ReturnType::Cancel),
+                None => {}
+            }
+
+            if self.state.selected != previous {
+                self.detail_offset = 0;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split() {
+        let narrow = split(80, 10, true);
+        assert_eq!(
+            narrow.list,
+            Rect {
+                x: 0,
+                y: 1,
+                width: 80,
+                height: 8
+            }
+        );
+        assert_eq!(narrow.detail, None);
+        assert_eq!(narrow.status.y, 9);
+
+        let tall = split(80, 30, true);
+        assert_eq!(tall.list.height, 14);
+        assert_eq!(
+            tall.detail,
+            Some(Rect {
+                x: 0,
+                y: 16,
+                width: 80,
+                height: 13
+            })
+        );
+
+        let wide = split(120, 30, true);
+        assert_eq!(wide.list.width, 60);
+        assert_eq!(
+            wide.detail,
+            Some(Rect {
+                x: 61,
+                y: 1,
+                width: 59,
+                height: 28
+            })
+        );
+
+        // No room at all
+        assert_eq!(split(0, 0, true).list.height, 0);
+    }
+
+    #[test]
+    fn test_list_state() {
+        let mut state = ListState::default();
+
+        state.move_by(-1, 5);
+        assert_eq!(state.selected, 0);
+
+        state.move_by(10, 5);
+        state.scroll_into_view(3, 5);
+        assert_eq!(
+            state,
+            ListState {
+                selected: 4,
+                offset: 2
+            }
+        );
+
+        state.move_by(-3, 5);
+        state.scroll_into_view(3, 5);
+        assert_eq!(
+            state,
+            ListState {
+                selected: 1,
+                offset: 1
+            }
+        );
+
+        // The list got taller than the items
+        state.scroll_into_view(10, 5);
+        assert_eq!(state.offset, 0);
+    }
+
+    #[test]
+    fn test_bindings() {
+        let view = ListView::new("", &[1], |n: &i32, _| n.to_string())
+            .bind(Binding::new(KeyCode::Enter, Action::Custom("open")));
+
+        let key = |code, modifiers| KeyEvent::new(code, modifiers);
+
+        assert_eq!(
+            view.action(&key(KeyCode::Enter, KeyModifiers::NONE)),
+            Some(Action::Custom("open"))
+        );
+        assert_eq!(
+            view.action(&key(KeyCode::Char('G'), KeyModifiers::SHIFT)),
+            Some(Action::Last)
+        );
+        assert_eq!(
+            view.action(&key(KeyCode::Char('d'), KeyModifiers::CONTROL)),
+            Some(Action::DetailDown)
+        );
+        assert_eq!(
+            view.action(&key(KeyCode::Char('d'), KeyModifiers::NONE)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_frame() {
+        let items = ["first", "a very long second item"];
+        let mut view = ListView::new("Items", &items, |item: &&str, _| item.to_string())
+            .detail(|item| vec![format!("$b `{}`", item), "more".into()]);
+        view.state.selected = 1;
+
+        let rows: Vec<String> = view
+            .frame(40, 6)
+            .iter()
+            .map(|row| markup::render_plain(row))
+            .collect();
+
+        assert_eq!(
+            rows,
+            vec![
+                format!("{:40}", "Items"),
+                format!("{:40}", "  first"),
+                format!("{:40}", "> a very long second item"),
+                format!("{:40}", ""),
+                format!("{:40}", ""),
+                "2/2 ↑↓ move · Enter select · Tab detail…".into(),
+            ]
+        );
+
+        let rows: Vec<String> = view
+            .frame(100, 4)
+            .iter()
+            .map(|row| markup::render_plain(row))
+            .collect();
+
+        assert_eq!(
+            rows[1],
+            format!("{:50}│{:49}", "  first", "a very long second item")
+        );
+        assert_eq!(
+            rows[2],
+            format!("{:50}│{:49}", "> a very long second item", "more")
+        );
+    }
+
+    #[test]
+    fn test_search() {
+        let items = ["apple", "banana", "cherry", "avocado"];
+        let mut view = ListView::new("Fruit", &items, |item: &&str, _| item.to_string())
+            .searchable(|item| item.to_string());
+        view.state.selected = 3;
+
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+
+        assert_eq!(view.action(&key(KeyCode::Char('/'))), Some(Action::Search));
+        view.searching = true;
+
+        assert!(view.edit_query(&key(KeyCode::Char('a'))));
+        assert!(view.edit_query(&key(KeyCode::Char('o'))));
+        // The selected item still matches, so it stays selected
+        assert_eq!(view.shown, vec![3]);
+        assert_eq!(view.current(), Some(3));
+
+        assert!(!view.edit_query(&key(KeyCode::Down)));
+        assert!(view.edit_query(&key(KeyCode::Char('x'))));
+        assert_eq!(view.current(), None);
+
+        let rows: Vec<String> = view
+            .frame(30, 4)
+            .iter()
+            .map(|row| markup::render_plain(row).trim_end().to_string())
+            .collect();
+        assert_eq!(rows[1], "  No matching items");
+
+        assert!(view.edit_query(&key(KeyCode::Esc)));
+        assert_eq!(view.shown.len(), 4);
+        assert!(!view.searching);
+    }
+}